@@ -1,236 +1,239 @@
-#![feature(test)]
+//! Matrix multiplication benchmarks.
+//!
+//! Every shape of [`SHAPES`] runs through every backend compiled in for
+//! `f32`, and through the native kernels for `f16` and `i8`. Criterion
+//! reports the throughput in floating point operations (`2 * m * n * k` per
+//! matrix), so its `Gelem/s` read as GFLOP/s.
+//!
+//! At the end of the run the median timings are also written as JSON, in
+//! the `customBiggerIsBetter` format of `github-action-benchmark`, to
+//! `$GGBLAS_BENCH_JSON` (`target/ggblas-bench.json` by default): one GFLOP/s
+//! and one GB/s entry per benchmark, the bandwidth counting each of `A`, `B`
+//! and `C` once.
+//!
+//! ```bash
+//! cargo bench --features matrixmultiply
+//! cargo bench -- f32_t/ggml/gemv
+//! ```
+use criterion::{BenchmarkId, Criterion, Throughput};
+use ggblas::backend;
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-extern crate test;
-#[cfg(feature = "f16")]
-use half::f16;
-use test::{black_box, Bencher};
-
-#[cfg(feature = "f16")]
-use ggblas::f16::{batched_sgemm_t_f16_mixed, batched_sgemm_t_f16_pure};
-#[cfg(any(
-    feature = "intel-mkl",
-    feature = "cblas",
-    feature = "matrixmultiply",
-    feature = "faer-rs"
-))]
-use ggblas::tests::matmul;
-use ggblas::tests::Tensor;
-use ggblas::{batched_sgemm, batched_sgemm_t};
-
-const M: usize = 1;
-const N: usize = 4096 * 3;
-const K: usize = 4096;
-
-#[bench]
-#[cfg(feature = "intel-mkl")]
-fn bench_mkl_n(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![K, N],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<false>(&a, &b, &mut c)));
+#[derive(Clone, Copy)]
+struct Shape {
+    kind: &'static str,
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
 }
 
-#[bench]
-#[cfg(feature = "intel-mkl")]
-fn bench_mkl_t(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![N, K],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<true>(&a, &b, &mut c)));
+const fn shape(kind: &'static str, batch: usize, m: usize, n: usize, k: usize) -> Shape {
+    Shape {
+        kind,
+        batch,
+        m,
+        n,
+        k,
+    }
 }
 
-#[bench]
-#[cfg(feature = "faer-rs")]
-fn bench_faer_rs_n(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![K, N],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<false>(&a, &b, &mut c)));
-}
+const SHAPES: &[Shape] = &[
+    shape("square", 1, 128, 128, 128),
+    shape("square", 1, 512, 512, 512),
+    shape("square", 1, 1024, 1024, 1024),
+    shape("tall", 1, 4096, 64, 1024),
+    shape("long_k", 1, 64, 64, 65536),
+    shape("gemv", 1, 1, 4096 * 3, 4096),
+    shape("gemv", 1, 1, 4096, 4096),
+    shape("batched", 16, 128, 128, 128),
+    shape("batched", 64, 64, 64, 64),
+];
 
-#[bench]
-#[cfg(feature = "faer-rs")]
-fn bench_faer_rs_t(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![N, K],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<true>(&a, &b, &mut c)));
-}
+impl Shape {
+    fn id(&self) -> String {
+        let Shape {
+            kind,
+            batch,
+            m,
+            n,
+            k,
+        } = self;
+        format!("{kind}/{batch}x{m}x{n}x{k}")
+    }
 
-#[bench]
-#[cfg(feature = "cblas")]
-fn bench_blas_n(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![K, N],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<false>(&a, &b, &mut c)));
-}
+    fn flops(&self) -> u64 {
+        (2 * self.batch * self.m * self.n * self.k) as u64
+    }
 
-#[bench]
-#[cfg(feature = "cblas")]
-fn bench_blas_t(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![N, K],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<true>(&a, &b, &mut c)));
+    /// Bytes of `A`, `B` and `C`, with `(a, b, c)` the element sizes.
+    fn bytes(&self, (a, b, c): (usize, usize, usize)) -> u64 {
+        let Shape { batch, m, n, k, .. } = *self;
+        (batch * (m * k * a + k * n * b + m * n * c)) as u64
+    }
+
+    fn data<T>(&self, len: usize, f: impl Fn(usize) -> T) -> Vec<T> {
+        (0..self.batch * len).map(f).collect()
+    }
 }
 
-#[bench]
-#[cfg(feature = "matrixmultiply")]
-fn bench_matrixmultiply_n(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![K, N],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<false>(&a, &b, &mut c)));
+struct Record {
+    flops: u64,
+    bytes: u64,
+    /// Seconds per iteration of every sample.
+    samples: Vec<f64>,
 }
 
-#[bench]
-#[cfg(feature = "matrixmultiply")]
-fn bench_matrixmultiply_t(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![N, K],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(matmul::<true>(&a, &b, &mut c)));
+static RECORDS: Mutex<BTreeMap<String, Record>> = Mutex::new(BTreeMap::new());
+
+/// Samples of every benchmark, set on each group so that `--sample-size`
+/// cannot make [`bench`] keep warm-up runs.
+const SAMPLE_SIZE: usize = 10;
+
+/// Benchmarks `f` as `group/function/shape`, recording its timings for the
+/// JSON report.
+///
+/// Criterion calls the routine during the warm-up too, then once per sample
+/// (twice in all with `--quick`): only those last calls are recorded.
+fn bench(
+    c: &mut Criterion,
+    group: &str,
+    function: &str,
+    shape: Shape,
+    sizes: (usize, usize, usize),
+    mut f: impl FnMut(),
+) {
+    let name = format!("{group}/{function}/{}", shape.id());
+    let mut samples = vec![];
+    let mut group = c.benchmark_group(group);
+    group.sample_size(SAMPLE_SIZE);
+    group.throughput(Throughput::Elements(shape.flops()));
+    group.bench_function(BenchmarkId::new(function, shape.id()), |bencher| {
+        bencher.iter_custom(|iters| {
+            let start = Instant::now();
+            for _ in 0..iters {
+                f();
+            }
+            let elapsed = start.elapsed();
+            samples.push(elapsed.as_secs_f64() / iters as f64);
+            elapsed
+        })
+    });
+    group.finish();
+
+    let measured = if std::env::args().any(|arg| arg == "--quick") {
+        2
+    } else {
+        SAMPLE_SIZE
+    };
+    if !samples.is_empty() {
+        let samples = samples.split_off(samples.len().saturating_sub(measured));
+        let record = Record {
+            flops: shape.flops(),
+            bytes: shape.bytes(sizes),
+            samples,
+        };
+        RECORDS.lock().unwrap().insert(name, record);
+    }
 }
 
-#[bench]
-fn bench_ggblas_t_f32(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![N, K],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(batched_sgemm_t(a.data(), b.data(), c.data_mut(), M, N, K)));
+fn f32_backends(c: &mut Criterion) {
+    for backend in backend::available() {
+        for &shape in SHAPES {
+            let Shape { m, n, k, .. } = shape;
+            let a = shape.data(m * k, |i| (i % 7) as f32 - 3.0);
+            let b = shape.data(k * n, |i| (i % 5) as f32 - 2.0);
+            let mut out = shape.data(m * n, |_| 0.0f32);
+            let sizes = (4, 4, 4);
+            bench(c, "f32_n", backend.name(), shape, sizes, || {
+                backend.batched_sgemm(black_box(&a), black_box(&b), &mut out, m, n, k)
+            });
+            bench(c, "f32_t", backend.name(), shape, sizes, || {
+                backend.batched_sgemm_t(black_box(&a), black_box(&b), &mut out, m, n, k)
+            });
+        }
+    }
 }
 
 #[cfg(feature = "f16")]
-#[bench]
-fn bench_ggblas_t_f16_mixed(bench: &mut Bencher) {
-    let a_data = vec![0.0; M * K];
-    let b_data = vec![f16::from_f32(0.0); N * K];
-    let mut c_data = vec![0.0; M * N];
-    bench.iter(|| {
-        black_box(batched_sgemm_t_f16_mixed(
-            &a_data,
-            &b_data,
-            &mut c_data,
-            M,
-            N,
-            K,
-        ))
-    });
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+fn f16(c: &mut Criterion) {
+    use ggblas::f16::{batched_sgemm_t_f16_mixed, batched_sgemm_t_f16_pure};
+    use half::f16;
+    for &shape in SHAPES {
+        let Shape { m, n, k, .. } = shape;
+        let a = shape.data(m * k, |i| (i % 7) as f32 - 3.0);
+        let a16 = shape.data(m * k, |i| f16::from_f32((i % 7) as f32 - 3.0));
+        let b16 = shape.data(k * n, |i| f16::from_f32((i % 5) as f32 - 2.0));
+        let mut out = shape.data(m * n, |_| 0.0f32);
+        let mut out16 = shape.data(m * n, |_| f16::ZERO);
+        bench(c, "f16_mixed_t", "ggml", shape, (4, 2, 4), || {
+            batched_sgemm_t_f16_mixed(black_box(&a), black_box(&b16), &mut out, m, n, k)
+        });
+        bench(c, "f16_pure_t", "ggml", shape, (2, 2, 2), || {
+            batched_sgemm_t_f16_pure(black_box(&a16), black_box(&b16), &mut out16, m, n, k)
+        });
+    }
 }
 
-#[cfg(feature = "f16")]
-#[bench]
-fn bench_ggblas_t_f16_pure(bench: &mut Bencher) {
-    let a_data = vec![f16::from_f32(0.0); M * K];
-    let b_data = vec![f16::from_f32(0.0); N * K];
-    let mut c_data = vec![f16::from_f32(0.0); M * N];
-    bench.iter(|| {
-        black_box(batched_sgemm_t_f16_pure(
-            &a_data,
-            &b_data,
-            &mut c_data,
-            M,
-            N,
-            K,
-        ))
+fn int8(c: &mut Criterion) {
+    use ggblas::int8::{gemm_i8, gemm_i8_t};
+    for &shape in SHAPES {
+        let Shape { m, n, k, .. } = shape;
+        let a = shape.data(m * k, |i| (i % 7) as i8 - 3);
+        let b = shape.data(k * n, |i| (i % 5) as i8 - 2);
+        let mut out = shape.data(m * n, |_| 0i32);
+        let sizes = (1, 1, 4);
+        bench(c, "i8_n", "ggml", shape, sizes, || {
+            gemm_i8(black_box(&a), black_box(&b), &mut out, m, n, k)
+        });
+        bench(c, "i8_t", "ggml", shape, sizes, || {
+            gemm_i8_t(black_box(&a), black_box(&b), &mut out, m, n, k)
+        });
+    }
+}
+
+fn write_json() {
+    let path = std::env::var("GGBLAS_BENCH_JSON").unwrap_or_else(|_| {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/target/ggblas-bench.json").into()
     });
+    let records = RECORDS.lock().unwrap();
+    if records.is_empty() {
+        return;
+    }
+    let mut entries = vec![];
+    for (name, record) in records.iter() {
+        let mut samples = record.samples.clone();
+        samples.sort_by(f64::total_cmp);
+        let time = samples[samples.len() / 2];
+        let gflops = record.flops as f64 / time / 1e9;
+        let gbytes = record.bytes as f64 / time / 1e9;
+        entries.push(format!(
+            "  {{\"name\": \"{name}\", \"unit\": \"GFLOP/s\", \"value\": {gflops:.3}}}"
+        ));
+        entries.push(format!(
+            "  {{\"name\": \"{name} bandwidth\", \"unit\": \"GB/s\", \"value\": {gbytes:.3}}}"
+        ));
+    }
+    let json = format!("[\n{}\n]\n", entries.join(",\n"));
+    if let Err(err) = std::fs::write(&path, json) {
+        eprintln!("Could not write {path}: {err}");
+    }
 }
 
-#[bench]
-fn bench_ggblas_n(bench: &mut Bencher) {
-    let a = Tensor {
-        shape: vec![M, K],
-        data: vec![0.0; M * K],
-    };
-    let b = Tensor {
-        shape: vec![K, N],
-        data: vec![0.0; N * K],
-    };
-    let mut c = Tensor {
-        shape: vec![M, N],
-        data: vec![0.0; M * N],
-    };
-    bench.iter(|| black_box(batched_sgemm(a.data(), b.data(), c.data_mut(), M, N, K)));
+fn main() {
+    let mut c = Criterion::default()
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(3))
+        .configure_from_args();
+    f32_backends(&mut c);
+    #[cfg(feature = "f16")]
+    #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+    f16(&mut c);
+    int8(&mut c);
+    c.final_summary();
+    write_json();
 }
@@ -0,0 +1,90 @@
+/*
+ * CBLAS compatible interface of ggblas.
+ *
+ * Build the shared library with
+ * `cargo rustc --release --lib --crate-type cdylib --features capi`
+ * and link against `libggblas`. The declarations follow the reference CBLAS
+ * (and MKL for the batched GEMMs), so this header can stand in for
+ * `cblas.h` for the routines below.
+ */
+#ifndef GGBLAS_H
+#define GGBLAS_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#ifndef CBLAS_INDEX
+#define CBLAS_INDEX size_t
+#endif
+
+typedef enum CBLAS_LAYOUT { CblasRowMajor = 101, CblasColMajor = 102 } CBLAS_LAYOUT;
+typedef enum CBLAS_TRANSPOSE {
+    CblasNoTrans = 111,
+    CblasTrans = 112,
+    CblasConjTrans = 113
+} CBLAS_TRANSPOSE;
+typedef enum CBLAS_UPLO { CblasUpper = 121, CblasLower = 122 } CBLAS_UPLO;
+typedef enum CBLAS_DIAG { CblasNonUnit = 131, CblasUnit = 132 } CBLAS_DIAG;
+typedef enum CBLAS_SIDE { CblasLeft = 141, CblasRight = 142 } CBLAS_SIDE;
+typedef CBLAS_LAYOUT CBLAS_ORDER;
+
+/* Level 1 */
+float cblas_sdot(const int N, const float *X, const int incX, const float *Y, const int incY);
+void cblas_saxpy(const int N, const float alpha, const float *X, const int incX, float *Y,
+                 const int incY);
+void cblas_sscal(const int N, const float alpha, float *X, const int incX);
+float cblas_snrm2(const int N, const float *X, const int incX);
+float cblas_sasum(const int N, const float *X, const int incX);
+CBLAS_INDEX cblas_isamax(const int N, const float *X, const int incX);
+void cblas_scopy(const int N, const float *X, const int incX, float *Y, const int incY);
+
+/* Level 2 */
+void cblas_sgemv(const CBLAS_LAYOUT layout, const CBLAS_TRANSPOSE TransA, const int M,
+                 const int N, const float alpha, const float *A, const int lda, const float *X,
+                 const int incX, const float beta, float *Y, const int incY);
+
+/* Level 3 */
+void cblas_sgemm(const CBLAS_LAYOUT layout, const CBLAS_TRANSPOSE TransA,
+                 const CBLAS_TRANSPOSE TransB, const int M, const int N, const int K,
+                 const float alpha, const float *A, const int lda, const float *B, const int ldb,
+                 const float beta, float *C, const int ldc);
+void cblas_strsm(const CBLAS_LAYOUT layout, const CBLAS_SIDE Side, const CBLAS_UPLO Uplo,
+                 const CBLAS_TRANSPOSE TransA, const CBLAS_DIAG Diag, const int M, const int N,
+                 const float alpha, const float *A, const int lda, float *B, const int ldb);
+
+/* Batched level 3 */
+void cblas_sgemm_batch(const CBLAS_LAYOUT layout, const CBLAS_TRANSPOSE *TransA_array,
+                       const CBLAS_TRANSPOSE *TransB_array, const int *M_array,
+                       const int *N_array, const int *K_array, const float *alpha_array,
+                       const float **A_array, const int *lda_array, const float **B_array,
+                       const int *ldb_array, const float *beta_array, float **C_array,
+                       const int *ldc_array, const int group_count, const int *group_size);
+void cblas_sgemm_batch_strided(const CBLAS_LAYOUT layout, const CBLAS_TRANSPOSE TransA,
+                               const CBLAS_TRANSPOSE TransB, const int M, const int N,
+                               const int K, const float alpha, const float *A, const int lda,
+                               const int stridea, const float *B, const int ldb,
+                               const int strideb, const float beta, float *C, const int ldc,
+                               const int stridec, const int batch_size);
+
+/*
+ * Fortran interface: column major, every argument passed by pointer. The
+ * hidden lengths of the character arguments are ignored and can be omitted.
+ */
+void sgemm_(const char *transa, const char *transb, const int *m, const int *n, const int *k,
+            const float *alpha, const float *a, const int *lda, const float *b, const int *ldb,
+            const float *beta, float *c, const int *ldc);
+void sgemv_(const char *trans, const int *m, const int *n, const float *alpha, const float *a,
+            const int *lda, const float *x, const int *incx, const float *beta, float *y,
+            const int *incy);
+void strsm_(const char *side, const char *uplo, const char *transa, const char *diag,
+            const int *m, const int *n, const float *alpha, const float *a, const int *lda,
+            float *b, const int *ldb);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GGBLAS_H */
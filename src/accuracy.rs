@@ -0,0 +1,401 @@
+//! Accumulation modes for long dot products.
+//!
+//! The default kernels sum in a handful of independent `f32` lanes, whose
+//! error grows linearly with `k`. For `k` in the hundred thousands, or sums
+//! that cancel, [`Accuracy`] selects a slower but more accurate reduction.
+//! The error bounds of each mode are listed in [`crate::ggml::accurate`].
+//!
+//! [`Accuracy::Fast`] runs the regular kernels, split along `k` for long
+//! reductions with few outputs. The other modes compute every output on a
+//! single thread, so the result never depends on the thread count. Apart
+//! from [`Accuracy::Fast`] and [`Accuracy::Pairwise`], which follow the SIMD
+//! width of the target, the modes evaluate in a fixed order and are
+//! reproducible bit for bit across machines.
+use crate::ggml::accurate::{
+    vec_dot_f32_comp, vec_dot_f32_det, vec_dot_f32_f64, vec_dot_f32_pairwise,
+};
+use crate::ggml::vec_dot_f32;
+use crate::raw::{ggml_compute_forward_mul_mat_t_dot, DotF32};
+use crate::{batching, get_pool, size};
+
+/// How the dot products of a GEMM get accumulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Accuracy {
+    /// Independent `f32` lanes, the regular kernels.
+    #[default]
+    Fast,
+    /// Blocks of the regular kernel added pairwise, the error only grows
+    /// logarithmically past the block size. Nearly as fast as [`Accuracy::Fast`].
+    Pairwise,
+    /// Compensated summation, as accurate as twice the working precision.
+    Compensated,
+    /// Accumulation in `f64`, rounded once at the end.
+    F64,
+    /// A fixed number of `f32` accumulators whatever the SIMD width, for
+    /// results that are bitwise identical on every machine and thread count.
+    /// About as accurate as [`Accuracy::Fast`].
+    Deterministic,
+}
+
+impl Accuracy {
+    fn dot(self) -> DotF32 {
+        match self {
+            Accuracy::Fast => vec_dot_f32,
+            Accuracy::Pairwise => vec_dot_f32_pairwise,
+            Accuracy::Compensated => vec_dot_f32_comp,
+            Accuracy::F64 => vec_dot_f32_f64,
+            Accuracy::Deterministic => vec_dot_f32_det,
+        }
+    }
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B.T
+/// ```
+///
+/// like [`crate::batched_sgemm_t`] with the dot products accumulated
+/// according to `accuracy`.
+///
+/// ```
+/// use ggblas::{batched_sgemm_t_accuracy, Accuracy};
+///
+/// // 1 + 1e-8 - 1 where f32 can't represent 1 + 1e-8.
+/// let a = vec![1., 1e-8, -1.];
+/// let b = vec![1., 1., 1.];
+/// let mut c = vec![0.];
+///
+/// batched_sgemm_t_accuracy(Accuracy::Compensated, &a, &b, &mut c, 1, 1, 3);
+/// assert_eq!(c, &[1e-8]);
+/// ```
+pub fn batched_sgemm_t_accuracy(
+    accuracy: Accuracy,
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+) {
+    if accuracy == Accuracy::Fast {
+        return crate::batched_sgemm_t(ap, bp, cp, m, n, k);
+    }
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
+    unsafe {
+        ggml_compute_forward_mul_mat_t_dot(
+            accuracy.dot(),
+            ap,
+            a_skip,
+            bp,
+            b_skip,
+            cp,
+            c_skip,
+            m,
+            n,
+            k,
+            batching,
+            None,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// like [`crate::batched_sgemm`] with the dot products accumulated
+/// according to `accuracy`. `C` is overwritten. Except for
+/// [`Accuracy::Fast`], `B` is first copied transposed so that the products
+/// run along contiguous rows.
+///
+/// ```
+/// use ggblas::{batched_sgemm_accuracy, Accuracy};
+///
+/// let a = vec![1., 1e-8, -1.];
+/// let b = vec![1., 1., 1.];
+/// let mut c = vec![0.];
+///
+/// batched_sgemm_accuracy(Accuracy::F64, &a, &b, &mut c, 1, 1, 3);
+/// assert_eq!(c, &[1e-8]);
+/// ```
+pub fn batched_sgemm_accuracy(
+    accuracy: Accuracy,
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+) {
+    if accuracy == Accuracy::Fast {
+        return crate::batched_sgemm(ap, bp, cp, m, n, k);
+    }
+    let mut bt = vec![0.0; bp.len()];
+    let b_skip = size(&[k, n]);
+    if b_skip == 0 {
+        return batched_sgemm_t_accuracy(accuracy, ap, &bt, cp, m, n, k);
+    }
+    bp.chunks_exact(b_skip)
+        .zip(bt.chunks_exact_mut(b_skip))
+        .for_each(|(b, bt)| {
+            for kk in 0..k {
+                for j in 0..n {
+                    bt[j * k + kk] = b[kk * n + j];
+                }
+            }
+        });
+    batched_sgemm_t_accuracy(accuracy, ap, &bt, cp, m, n, k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const U: f64 = f32::EPSILON as f64 / 2.0;
+    /// Every test value is a multiple of `2^-FIXED`, so that the products and
+    /// their sums are exact integers once scaled by `2^(2 * FIXED)`.
+    const FIXED: i32 = 30;
+
+    /// 16 bits integers scaled by small powers of two.
+    fn dyadic(len: usize, seed: u64) -> Vec<f32> {
+        let mut state = seed | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let mantissa = (state >> 48) as i64 as f32 - 32768.0;
+                mantissa * 2f32.powi(-((state % 12) as i32))
+            })
+            .collect()
+    }
+
+    /// The exact dot product, and `sum(|a * b|)`.
+    fn exact(a: &[f32], b: &[f32]) -> (f64, f64) {
+        let fixed = |v: f32| {
+            let v = v as f64 * 2f64.powi(FIXED);
+            assert_eq!(v.fract(), 0.0);
+            v as i128
+        };
+        let (mut sum, mut abs) = (0i128, 0i128);
+        for (&a, &b) in a.iter().zip(b) {
+            let p = fixed(a) * fixed(b);
+            sum += p;
+            abs += p.abs();
+        }
+        let unscale = 2f64.powi(-2 * FIXED);
+        (sum as f64 * unscale, abs as f64 * unscale)
+    }
+
+    fn bound(accuracy: Accuracy, k: usize, exact: f64, abs: f64) -> f64 {
+        let k = k as f64;
+        let block = crate::ggml::accurate::BLOCK as f64;
+        match accuracy {
+            Accuracy::Fast => k * U * abs,
+            Accuracy::Pairwise => (block + (k / block).log2().max(0.0) + 1.0) * U * abs,
+            Accuracy::Compensated => U * exact.abs() + (k * U).powi(2) * abs,
+            Accuracy::F64 => U * exact.abs() + k * 2f64.powi(-53) * abs,
+            Accuracy::Deterministic => {
+                let lanes = crate::ggml::accurate::LANES as f64;
+                (k / lanes + lanes.log2() + 1.0) * U * abs
+            }
+        }
+    }
+
+    const MODES: [Accuracy; 5] = [
+        Accuracy::Fast,
+        Accuracy::Pairwise,
+        Accuracy::Compensated,
+        Accuracy::F64,
+        Accuracy::Deterministic,
+    ];
+
+    #[test]
+    fn error_bounds() {
+        let (m, n) = (2, 3);
+        for k in [1, 7, 255, 257, 4099, 150_000] {
+            let mut a = dyadic(m * k, k as u64);
+            let mut b = dyadic(n * k, k as u64 + 1);
+            // Make the first row of `A` and the last row of `B` cancel out:
+            // the second half undoes the first one, except for a few terms.
+            let half = k / 2;
+            for l in 0..half {
+                if l % 16 != 0 {
+                    a[half + l] = a[l];
+                }
+                b[(n - 1) * k + half + l] = -b[(n - 1) * k + l];
+            }
+            for accuracy in MODES {
+                let mut c = vec![f32::NAN; m * n];
+                batched_sgemm_t_accuracy(accuracy, &a, &b, &mut c, m, n, k);
+                for i in 0..m {
+                    for j in 0..n {
+                        let (exact, abs) = exact(&a[i * k..(i + 1) * k], &b[j * k..(j + 1) * k]);
+                        let got = c[i * n + j] as f64;
+                        let bound = bound(accuracy, k, exact, abs);
+                        assert!(
+                            (got - exact).abs() <= bound,
+                            "{accuracy:?} k={k} ({i}, {j}): {got} != {exact} (bound {bound})"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cancellation() {
+        // Large terms cancelling out exactly, leaving the small ones.
+        let k = 100_000;
+        let small = dyadic(k, 3);
+        let mut a = vec![0.0; 3 * k];
+        let mut b = vec![1.0; 3 * k];
+        for l in 0..k {
+            a[3 * l] = 4096.0 + l as f32;
+            a[3 * l + 1] = small[l] * 2f32.powi(-12);
+            a[3 * l + 2] = -a[3 * l];
+            b[3 * l + 1] = 1.0;
+        }
+        let (exact, _) = exact(&a, &b);
+        // Far tighter than the worst case bounds for such a `k`.
+        for accuracy in [Accuracy::Compensated, Accuracy::F64] {
+            for n_kernel in [false, true] {
+                let mut c = vec![f32::NAN];
+                if n_kernel {
+                    batched_sgemm_accuracy(accuracy, &a, &b, &mut c, 1, 1, 3 * k);
+                } else {
+                    batched_sgemm_t_accuracy(accuracy, &a, &b, &mut c, 1, 1, 3 * k);
+                }
+                let err = (c[0] as f64 - exact).abs() / exact.abs();
+                assert!(err < 1e-6, "{accuracy:?} {} != {exact}", c[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn fast_is_the_regular_kernel() {
+        // Long enough for the regular kernels to split the reduction.
+        let (m, n, k) = (2, 3, crate::raw::split_k::MIN_K + 3);
+        // Small integers, so both layouts are exact and must agree.
+        let a: Vec<f32> = (0..m * k).map(|s| (s % 7) as f32 - 3.0).collect();
+        let b: Vec<f32> = (0..k * n).map(|s| (s % 5) as f32 - 2.0).collect();
+        let mut b_t = vec![0.0; b.len()];
+        for l in 0..k {
+            for j in 0..n {
+                b_t[j * k + l] = b[l * n + j];
+            }
+        }
+
+        let mut expected = vec![f32::NAN; m * n];
+        crate::batched_sgemm(&a, &b, &mut expected, m, n, k);
+        let mut c = vec![f32::NAN; m * n];
+        batched_sgemm_accuracy(Accuracy::Fast, &a, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+
+        crate::batched_sgemm_t(&a, &b_t, &mut expected, m, n, k);
+        let mut c_t = vec![f32::NAN; m * n];
+        batched_sgemm_t_accuracy(Accuracy::Fast, &a, &b_t, &mut c_t, m, n, k);
+        assert_eq!(c_t, expected);
+        assert_eq!(c_t, c);
+    }
+
+    #[test]
+    fn modes_agree_on_gemm() {
+        let (m, n, k) = (5, 7, 300);
+        let a = dyadic(2 * m * k, 1);
+        let b = dyadic(2 * k * n, 2);
+        let mut reference = vec![0.0; 2 * m * n];
+        crate::reference::batched_sgemm(&a, &b, &mut reference, m, n, k);
+        for accuracy in MODES {
+            let mut c = vec![f32::NAN; 2 * m * n];
+            batched_sgemm_accuracy(accuracy, &a, &b, &mut c, m, n, k);
+            for (x, y) in c.iter().zip(&reference) {
+                assert!((x - y).abs() <= 1e-3 * y.abs().max(1.0), "{accuracy:?}");
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn deterministic() {
+        use crate::ggml::accurate::LANES;
+        use crate::ThreadPool;
+
+        let (batch, m, n, k) = (2, 7, 9, 1003);
+        let a = dyadic(batch * m * k, 5);
+        let b = dyadic(batch * n * k, 6);
+
+        // The evaluation order spelled out with scalars, which no SIMD width
+        // can change.
+        let mut expected = vec![0.0f32; batch * m * n];
+        for s in 0..batch {
+            for i in 0..m {
+                for j in 0..n {
+                    let a = &a[(s * m + i) * k..(s * m + i + 1) * k];
+                    let b = &b[(s * n + j) * k..(s * n + j + 1) * k];
+                    let mut acc = [0.0f32; LANES];
+                    for l in 0..k {
+                        acc[l % LANES] = a[l].mul_add(b[l], acc[l % LANES]);
+                    }
+                    let mut width = LANES;
+                    while width > 1 {
+                        width /= 2;
+                        for l in 0..width {
+                            acc[l] += acc[l + width];
+                        }
+                    }
+                    expected[(s * m + i) * n + j] = acc[0];
+                }
+            }
+        }
+
+        for threads in 1..=8 {
+            let pool = ThreadPool::new(threads);
+            for accuracy in [
+                Accuracy::Compensated,
+                Accuracy::F64,
+                Accuracy::Deterministic,
+            ] {
+                let mut c = vec![f32::NAN; batch * m * n];
+                unsafe {
+                    ggml_compute_forward_mul_mat_t_dot(
+                        accuracy.dot(),
+                        &a,
+                        m * k,
+                        &b,
+                        n * k,
+                        &mut c,
+                        m * n,
+                        m,
+                        n,
+                        k,
+                        batch,
+                        None,
+                        &pool,
+                    );
+                }
+                let mut single = vec![f32::NAN; batch * m * n];
+                batched_sgemm_t_accuracy(accuracy, &a, &b, &mut single, m, n, k);
+                let bits = |x: &[f32]| x.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+                assert_eq!(bits(&c), bits(&single), "{accuracy:?} {threads}");
+                if accuracy == Accuracy::Deterministic {
+                    assert_eq!(bits(&c), bits(&expected), "{threads}");
+                }
+            }
+        }
+    }
+}
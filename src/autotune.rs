@@ -0,0 +1,410 @@
+//! Picking the fastest backend for each problem shape.
+//!
+//! The best choice depends on the shape: the native `A * B.T` kernel beats
+//! most libraries while the `A * B` one loses to MKL, and small problems run
+//! faster on fewer threads. [`Tuner`] times every candidate the first time a
+//! shape shows up and remembers the winner. The table can be saved to a file
+//! and loaded back so the timings are only paid once per machine.
+//!
+//! ```no_run
+//! use ggblas::autotune::Tuner;
+//! use ggblas::backend;
+//! use std::sync::Arc;
+//!
+//! let tuner = Tuner::new();
+//! let _ = tuner.load("tuning.txt");
+//! let tuner = Arc::new(tuner);
+//! backend::set_default(tuner.clone());
+//!
+//! // ... run the workload through `backend::sgemm` and `backend::sgemm_t`
+//!
+//! tuner.save("tuning.txt").unwrap();
+//! ```
+use crate::backend::{self, Backend, GgmlPool};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Element type of a tuned problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DType {
+    F32,
+}
+
+impl DType {
+    fn as_str(self) -> &'static str {
+        match self {
+            DType::F32 => "f32",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "f32" => Some(DType::F32),
+            _ => None,
+        }
+    }
+}
+
+/// A problem shape, the batch size is not part of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub m: usize,
+    pub n: usize,
+    pub k: usize,
+    pub dtype: DType,
+    /// `C = A * B.T` rather than `C = A * B`.
+    pub transpose: bool,
+}
+
+/// Times candidate backends per shape and runs the winners.
+///
+/// A tuner is itself a [`Backend`], named `auto`, so it can be set as the
+/// default backend or passed wherever one is expected.
+pub struct Tuner {
+    candidates: Vec<Arc<dyn Backend>>,
+    table: RwLock<HashMap<Key, String>>,
+    /// Held while timing, so that shapes are tuned one at a time.
+    tuning: Mutex<()>,
+    repeats: usize,
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tuner {
+    /// Every backend compiled in, plus the native kernels on fewer threads
+    /// (powers of two below the number of cores).
+    pub fn new() -> Self {
+        let mut candidates = backend::available();
+        let cpus = num_cpus::get();
+        let mut threads = 1;
+        while threads < cpus {
+            candidates.push(Arc::new(GgmlPool::new(threads)));
+            threads *= 2;
+        }
+        Self::with_candidates(candidates)
+    }
+
+    /// Only considers `candidates`, which must have distinct names.
+    pub fn with_candidates(candidates: Vec<Arc<dyn Backend>>) -> Self {
+        assert!(!candidates.is_empty(), "no candidate backend");
+        Self {
+            candidates,
+            table: RwLock::new(HashMap::new()),
+            tuning: Mutex::new(()),
+            repeats: 3,
+        }
+    }
+
+    /// Number of timed runs per candidate, the fastest one counts.
+    pub fn repeats(mut self, repeats: usize) -> Self {
+        self.repeats = repeats.max(1);
+        self
+    }
+
+    fn candidate(&self, name: &str) -> Option<&Arc<dyn Backend>> {
+        self.candidates.iter().find(|c| c.name() == name)
+    }
+
+    fn lookup(&self, key: Key) -> Option<Arc<dyn Backend>> {
+        let table = self.table.read().unwrap();
+        self.candidate(table.get(&key)?).cloned()
+    }
+
+    /// The backend running `key`, timing the candidates if the shape was
+    /// never seen, or if its recorded winner is not a candidate.
+    ///
+    /// Tuning runs on one thread at a time, so the timings don't contend with
+    /// each other and threads missing on the same shape wait for the first
+    /// one's winner instead of timing it again.
+    pub fn select(&self, key: Key) -> Arc<dyn Backend> {
+        if let Some(backend) = self.lookup(key) {
+            return backend;
+        }
+        let _tuning = self.tuning.lock().unwrap();
+        if let Some(backend) = self.lookup(key) {
+            return backend;
+        }
+        let winner = self.tune(key);
+        self.table
+            .write()
+            .unwrap()
+            .insert(key, winner.name().to_string());
+        winner
+    }
+
+    fn tune(&self, key: Key) -> Arc<dyn Backend> {
+        let Key {
+            m, n, k, transpose, ..
+        } = key;
+        let a: Vec<f32> = (0..m * k).map(|i| (i % 7) as f32 - 3.0).collect();
+        let b: Vec<f32> = (0..k * n).map(|i| (i % 5) as f32 - 2.0).collect();
+        let mut c = vec![0.0; m * n];
+        let mut best = (Duration::MAX, &self.candidates[0]);
+        for candidate in &self.candidates {
+            let mut run = || {
+                let start = Instant::now();
+                if transpose {
+                    candidate.batched_sgemm_t(&a, &b, &mut c, m, n, k);
+                } else {
+                    candidate.batched_sgemm(&a, &b, &mut c, m, n, k);
+                }
+                start.elapsed()
+            };
+            // Warm up caches and lazily created thread pools.
+            run();
+            let time = (0..self.repeats).map(|_| run()).min().unwrap();
+            if time < best.0 {
+                best = (time, candidate);
+            }
+        }
+        best.1.clone()
+    }
+
+    /// The recorded winners, as `(key, backend name)`.
+    pub fn table(&self) -> Vec<(Key, String)> {
+        let table = self.table.read().unwrap();
+        table.iter().map(|(k, v)| (*k, v.clone())).collect()
+    }
+
+    /// Writes the table, one `dtype n|t m n k backend` line per shape.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut table = self.table();
+        table.sort_by_key(|(key, _)| (key.transpose, key.m, key.n, key.k));
+        let mut out = String::from("# ggblas tuning table: dtype n|t m n k backend\n");
+        for (key, name) in table {
+            let tr = if key.transpose { "t" } else { "n" };
+            let dtype = key.dtype.as_str();
+            writeln!(out, "{dtype} {tr} {} {} {} {name}", key.m, key.n, key.k).unwrap();
+        }
+        std::fs::write(path, out)
+    }
+
+    /// Merges a table written by [`Tuner::save`], overriding the entries
+    /// already known. Backends missing from this build are kept in the table
+    /// but get tuned again when their shape is used.
+    pub fn load(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        let mut entries = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid tuning entry on line {}: {line}", i + 1),
+                )
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [dtype, tr, m, n, k, name] = fields[..] else {
+                return Err(invalid());
+            };
+            let key = Key {
+                dtype: DType::parse(dtype).ok_or_else(invalid)?,
+                transpose: match tr {
+                    "n" => false,
+                    "t" => true,
+                    _ => return Err(invalid()),
+                },
+                m: m.parse().map_err(|_| invalid())?,
+                n: n.parse().map_err(|_| invalid())?,
+                k: k.parse().map_err(|_| invalid())?,
+            };
+            entries.push((key, name.to_string()));
+        }
+        self.table.write().unwrap().extend(entries);
+        Ok(())
+    }
+}
+
+impl Backend for Tuner {
+    fn name(&self) -> &str {
+        "auto"
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        let key = Key {
+            m,
+            n,
+            k,
+            dtype: DType::F32,
+            transpose: false,
+        };
+        self.select(key).batched_sgemm(ap, bp, cp, m, n, k)
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        let key = Key {
+            m,
+            n,
+            k,
+            dtype: DType::F32,
+            transpose: true,
+        };
+        self.select(key).batched_sgemm_t(ap, bp, cp, m, n, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Ggml;
+
+    /// A backend that is always slower than the native one.
+    struct Slow;
+
+    impl Backend for Slow {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn batched_sgemm(
+            &self,
+            ap: &[f32],
+            bp: &[f32],
+            cp: &mut [f32],
+            m: usize,
+            n: usize,
+            k: usize,
+        ) {
+            std::thread::sleep(Duration::from_millis(20));
+            Ggml.batched_sgemm(ap, bp, cp, m, n, k)
+        }
+
+        fn batched_sgemm_t(
+            &self,
+            ap: &[f32],
+            bp: &[f32],
+            cp: &mut [f32],
+            m: usize,
+            n: usize,
+            k: usize,
+        ) {
+            std::thread::sleep(Duration::from_millis(20));
+            Ggml.batched_sgemm_t(ap, bp, cp, m, n, k)
+        }
+    }
+
+    #[test]
+    fn picks_and_persists() {
+        let tuner = Tuner::with_candidates(vec![Arc::new(Slow), Arc::new(Ggml)]).repeats(1);
+        let a = vec![1., 2., 3., 4., 5., 6., 7., 8.];
+        let b = vec![1., 2., 3., 4.];
+        let mut c = vec![0.0; 4];
+        // Two batches of (2, 2) x (2, 1).
+        tuner.batched_sgemm(&a, &b, &mut c, 2, 1, 2);
+        assert_eq!(c, &[5., 11., 39., 53.]);
+        let key = Key {
+            m: 2,
+            n: 1,
+            k: 2,
+            dtype: DType::F32,
+            transpose: false,
+        };
+        assert_eq!(tuner.table(), vec![(key, "ggml".to_string())]);
+
+        let path = std::env::temp_dir().join(format!("ggblas-tuning-{}.txt", std::process::id()));
+        tuner.save(&path).unwrap();
+        let loaded = Tuner::with_candidates(vec![Arc::new(Slow), Arc::new(Ggml)]);
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded.table(), tuner.table());
+        assert_eq!(loaded.select(key).name(), "ggml");
+
+        std::fs::write(&path, "f32 x 1 2 3 ggml\n").unwrap();
+        assert_eq!(
+            loaded.load(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Counts the products it runs.
+    #[derive(Default)]
+    struct Counting(std::sync::atomic::AtomicUsize);
+
+    impl Backend for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn batched_sgemm(
+            &self,
+            ap: &[f32],
+            bp: &[f32],
+            cp: &mut [f32],
+            m: usize,
+            n: usize,
+            k: usize,
+        ) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ggml.batched_sgemm(ap, bp, cp, m, n, k)
+        }
+
+        fn batched_sgemm_t(
+            &self,
+            ap: &[f32],
+            bp: &[f32],
+            cp: &mut [f32],
+            m: usize,
+            n: usize,
+            k: usize,
+        ) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ggml.batched_sgemm_t(ap, bp, cp, m, n, k)
+        }
+    }
+
+    #[test]
+    fn tunes_once_per_shape() {
+        let counting = Arc::new(Counting::default());
+        let tuner = Tuner::with_candidates(vec![counting.clone(), Arc::new(Slow)]).repeats(1);
+        let key = Key {
+            m: 3,
+            n: 2,
+            k: 4,
+            dtype: DType::F32,
+            transpose: true,
+        };
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| assert_eq!(tuner.select(key).name(), "counting"));
+            }
+        });
+        // A warm-up and a timed run, by a single thread.
+        assert_eq!(counting.0.load(std::sync::atomic::Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn default_candidates() {
+        let tuner = Tuner::new();
+        let names: Vec<_> = tuner.candidates.iter().map(|c| c.name()).collect();
+        assert_eq!(names[0], "ggml");
+        let mut dedup = names.clone();
+        dedup.sort();
+        dedup.dedup();
+        assert_eq!(dedup.len(), names.len());
+
+        let a = vec![1., 2., 3., 4.];
+        let mut c = vec![0.0; 4];
+        tuner.batched_sgemm_t(&a, &a, &mut c, 2, 2, 2);
+        assert_eq!(c, &[5., 11., 11., 25.]);
+    }
+}
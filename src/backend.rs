@@ -0,0 +1,531 @@
+//! Runtime selection of the library running the GEMMs.
+//!
+//! Every backend enabled at compile time implements [`Backend`], the native
+//! kernels being always available as [`Ggml`]. Calls can go to a given
+//! backend directly, or through [`sgemm`] and [`sgemm_t`] which use the
+//! process wide default set by [`set_default`].
+//!
+//! ```
+//! use ggblas::backend::{self, Backend};
+//!
+//! let a = vec![1., 2., 3., 4.];
+//! let b = vec![1., 2., 3., 4.];
+//! let mut c = vec![f32::NAN; 4];
+//!
+//! let ggml = backend::by_name("ggml").unwrap();
+//! ggml.batched_sgemm(&a, &b, &mut c, 2, 2, 2);
+//! assert_eq!(c, &[7., 10., 15., 22.]);
+//!
+//! backend::set_default(ggml);
+//! backend::sgemm_t(&a, &b, &mut c, 2, 2, 2);
+//! assert_eq!(c, &[5., 11., 11., 25.]);
+//! ```
+use crate::size;
+use std::sync::{Arc, RwLock};
+
+/// A library able to compute batched GEMMs.
+///
+/// The buffers are row major and batched like [`crate::batched_sgemm`], and
+/// `C` is overwritten as it is by the free functions.
+pub trait Backend: Send + Sync {
+    /// Name used by [`by_name`].
+    fn name(&self) -> &str;
+
+    /// Computes `C = A * B`.
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize);
+
+    /// Computes `C = A * B.T`.
+    fn batched_sgemm_t(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize);
+}
+
+/// Checks the sizes and returns the batching.
+// Unused on wasm unless one of the foreign backends is enabled.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn batching(ap: &[f32], bp: &[f32], cp: &[f32], m: usize, n: usize, k: usize) -> usize {
+    crate::batching([
+        ("A", ap.len(), size(&[m, k])),
+        ("B", bp.len(), size(&[k, n])),
+        ("C", cp.len(), size(&[m, n])),
+    ])
+}
+
+/// The native kernels of this crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ggml;
+
+impl Backend for Ggml {
+    fn name(&self) -> &str {
+        "ggml"
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        crate::batched_sgemm(ap, bp, cp, m, n, k)
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        crate::batched_sgemm_t(ap, bp, cp, m, n, k)
+    }
+}
+
+/// The native kernels on a dedicated pool of threads instead of the global
+/// one using every core.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct GgmlPool {
+    pool: threadpool::ThreadPool,
+    name: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GgmlPool {
+    pub fn new(threads: usize) -> Self {
+        Self {
+            pool: threadpool::ThreadPool::new(threads),
+            name: format!("ggml-{threads}"),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.max_count()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Backend for GgmlPool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        let batching = batching(ap, bp, cp, m, n, k);
+        unsafe {
+            crate::raw::ggml_compute_forward_mul_mat(
+                ap,
+                m * k,
+                false,
+                bp,
+                k * n,
+                cp,
+                m * n,
+                m,
+                n,
+                k,
+                batching,
+                None,
+                &self.pool,
+            )
+        }
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        let batching = batching(ap, bp, cp, m, n, k);
+        unsafe {
+            crate::raw::ggml_compute_forward_mul_mat_t(
+                ap,
+                m * k,
+                bp,
+                k * n,
+                cp,
+                m * n,
+                m,
+                n,
+                k,
+                batching,
+                None,
+                &self.pool,
+            )
+        }
+    }
+}
+
+/// The CBLAS library linked in, MKL with the `intel-mkl` feature.
+#[cfg(any(feature = "cblas", feature = "intel-mkl"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cblas;
+
+#[cfg(any(feature = "cblas", feature = "intel-mkl"))]
+impl Cblas {
+    fn gemm(
+        &self,
+        transpose: bool,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        use cblas_sys::{cblas_sgemm, CblasNoTrans, CblasRowMajor, CblasTrans};
+        let batching = batching(ap, bp, cp, m, n, k);
+        let (b_tr, ldb) = if transpose {
+            (CblasTrans, k)
+        } else {
+            (CblasNoTrans, n)
+        };
+        for step in 0..batching {
+            unsafe {
+                cblas_sgemm(
+                    CblasRowMajor,
+                    CblasNoTrans,
+                    b_tr,
+                    m as libc::c_int,
+                    n as libc::c_int,
+                    k as libc::c_int,
+                    1.0,
+                    ap[step * m * k..].as_ptr(),
+                    k as libc::c_int,
+                    bp[step * k * n..].as_ptr(),
+                    ldb as libc::c_int,
+                    0.0,
+                    cp[step * m * n..].as_mut_ptr(),
+                    n as libc::c_int,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "cblas", feature = "intel-mkl"))]
+impl Backend for Cblas {
+    fn name(&self) -> &str {
+        if cfg!(feature = "intel-mkl") {
+            "mkl"
+        } else {
+            "cblas"
+        }
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        self.gemm(false, ap, bp, cp, m, n, k)
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        self.gemm(true, ap, bp, cp, m, n, k)
+    }
+}
+
+/// [matrixmultiply](https://docs.rs/matrixmultiply/).
+#[cfg(feature = "matrixmultiply")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatrixMultiply;
+
+#[cfg(feature = "matrixmultiply")]
+impl MatrixMultiply {
+    fn gemm(
+        &self,
+        transpose: bool,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        let batching = batching(ap, bp, cp, m, n, k);
+        let (br, bc) = if transpose {
+            (1, k as isize)
+        } else {
+            (n as isize, 1)
+        };
+        for step in 0..batching {
+            unsafe {
+                matrixmultiply::sgemm(
+                    m,
+                    k,
+                    n,
+                    1.0,
+                    ap[step * m * k..].as_ptr(),
+                    k as isize,
+                    1,
+                    bp[step * k * n..].as_ptr(),
+                    br,
+                    bc,
+                    0.0,
+                    cp[step * m * n..].as_mut_ptr(),
+                    n as isize,
+                    1,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "matrixmultiply")]
+impl Backend for MatrixMultiply {
+    fn name(&self) -> &str {
+        "matrixmultiply"
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        self.gemm(false, ap, bp, cp, m, n, k)
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        self.gemm(true, ap, bp, cp, m, n, k)
+    }
+}
+
+/// [faer](https://docs.rs/faer/), with its global parallelism setting.
+#[cfg(feature = "faer")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Faer;
+
+#[cfg(feature = "faer")]
+impl Backend for Faer {
+    fn name(&self) -> &str {
+        "faer"
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        use faer::{linalg::matmul::matmul, Accum, MatMut, MatRef};
+        let batching = batching(ap, bp, cp, m, n, k);
+        // Stepping rather than chunking, any of the operands may be empty.
+        for step in 0..batching {
+            let a = &ap[step * m * k..][..m * k];
+            let b = &bp[step * k * n..][..k * n];
+            let c = &mut cp[step * m * n..][..m * n];
+            matmul(
+                MatMut::from_row_major_slice_mut(c, m, n),
+                Accum::Replace,
+                MatRef::from_row_major_slice(a, m, k),
+                MatRef::from_row_major_slice(b, k, n),
+                1.0,
+                faer::get_global_parallelism(),
+            );
+        }
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        use faer::{linalg::matmul::matmul, Accum, MatMut, MatRef};
+        let batching = batching(ap, bp, cp, m, n, k);
+        for step in 0..batching {
+            let a = &ap[step * m * k..][..m * k];
+            let b = &bp[step * k * n..][..k * n];
+            let c = &mut cp[step * m * n..][..m * n];
+            // The row major `(n, k)` buffer is `B` in column major.
+            matmul(
+                MatMut::from_row_major_slice_mut(c, m, n),
+                Accum::Replace,
+                MatRef::from_row_major_slice(a, m, k),
+                MatRef::from_column_major_slice(b, k, n),
+                1.0,
+                faer::get_global_parallelism(),
+            );
+        }
+    }
+}
+
+/// [faer-core](https://docs.rs/faer-core/), running on its rayon pool.
+#[cfg(feature = "faer-rs")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FaerCore;
+
+#[cfg(feature = "faer-rs")]
+impl FaerCore {
+    fn gemm(
+        &self,
+        transpose: bool,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        use faer_core::{mul, Conj, MatMut, MatRef, Parallelism};
+        let batching = batching(ap, bp, cp, m, n, k);
+        let (br, bc) = if transpose {
+            (1, k as isize)
+        } else {
+            (n as isize, 1)
+        };
+        for step in 0..batching {
+            let ap = &ap[step * m * k..];
+            let bp = &bp[step * k * n..];
+            let cp = &mut cp[step * m * n..];
+            // No `alpha` overwrites the destination.
+            unsafe {
+                mul::matmul(
+                    MatMut::from_raw_parts(cp.as_mut_ptr(), m, n, n as isize, 1),
+                    Conj::No,
+                    MatRef::from_raw_parts(ap.as_ptr(), m, k, k as isize, 1),
+                    Conj::No,
+                    MatRef::from_raw_parts(bp.as_ptr(), k, n, br, bc),
+                    Conj::No,
+                    None,
+                    1.0,
+                    Parallelism::Rayon(0),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "faer-rs")]
+impl Backend for FaerCore {
+    fn name(&self) -> &str {
+        "faer-core"
+    }
+
+    fn batched_sgemm(&self, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+        self.gemm(false, ap, bp, cp, m, n, k)
+    }
+
+    fn batched_sgemm_t(
+        &self,
+        ap: &[f32],
+        bp: &[f32],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        self.gemm(true, ap, bp, cp, m, n, k)
+    }
+}
+
+/// Every backend compiled in, the native one first.
+pub fn available() -> Vec<Arc<dyn Backend>> {
+    #[allow(unused_mut)]
+    let mut backends: Vec<Arc<dyn Backend>> = vec![Arc::new(Ggml)];
+    #[cfg(any(feature = "cblas", feature = "intel-mkl"))]
+    backends.push(Arc::new(Cblas));
+    #[cfg(feature = "matrixmultiply")]
+    backends.push(Arc::new(MatrixMultiply));
+    #[cfg(feature = "faer")]
+    backends.push(Arc::new(Faer));
+    #[cfg(feature = "faer-rs")]
+    backends.push(Arc::new(FaerCore));
+    backends
+}
+
+/// Looks a compiled in backend up by its [`Backend::name`].
+pub fn by_name(name: &str) -> Option<Arc<dyn Backend>> {
+    available().into_iter().find(|b| b.name() == name)
+}
+
+static DEFAULT: RwLock<Option<Arc<dyn Backend>>> = RwLock::new(None);
+
+/// The backend used by [`sgemm`] and [`sgemm_t`], [`Ggml`] unless changed.
+pub fn default() -> Arc<dyn Backend> {
+    DEFAULT
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| Arc::new(Ggml))
+}
+
+/// Changes the backend used by [`sgemm`] and [`sgemm_t`] for the whole
+/// process.
+pub fn set_default(backend: Arc<dyn Backend>) {
+    *DEFAULT.write().unwrap() = Some(backend);
+}
+
+/// Computes `C = A * B` on the default backend, see [`Backend::batched_sgemm`].
+pub fn sgemm(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+    default().batched_sgemm(ap, bp, cp, m, n, k)
+}
+
+/// Computes `C = A * B.T` on the default backend, see
+/// [`Backend::batched_sgemm_t`].
+pub fn sgemm_t(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+    default().batched_sgemm_t(ap, bp, cp, m, n, k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(n: usize, seed: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| ((i * 7919 + seed) % 61) as f32 / 16.0 - 1.875)
+            .collect()
+    }
+
+    #[test]
+    fn backends_agree() {
+        let (batch, m, n, k) = (2, 13, 17, 37);
+        let a = data(batch * m * k, 1);
+        let b = data(batch * k * n, 2);
+        let mut expected = vec![0.0; batch * m * n];
+        for s in 0..batch {
+            for i in 0..m {
+                for j in 0..n {
+                    expected[(s * m + i) * n + j] = (0..k)
+                        .map(|l| a[(s * m + i) * k + l] * b[(s * k + l) * n + j])
+                        .sum();
+                }
+            }
+        }
+        // B.T stored row major.
+        let mut b_t = vec![0.0; b.len()];
+        for s in 0..batch {
+            for l in 0..k {
+                for j in 0..n {
+                    b_t[(s * n + j) * k + l] = b[(s * k + l) * n + j];
+                }
+            }
+        }
+
+        for backend in available() {
+            assert!(by_name(backend.name()).is_some());
+            // Garbage in C must be overwritten.
+            let mut c = vec![f32::NAN; expected.len()];
+            backend.batched_sgemm(&a, &b, &mut c, m, n, k);
+            let mut c_t = vec![f32::NAN; expected.len()];
+            backend.batched_sgemm_t(&a, &b_t, &mut c_t, m, n, k);
+            for ((x, y), z) in c.iter().zip(&c_t).zip(&expected) {
+                assert!(
+                    (x - z).abs() < 1e-4 * z.abs().max(1.0),
+                    "{}",
+                    backend.name()
+                );
+                assert!(
+                    (y - z).abs() < 1e-4 * z.abs().max(1.0),
+                    "{}",
+                    backend.name()
+                );
+            }
+        }
+        assert!(by_name("unknown").is_none());
+    }
+}
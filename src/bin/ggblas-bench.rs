@@ -0,0 +1,492 @@
+//! Times ggblas against the other backends compiled in, on your shapes.
+//!
+//! ```bash
+//! cargo run --release --features matrixmultiply --bin ggblas-bench -- \
+//!     --m 1,64 --n 4096 --k 4096 --transpose n,t --threads 1,4
+//! ```
+//!
+//! Every combination of the given values is run, each result is checked
+//! against [`ggblas::reference`] and the timings are printed as a table, or as
+//! CSV with `--csv`.
+#[cfg(not(target_arch = "wasm32"))]
+use ggblas::backend::{self, Backend, GgmlPool};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+const USAGE: &str = "\
+Usage: ggblas-bench [OPTIONS]
+
+Every option but the flags takes a comma separated list of values, and every
+combination of them is run.
+
+Options:
+  --m <M>              Rows of A and C [default: 512]
+  --n <N>              Columns of B and C [default: 512]
+  --k <K>              Columns of A and rows of B [default: 512]
+  --batch <BATCH>      Number of matrices [default: 1]
+  --dtype <DTYPE>      f32, f16 or i8 [default: f32]
+  --transpose <T>      n for C = A * B, t for C = A * B.T [default: n,t]
+  --threads <THREADS>  Threads of the native f32 kernels [default: all cores]
+  --backends <NAMES>   f32 backends to compare [default: every one compiled in]
+  --warmup <N>         Untimed runs [default: 2]
+  --iters <N>          Timed runs [default: 10]
+  --no-check           Skip the validation against the reference
+  --csv                Print CSV instead of a table
+  -h, --help           Print this help
+
+f16 and i8 only run on the native kernels, with every core, and f16 only
+supports t.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DType {
+    F32,
+    F16,
+    I8,
+}
+
+struct Args {
+    m: Vec<usize>,
+    n: Vec<usize>,
+    k: Vec<usize>,
+    batch: Vec<usize>,
+    dtype: Vec<DType>,
+    transpose: Vec<bool>,
+    threads: Vec<usize>,
+    backends: Option<Vec<String>>,
+    warmup: usize,
+    iters: usize,
+    check: bool,
+    csv: bool,
+}
+
+fn list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|v| parse(v.trim()).ok_or_else(|| format!("invalid value `{v}`")))
+        .collect()
+}
+
+fn count(v: &str) -> Option<usize> {
+    v.parse().ok().filter(|&v| v > 0)
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut parsed = Args {
+            m: vec![512],
+            n: vec![512],
+            k: vec![512],
+            batch: vec![1],
+            dtype: vec![DType::F32],
+            transpose: vec![false, true],
+            threads: vec![num_cpus::get()],
+            backends: None,
+            warmup: 2,
+            iters: 10,
+            check: true,
+            csv: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {arg}"))
+            };
+            match arg.as_str() {
+                "--m" => parsed.m = list(&value()?, count)?,
+                "--n" => parsed.n = list(&value()?, count)?,
+                "--k" => parsed.k = list(&value()?, count)?,
+                "--batch" => parsed.batch = list(&value()?, count)?,
+                "--dtype" => {
+                    parsed.dtype = list(&value()?, |v| match v {
+                        "f32" => Some(DType::F32),
+                        "f16" => Some(DType::F16),
+                        "i8" => Some(DType::I8),
+                        _ => None,
+                    })?
+                }
+                "--transpose" => {
+                    parsed.transpose = list(&value()?, |v| match v {
+                        "n" => Some(false),
+                        "t" => Some(true),
+                        _ => None,
+                    })?
+                }
+                "--threads" => parsed.threads = list(&value()?, count)?,
+                "--backends" => parsed.backends = Some(list(&value()?, |v| Some(v.to_string()))?),
+                "--warmup" => parsed.warmup = value()?.parse().map_err(|_| "invalid --warmup")?,
+                "--iters" => parsed.iters = count(&value()?).ok_or("invalid --iters")?,
+                "--no-check" => parsed.check = false,
+                "--csv" => parsed.csv = true,
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(format!("unknown argument {arg}")),
+            }
+        }
+        Ok(Some(parsed))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Problem {
+    dtype: DType,
+    transpose: bool,
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+}
+
+impl Problem {
+    fn flops(&self) -> f64 {
+        2.0 * (self.batch * self.m * self.n * self.k) as f64
+    }
+
+    /// `A`, `B` and `B.T` filled with values in `[-4, 4)`, exact in `f16`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn inputs(&self) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let Problem { batch, m, n, k, .. } = *self;
+        let value = |i: usize, seed: usize| ((i * 7919 + seed) % 257) as f32 / 32.0 - 4.0;
+        let a: Vec<f32> = (0..batch * m * k).map(|i| value(i, 1)).collect();
+        let b: Vec<f32> = (0..batch * k * n).map(|i| value(i, 2)).collect();
+        let mut b_t = vec![0.0; b.len()];
+        for (b, b_t) in b.chunks(k * n).zip(b_t.chunks_mut(k * n)) {
+            for l in 0..k {
+                for j in 0..n {
+                    b_t[j * k + l] = b[l * n + j];
+                }
+            }
+        }
+        (a, b, b_t)
+    }
+}
+
+/// Timings and validation of one run.
+struct Row {
+    problem: Problem,
+    backend: String,
+    median: Duration,
+    min: Duration,
+    /// Largest error relative to the usual dot product error bound, the
+    /// result is wrong past 1.
+    error: Option<f64>,
+}
+
+/// Runs `f` `warmup + iters` times, returns its median and fastest times.
+#[cfg(not(target_arch = "wasm32"))]
+fn time(warmup: usize, iters: usize, mut f: impl FnMut()) -> (Duration, Duration) {
+    (0..warmup).for_each(|_| f());
+    let mut times: Vec<Duration> = (0..iters)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    (times[iters / 2], times[0])
+}
+
+/// Error of `got` over `(k + 2) * u * sum(|a| * |b|) + 2 * u * |expected|`,
+/// the bound checked by the tests, `u` being the unit roundoff.
+#[cfg(not(target_arch = "wasm32"))]
+fn error(got: &[f32], a: &[f32], b: &[f32], p: Problem, u: f32) -> f64 {
+    let Problem {
+        m, n, k, transpose, ..
+    } = p;
+    let abs = |x: &[f32]| -> Vec<f32> { x.iter().map(|v| v.abs()).collect() };
+    let gemm = if transpose {
+        ggblas::reference::batched_sgemm_t
+    } else {
+        ggblas::reference::batched_sgemm
+    };
+    let mut expected = vec![0.0; got.len()];
+    gemm(a, b, &mut expected, m, n, k);
+    let mut magnitude = vec![0.0; got.len()];
+    gemm(&abs(a), &abs(b), &mut magnitude, m, n, k);
+    got.iter()
+        .zip(&expected)
+        .zip(&magnitude)
+        .map(|((&got, &expected), &magnitude)| {
+            let bound =
+                (k + 2) as f32 * u * magnitude + 2.0 * u * expected.abs() + f32::MIN_POSITIVE;
+            let error = (got - expected).abs() / bound;
+            // NaN must fail the check.
+            if error.is_nan() {
+                f64::INFINITY
+            } else {
+                error as f64
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn f32_backends(args: &Args) -> Result<Vec<Arc<dyn Backend>>, String> {
+    let names = match &args.backends {
+        Some(names) => names.clone(),
+        None => backend::available()
+            .iter()
+            .map(|b| b.name().to_string())
+            .collect(),
+    };
+    let mut backends: Vec<Arc<dyn Backend>> = vec![];
+    for name in names {
+        if name == "ggml" {
+            for &threads in &args.threads {
+                backends.push(Arc::new(GgmlPool::new(threads)));
+            }
+        } else {
+            let backend = backend::by_name(&name).ok_or_else(|| {
+                let available: Vec<_> = backend::available()
+                    .iter()
+                    .map(|b| b.name().to_string())
+                    .collect();
+                format!(
+                    "unknown backend `{name}`, available: {}",
+                    available.join(", ")
+                )
+            })?;
+            backends.push(backend);
+        }
+    }
+    Ok(backends)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_f32(args: &Args, backends: &[Arc<dyn Backend>], p: Problem, rows: &mut Vec<Row>) {
+    let Problem { m, n, k, .. } = p;
+    let (a, b, b_t) = p.inputs();
+    let b = if p.transpose { b_t } else { b };
+    for backend in backends {
+        let mut c = vec![f32::NAN; p.batch * m * n];
+        let (median, min) = time(args.warmup, args.iters, || {
+            if p.transpose {
+                backend.batched_sgemm_t(&a, &b, &mut c, m, n, k)
+            } else {
+                backend.batched_sgemm(&a, &b, &mut c, m, n, k)
+            }
+        });
+        rows.push(Row {
+            problem: p,
+            backend: backend.name().to_string(),
+            median,
+            min,
+            error: args.check.then(|| error(&c, &a, &b, p, f32::EPSILON)),
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "f16")]
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+fn run_f16(args: &Args, p: Problem, rows: &mut Vec<Row>) -> Result<(), String> {
+    use half::f16;
+    let Problem { m, n, k, .. } = p;
+    if !p.transpose {
+        return Err("f16 only supports --transpose t".into());
+    }
+    let (a, _, b_t) = p.inputs();
+    let to_f16 = |x: &[f32]| -> Vec<f16> { x.iter().map(|&v| f16::from_f32(v)).collect() };
+    let (a16, b16) = (to_f16(&a), to_f16(&b_t));
+    let mut c = vec![f16::ZERO; p.batch * m * n];
+    let (median, min) = time(args.warmup, args.iters, || {
+        ggblas::f16::batched_sgemm_t_f16_pure(&a16, &b16, &mut c, m, n, k)
+    });
+    let c: Vec<f32> = c.iter().map(|v| v.to_f32()).collect();
+    rows.push(Row {
+        problem: p,
+        backend: "ggml".into(),
+        median,
+        min,
+        error: args
+            .check
+            .then(|| error(&c, &a, &b_t, p, f16::EPSILON.to_f32())),
+    });
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(all(
+    feature = "f16",
+    not(any(target_arch = "arm", target_arch = "aarch64"))
+)))]
+fn run_f16(_: &Args, _: Problem, _: &mut Vec<Row>) -> Result<(), String> {
+    Err("f16 needs the `f16` feature, on x86".into())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_i8(args: &Args, p: Problem, rows: &mut Vec<Row>) {
+    let Problem { m, n, k, .. } = p;
+    let (a, b, b_t) = p.inputs();
+    let b = if p.transpose { b_t } else { b };
+    let to_i8 = |x: &[f32]| -> Vec<i8> { x.iter().map(|&v| (v * 32.0) as i8).collect() };
+    let (a8, b8) = (to_i8(&a), to_i8(&b));
+    let mut c = vec![0; p.batch * m * n];
+    let (median, min) = time(args.warmup, args.iters, || {
+        if p.transpose {
+            ggblas::int8::gemm_i8_t(&a8, &b8, &mut c, m, n, k)
+        } else {
+            ggblas::int8::gemm_i8(&a8, &b8, &mut c, m, n, k)
+        }
+    });
+    // The reference is exact on integers, with no roundoff allowed any
+    // difference fails.
+    let error = args.check.then(|| {
+        let widen = |x: &[i8]| -> Vec<f32> { x.iter().map(|&v| v as f32).collect() };
+        let c: Vec<f32> = c.iter().map(|&v| v as f32).collect();
+        error(&c, &widen(&a8), &widen(&b8), p, 0.0)
+    });
+    rows.push(Row {
+        problem: p,
+        backend: "ggml".into(),
+        median,
+        min,
+        error,
+    });
+}
+
+fn print(rows: &[Row], csv: bool) {
+    let header = [
+        "dtype",
+        "op",
+        "batch",
+        "m",
+        "n",
+        "k",
+        "backend",
+        "median_ms",
+        "min_ms",
+        "gflops",
+        "vs_best",
+        "error",
+        "check",
+    ];
+    let mut lines = vec![header.map(String::from).to_vec()];
+    for row in rows {
+        let p = row.problem;
+        let same = |other: &Row| {
+            let q = other.problem;
+            (q.dtype, q.transpose, q.batch, q.m, q.n, q.k)
+                == (p.dtype, p.transpose, p.batch, p.m, p.n, p.k)
+        };
+        let best = rows
+            .iter()
+            .filter(|r| same(r))
+            .map(|r| r.median)
+            .min()
+            .unwrap();
+        let dtype = match p.dtype {
+            DType::F32 => "f32",
+            DType::F16 => "f16",
+            DType::I8 => "i8",
+        };
+        let (error, check) = match row.error {
+            Some(error) => (
+                format!("{error:.3}"),
+                if error <= 1.0 { "ok" } else { "FAIL" },
+            ),
+            None => ("-".into(), "-"),
+        };
+        lines.push(vec![
+            dtype.into(),
+            if p.transpose { "t" } else { "n" }.into(),
+            p.batch.to_string(),
+            p.m.to_string(),
+            p.n.to_string(),
+            p.k.to_string(),
+            row.backend.clone(),
+            format!("{:.3}", row.median.as_secs_f64() * 1e3),
+            format!("{:.3}", row.min.as_secs_f64() * 1e3),
+            format!("{:.2}", p.flops() / row.median.as_secs_f64() / 1e9),
+            format!("{:.2}", row.median.as_secs_f64() / best.as_secs_f64()),
+            error,
+            check.into(),
+        ]);
+    }
+    if csv {
+        for line in lines {
+            println!("{}", line.join(","));
+        }
+        return;
+    }
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| lines.iter().map(|l| l[i].len()).max().unwrap())
+        .collect();
+    for line in lines {
+        let cells: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:>width$}"))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run(args: &Args) -> Result<Vec<Row>, String> {
+    let backends = if args.dtype.contains(&DType::F32) {
+        f32_backends(args)?
+    } else {
+        vec![]
+    };
+    let mut rows = vec![];
+    for &dtype in &args.dtype {
+        for &transpose in &args.transpose {
+            for &batch in &args.batch {
+                for &m in &args.m {
+                    for &n in &args.n {
+                        for &k in &args.k {
+                            let p = Problem {
+                                dtype,
+                                transpose,
+                                batch,
+                                m,
+                                n,
+                                k,
+                            };
+                            match dtype {
+                                DType::F32 => run_f32(args, &backends, p, &mut rows),
+                                DType::F16 => run_f16(args, p, &mut rows)?,
+                                DType::I8 => run_i8(args, p, &mut rows),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn run(_: &Args) -> Result<Vec<Row>, String> {
+    Err("ggblas-bench does not run on wasm32".into())
+}
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    match run(&args) {
+        Ok(rows) => {
+            print(&rows, args.csv);
+            if rows.iter().any(|r| r.error.is_some_and(|e| e > 1.0)) {
+                std::process::exit(1);
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,951 @@
+//! CBLAS compatible C interface, enabled by the `capi` feature.
+//!
+//! Built as a shared library, the crate exports the `cblas_*` symbols declared
+//! in `include/ggblas.h` with their standard signatures, so C and C++ code
+//! written against CBLAS can link against ggblas directly:
+//!
+//! ```bash
+//! cargo rustc --release --lib --crate-type cdylib --features capi
+//! ```
+//!
+//! The kernels only handle contiguous row major operands, so strided or column
+//! major operands are packed into temporary buffers first, and `alpha`/`beta`
+//! are applied when writing `C` back.
+//!
+//! Invalid arguments are reported on stderr the way the reference `xerbla`
+//! does, and the call returns without touching its outputs.
+use std::borrow::Cow;
+use std::ffi::c_int;
+
+pub mod fortran;
+
+pub const CBLAS_ROW_MAJOR: c_int = 101;
+pub const CBLAS_COL_MAJOR: c_int = 102;
+pub const CBLAS_NO_TRANS: c_int = 111;
+pub const CBLAS_TRANS: c_int = 112;
+pub const CBLAS_CONJ_TRANS: c_int = 113;
+pub const CBLAS_UPPER: c_int = 121;
+pub const CBLAS_LOWER: c_int = 122;
+pub const CBLAS_NON_UNIT: c_int = 131;
+pub const CBLAS_UNIT: c_int = 132;
+pub const CBLAS_LEFT: c_int = 141;
+pub const CBLAS_RIGHT: c_int = 142;
+
+fn xerbla(routine: &str, param: usize) {
+    eprintln!("Parameter {param} to routine {routine} was incorrect");
+}
+
+/// Decoded `CBLAS_LAYOUT`, `true` for column major.
+fn col_major(layout: c_int) -> Option<bool> {
+    match layout {
+        CBLAS_ROW_MAJOR => Some(false),
+        CBLAS_COL_MAJOR => Some(true),
+        _ => None,
+    }
+}
+
+/// Decoded `CBLAS_TRANSPOSE`, `true` when transposed. Real matrices don't
+/// distinguish `ConjTrans` from `Trans`.
+fn transposed(trans: c_int) -> Option<bool> {
+    match trans {
+        CBLAS_NO_TRANS => Some(false),
+        CBLAS_TRANS | CBLAS_CONJ_TRANS => Some(true),
+        _ => None,
+    }
+}
+
+fn uplo(uplo: c_int) -> Option<crate::Uplo> {
+    match uplo {
+        CBLAS_UPPER => Some(crate::Uplo::Upper),
+        CBLAS_LOWER => Some(crate::Uplo::Lower),
+        _ => None,
+    }
+}
+
+fn diag(diag: c_int) -> Option<crate::Diag> {
+    match diag {
+        CBLAS_NON_UNIT => Some(crate::Diag::NonUnit),
+        CBLAS_UNIT => Some(crate::Diag::Unit),
+        _ => None,
+    }
+}
+
+fn side(side: c_int) -> Option<crate::Side> {
+    match side {
+        CBLAS_LEFT => Some(crate::Side::Left),
+        CBLAS_RIGHT => Some(crate::Side::Right),
+        _ => None,
+    }
+}
+
+/// Gathers the `(rows, cols)` matrix `x` into a contiguous row major buffer.
+/// Element `(r, c)` lives at `x[c * ld + r]` when `transposed`, and at
+/// `x[r * ld + c]` otherwise.
+unsafe fn pack<'a>(
+    x: *const f32,
+    rows: usize,
+    cols: usize,
+    ld: usize,
+    transposed: bool,
+) -> Cow<'a, [f32]> {
+    if rows == 0 || cols == 0 {
+        return Cow::Owned(vec![]);
+    }
+    if !transposed && ld == cols {
+        return Cow::Borrowed(std::slice::from_raw_parts(x, rows * cols));
+    }
+    let mut packed = Vec::with_capacity(rows * cols);
+    for r in 0..rows {
+        for c in 0..cols {
+            let idx = if transposed { c * ld + r } else { r * ld + c };
+            packed.push(*x.add(idx));
+        }
+    }
+    Cow::Owned(packed)
+}
+
+/// Writes `C = alpha * product + beta * C`, `C` is not read when `beta` is 0.
+unsafe fn write_back(
+    product: &[f32],
+    c: *mut f32,
+    m: usize,
+    n: usize,
+    ldc: usize,
+    col_major: bool,
+    alpha: f32,
+    beta: f32,
+) {
+    for i in 0..m {
+        for j in 0..n {
+            let c = c.add(if col_major { j * ldc + i } else { i * ldc + j });
+            let p = alpha * product[i * n + j];
+            *c = if beta == 0.0 { p } else { p + beta * *c };
+        }
+    }
+}
+
+/// Arguments shared by every product of a batch of GEMMs.
+pub(crate) struct GemmArgs {
+    col_major: bool,
+    trans_a: bool,
+    trans_b: bool,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    lda: usize,
+    ldb: usize,
+    beta: f32,
+    ldc: usize,
+}
+
+impl GemmArgs {
+    /// Validates the arguments the way the reference CBLAS does, `params` are
+    /// the positions of `layout, transa, transb, m, n, k, lda, ldb, ldc` in the
+    /// routine's signature.
+    pub(crate) fn new(
+        routine: &str,
+        params: [usize; 9],
+        layout: c_int,
+        transa: c_int,
+        transb: c_int,
+        (m, n, k): (c_int, c_int, c_int),
+        alpha: f32,
+        (lda, ldb, ldc): (c_int, c_int, c_int),
+        beta: f32,
+    ) -> Option<Self> {
+        let error = |i: usize| {
+            xerbla(routine, params[i]);
+            None
+        };
+        let Some(col_major) = col_major(layout) else {
+            return error(0);
+        };
+        let Some(trans_a) = transposed(transa) else {
+            return error(1);
+        };
+        let Some(trans_b) = transposed(transb) else {
+            return error(2);
+        };
+        let (Ok(m), Ok(n), Ok(k)) = (usize::try_from(m), usize::try_from(n), usize::try_from(k))
+        else {
+            return error(if m < 0 {
+                3
+            } else if n < 0 {
+                4
+            } else {
+                5
+            });
+        };
+        // Whether the stored `op(A)` and `op(B)` read as transposed row major.
+        let (a_t, b_t) = (col_major ^ trans_a, col_major ^ trans_b);
+        let min_lda = if a_t { m } else { k }.max(1);
+        let min_ldb = if b_t { k } else { n }.max(1);
+        let min_ldc = if col_major { m } else { n }.max(1);
+        let (Ok(lda), Ok(ldb), Ok(ldc)) = (
+            usize::try_from(lda),
+            usize::try_from(ldb),
+            usize::try_from(ldc),
+        ) else {
+            return error(if lda < 0 {
+                6
+            } else if ldb < 0 {
+                7
+            } else {
+                8
+            });
+        };
+        if lda < min_lda {
+            return error(6);
+        }
+        if ldb < min_ldb {
+            return error(7);
+        }
+        if ldc < min_ldc {
+            return error(8);
+        }
+        Some(Self {
+            col_major,
+            trans_a,
+            trans_b,
+            m,
+            n,
+            k,
+            alpha,
+            lda,
+            ldb,
+            beta,
+            ldc,
+        })
+    }
+
+    /// Computes every product of the batch with a single call to
+    /// [`crate::batched_sgemm_t`].
+    pub(crate) unsafe fn run(&self, a: &[*const f32], b: &[*const f32], c: &[*mut f32]) {
+        let (m, n, k) = (self.m, self.n, self.k);
+        if m == 0 || n == 0 || a.is_empty() {
+            return;
+        }
+        let batching = a.len();
+        let mut product = vec![0.0; batching * m * n];
+        if k > 0 && self.alpha != 0.0 {
+            let a_t = self.col_major ^ self.trans_a;
+            let b_t = self.col_major ^ self.trans_b;
+            let mut ap = Vec::with_capacity(batching * m * k);
+            let mut bp = Vec::with_capacity(batching * n * k);
+            for (&a, &b) in a.iter().zip(b) {
+                ap.extend_from_slice(&pack(a, m, k, self.lda, a_t));
+                // The kernel wants `op(B).T`, of shape `(n, k)`.
+                bp.extend_from_slice(&pack(b, n, k, self.ldb, !b_t));
+            }
+            crate::batched_sgemm_t(&ap, &bp, &mut product, m, n, k);
+        }
+        for (product, &c) in product.chunks_exact(m * n).zip(c) {
+            write_back(
+                product,
+                c,
+                m,
+                n,
+                self.ldc,
+                self.col_major,
+                self.alpha,
+                self.beta,
+            );
+        }
+    }
+}
+
+/// `C = alpha * op(A) * op(B) + beta * C`
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sgemm(
+    layout: c_int,
+    transa: c_int,
+    transb: c_int,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    b: *const f32,
+    ldb: c_int,
+    beta: f32,
+    c: *mut f32,
+    ldc: c_int,
+) {
+    if let Some(args) = GemmArgs::new(
+        "cblas_sgemm",
+        [1, 2, 3, 4, 5, 6, 9, 11, 14],
+        layout,
+        transa,
+        transb,
+        (m, n, k),
+        alpha,
+        (lda, ldb, ldc),
+        beta,
+    ) {
+        args.run(&[a], &[b], &[c]);
+    }
+}
+
+/// Batched GEMM with the MKL group interface: group `g` holds
+/// `group_size[g]` products sharing the arguments at index `g`, and the
+/// matrix pointers are laid out group after group.
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sgemm_batch(
+    layout: c_int,
+    transa_array: *const c_int,
+    transb_array: *const c_int,
+    m_array: *const c_int,
+    n_array: *const c_int,
+    k_array: *const c_int,
+    alpha_array: *const f32,
+    a_array: *const *const f32,
+    lda_array: *const c_int,
+    b_array: *const *const f32,
+    ldb_array: *const c_int,
+    beta_array: *const f32,
+    c_array: *const *mut f32,
+    ldc_array: *const c_int,
+    group_count: c_int,
+    group_size: *const c_int,
+) {
+    let Ok(group_count) = usize::try_from(group_count) else {
+        return xerbla("cblas_sgemm_batch", 15);
+    };
+    // Every group is checked before any of them runs, so that an invalid one
+    // leaves all the outputs untouched.
+    let mut groups = Vec::with_capacity(group_count);
+    for g in 0..group_count {
+        let Ok(size) = usize::try_from(*group_size.add(g)) else {
+            return xerbla("cblas_sgemm_batch", 16);
+        };
+        let Some(args) = GemmArgs::new(
+            "cblas_sgemm_batch",
+            [1, 2, 3, 4, 5, 6, 9, 11, 14],
+            layout,
+            *transa_array.add(g),
+            *transb_array.add(g),
+            (*m_array.add(g), *n_array.add(g), *k_array.add(g)),
+            *alpha_array.add(g),
+            (*lda_array.add(g), *ldb_array.add(g), *ldc_array.add(g)),
+            *beta_array.add(g),
+        ) else {
+            return;
+        };
+        groups.push((args, size));
+    }
+    let mut offset = 0;
+    for (args, size) in groups {
+        let a = std::slice::from_raw_parts(a_array.add(offset), size);
+        let b = std::slice::from_raw_parts(b_array.add(offset), size);
+        let c = std::slice::from_raw_parts(c_array.add(offset), size);
+        args.run(a, b, c);
+        offset += size;
+    }
+}
+
+/// Batched GEMM where the `i`-th product reads `a + i * stridea`,
+/// `b + i * strideb` and writes `c + i * stridec`.
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sgemm_batch_strided(
+    layout: c_int,
+    transa: c_int,
+    transb: c_int,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    stridea: c_int,
+    b: *const f32,
+    ldb: c_int,
+    strideb: c_int,
+    beta: f32,
+    c: *mut f32,
+    ldc: c_int,
+    stridec: c_int,
+    batch_size: c_int,
+) {
+    let Some(args) = GemmArgs::new(
+        "cblas_sgemm_batch_strided",
+        [1, 2, 3, 4, 5, 6, 9, 12, 16],
+        layout,
+        transa,
+        transb,
+        (m, n, k),
+        alpha,
+        (lda, ldb, ldc),
+        beta,
+    ) else {
+        return;
+    };
+    let Ok(stridea) = usize::try_from(stridea) else {
+        return xerbla("cblas_sgemm_batch_strided", 10);
+    };
+    let Ok(strideb) = usize::try_from(strideb) else {
+        return xerbla("cblas_sgemm_batch_strided", 13);
+    };
+    let Ok(stridec) = usize::try_from(stridec) else {
+        return xerbla("cblas_sgemm_batch_strided", 17);
+    };
+    let Ok(batch_size) = usize::try_from(batch_size) else {
+        return xerbla("cblas_sgemm_batch_strided", 18);
+    };
+    let a: Vec<_> = (0..batch_size).map(|i| a.add(i * stridea)).collect();
+    let b: Vec<_> = (0..batch_size).map(|i| b.add(i * strideb)).collect();
+    let c: Vec<_> = (0..batch_size).map(|i| c.add(i * stridec)).collect();
+    args.run(&a, &b, &c);
+}
+
+/// `y = alpha * op(A) * x + beta * y`
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sgemv(
+    layout: c_int,
+    trans: c_int,
+    m: c_int,
+    n: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    x: *const f32,
+    incx: c_int,
+    beta: f32,
+    y: *mut f32,
+    incy: c_int,
+) {
+    sgemv(
+        "cblas_sgemv",
+        0,
+        layout,
+        trans,
+        m,
+        n,
+        alpha,
+        a,
+        lda,
+        x,
+        incx,
+        beta,
+        y,
+        incy,
+    )
+}
+
+/// Shared with the Fortran interface, `shift` is the number of leading
+/// CBLAS parameters the caller doesn't have so that errors report the
+/// right position.
+pub(crate) unsafe fn sgemv(
+    routine: &str,
+    shift: usize,
+    layout: c_int,
+    trans: c_int,
+    m: c_int,
+    n: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    x: *const f32,
+    incx: c_int,
+    beta: f32,
+    y: *mut f32,
+    incy: c_int,
+) {
+    let xerbla = |param: usize| self::xerbla(routine, param - shift);
+    let Some(col_major) = col_major(layout) else {
+        return xerbla(1);
+    };
+    let Some(trans) = transposed(trans) else {
+        return xerbla(2);
+    };
+    let (Ok(m), Ok(n)) = (usize::try_from(m), usize::try_from(n)) else {
+        return xerbla(if m < 0 { 3 } else { 4 });
+    };
+    let min_lda = if col_major { m } else { n }.max(1);
+    if usize::try_from(lda)
+        .map(|lda| lda < min_lda)
+        .unwrap_or(true)
+    {
+        return xerbla(7);
+    }
+    if incx == 0 {
+        return xerbla(9);
+    }
+    if incy == 0 {
+        return xerbla(12);
+    }
+    let lda = lda as usize;
+    // `op(A)` has `leny` rows and `lenx` columns.
+    let (leny, lenx) = if trans { (n, m) } else { (m, n) };
+    if leny == 0 {
+        return;
+    }
+    let mut product = vec![0.0; leny];
+    if lenx > 0 && alpha != 0.0 {
+        let ap = pack(a, leny, lenx, lda, col_major ^ trans);
+        let xp: Vec<f32> = (0..lenx).map(|i| *x.add(offset(i, lenx, incx))).collect();
+        crate::batched_sgemm_t(&ap, &xp, &mut product, leny, 1, lenx);
+    }
+    for (i, p) in product.iter().enumerate() {
+        let y = y.add(offset(i, leny, incy));
+        *y = if beta == 0.0 {
+            alpha * p
+        } else {
+            alpha * p + beta * *y
+        };
+    }
+}
+
+/// Solves `op(A) * X = alpha * B` or `X * op(A) = alpha * B`, `X` overwrites `B`.
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_strsm(
+    layout: c_int,
+    side_: c_int,
+    uplo_: c_int,
+    transa: c_int,
+    diag_: c_int,
+    m: c_int,
+    n: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    b: *mut f32,
+    ldb: c_int,
+) {
+    strsm(
+        "cblas_strsm",
+        0,
+        layout,
+        side_,
+        uplo_,
+        transa,
+        diag_,
+        m,
+        n,
+        alpha,
+        a,
+        lda,
+        b,
+        ldb,
+    )
+}
+
+/// Shared with the Fortran interface, `shift` is the number of leading
+/// CBLAS parameters the caller doesn't have so that errors report the
+/// right position.
+pub(crate) unsafe fn strsm(
+    routine: &str,
+    shift: usize,
+    layout: c_int,
+    side_: c_int,
+    uplo_: c_int,
+    transa: c_int,
+    diag_: c_int,
+    m: c_int,
+    n: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    b: *mut f32,
+    ldb: c_int,
+) {
+    let xerbla = |param: usize| self::xerbla(routine, param - shift);
+    let Some(col_major) = col_major(layout) else {
+        return xerbla(1);
+    };
+    let Some(side) = side(side_) else {
+        return xerbla(2);
+    };
+    let Some(uplo) = uplo(uplo_) else {
+        return xerbla(3);
+    };
+    let Some(trans) = transposed(transa) else {
+        return xerbla(4);
+    };
+    let Some(diag) = diag(diag_) else {
+        return xerbla(5);
+    };
+    let (Ok(m), Ok(n)) = (usize::try_from(m), usize::try_from(n)) else {
+        return xerbla(if m < 0 { 6 } else { 7 });
+    };
+    let o = match side {
+        crate::Side::Left => m,
+        crate::Side::Right => n,
+    };
+    if usize::try_from(lda)
+        .map(|lda| lda < o.max(1))
+        .unwrap_or(true)
+    {
+        return xerbla(10);
+    }
+    let min_ldb = if col_major { m } else { n }.max(1);
+    if usize::try_from(ldb)
+        .map(|ldb| ldb < min_ldb)
+        .unwrap_or(true)
+    {
+        return xerbla(12);
+    }
+    if m == 0 || n == 0 {
+        return;
+    }
+    let (lda, ldb) = (lda as usize, ldb as usize);
+
+    let mut bp = pack(b, m, n, ldb, col_major).into_owned();
+    if alpha == 0.0 {
+        bp.fill(0.0);
+    } else {
+        if alpha != 1.0 {
+            crate::sscal(bp.len(), alpha, &mut bp, 1);
+        }
+        let ap = pack(a, o, o, lda, col_major ^ trans);
+        // Transposing swaps the stored triangle.
+        let uplo = match (uplo, trans) {
+            (uplo, false) => uplo,
+            (crate::Uplo::Upper, true) => crate::Uplo::Lower,
+            (crate::Uplo::Lower, true) => crate::Uplo::Upper,
+        };
+        crate::strsm(side, uplo, diag, &ap, &mut bp, m, n);
+    }
+    write_back(&bp, b, m, n, ldb, col_major, 1.0, 0.0);
+}
+
+/// Index of the `i`-th element of a BLAS vector of `n` elements, negative
+/// increments walk the buffer backwards.
+fn offset(i: usize, n: usize, inc: c_int) -> usize {
+    let stride = inc.unsigned_abs() as usize;
+    if inc > 0 {
+        i * stride
+    } else {
+        (n - 1 - i) * stride
+    }
+}
+
+/// Views a BLAS vector with a positive increment as a slice for the level-1
+/// routines.
+unsafe fn vector<'a>(x: *const f32, n: usize, inc: c_int) -> &'a [f32] {
+    std::slice::from_raw_parts(x, (n - 1) * inc as usize + 1)
+}
+
+unsafe fn vector_mut<'a>(x: *mut f32, n: usize, inc: c_int) -> &'a mut [f32] {
+    std::slice::from_raw_parts_mut(x, (n - 1) * inc as usize + 1)
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sdot(
+    n: c_int,
+    x: *const f32,
+    incx: c_int,
+    y: *const f32,
+    incy: c_int,
+) -> f32 {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return 0.0;
+    };
+    if incx > 0 && incy > 0 {
+        let (xs, ys) = (vector(x, n, incx), vector(y, n, incy));
+        crate::sdot(n, xs, incx as usize, ys, incy as usize)
+    } else {
+        (0..n)
+            .map(|i| *x.add(offset(i, n, incx)) * *y.add(offset(i, n, incy)))
+            .sum()
+    }
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_saxpy(
+    n: c_int,
+    alpha: f32,
+    x: *const f32,
+    incx: c_int,
+    y: *mut f32,
+    incy: c_int,
+) {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return;
+    };
+    if alpha == 0.0 {
+        return;
+    }
+    if incx > 0 && incy > 0 {
+        let (xs, ys) = (vector(x, n, incx), vector_mut(y, n, incy));
+        crate::saxpy(n, alpha, xs, incx as usize, ys, incy as usize)
+    } else {
+        (0..n).for_each(|i| *y.add(offset(i, n, incy)) += alpha * *x.add(offset(i, n, incx)));
+    }
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sscal(n: c_int, alpha: f32, x: *mut f32, incx: c_int) {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return;
+    };
+    if incx > 0 {
+        crate::sscal(n, alpha, vector_mut(x, n, incx), incx as usize)
+    }
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_snrm2(n: c_int, x: *const f32, incx: c_int) -> f32 {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return 0.0;
+    };
+    if incx > 0 {
+        crate::snrm2(n, vector(x, n, incx), incx as usize)
+    } else {
+        0.0
+    }
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sasum(n: c_int, x: *const f32, incx: c_int) -> f32 {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return 0.0;
+    };
+    if incx > 0 {
+        crate::sasum(n, vector(x, n, incx), incx as usize)
+    } else {
+        0.0
+    }
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_isamax(n: c_int, x: *const f32, incx: c_int) -> usize {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return 0;
+    };
+    if incx > 0 {
+        crate::isamax(n, vector(x, n, incx), incx as usize).unwrap_or(0)
+    } else {
+        0
+    }
+}
+
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any CBLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn cblas_scopy(
+    n: c_int,
+    x: *const f32,
+    incx: c_int,
+    y: *mut f32,
+    incy: c_int,
+) {
+    let Ok(n @ 1..) = usize::try_from(n) else {
+        return;
+    };
+    if incx > 0 && incy > 0 {
+        let (xs, ys) = (vector(x, n, incx), vector_mut(y, n, incy));
+        crate::scopy(n, xs, incx as usize, ys, incy as usize)
+    } else {
+        (0..n).for_each(|i| *y.add(offset(i, n, incy)) = *x.add(offset(i, n, incx)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every exported function is declared in the header.
+    #[test]
+    fn header_is_complete() {
+        let header = include_str!("../include/ggblas.h");
+        let source = [include_str!("capi.rs"), include_str!("capi/fortran.rs")].concat();
+        let exported: Vec<&str> = source
+            .split("pub unsafe extern \"C\" fn ")
+            .skip(1)
+            .map(|s| &s[..s.find('(').unwrap()])
+            .collect();
+        assert!(exported.len() > 10);
+        for name in exported {
+            assert!(header.contains(&format!(" {name}(")), "{name} missing");
+        }
+    }
+
+    /// Naive `C = alpha * op(A) * op(B) + beta * C` on logical accessors.
+    fn reference(
+        m: usize,
+        n: usize,
+        k: usize,
+        a: impl Fn(usize, usize) -> f32,
+        b: impl Fn(usize, usize) -> f32,
+        alpha: f32,
+        beta: f32,
+        c: impl Fn(usize, usize) -> f32,
+    ) -> Vec<f32> {
+        let mut out = vec![0.0; m * n];
+        for i in 0..m {
+            for j in 0..n {
+                let p: f32 = (0..k).map(|l| a(i, l) * b(l, j)).sum();
+                out[i * n + j] = alpha * p + beta * c(i, j);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn sgemm_layouts() {
+        let (m, n, k) = (5, 7, 9);
+        // Padded leading dimensions.
+        let (lda, ldb, ldc) = (11, 13, 17);
+        let a: Vec<f32> = (0..lda * 11).map(|i| (i % 7) as f32 - 3.0).collect();
+        let b: Vec<f32> = (0..ldb * 13).map(|i| (i % 5) as f32 - 2.0).collect();
+        let c0: Vec<f32> = (0..ldc * 17).map(|i| (i % 3) as f32).collect();
+        for layout in [CBLAS_ROW_MAJOR, CBLAS_COL_MAJOR] {
+            for transa in [CBLAS_NO_TRANS, CBLAS_TRANS] {
+                for transb in [CBLAS_NO_TRANS, CBLAS_TRANS] {
+                    let col = layout == CBLAS_COL_MAJOR;
+                    let at = col ^ (transa == CBLAS_TRANS);
+                    let bt = col ^ (transb == CBLAS_TRANS);
+                    let ga = |i: usize, l: usize| if at { a[l * lda + i] } else { a[i * lda + l] };
+                    let gb = |l: usize, j: usize| if bt { b[j * ldb + l] } else { b[l * ldb + j] };
+                    let ci = |i: usize, j: usize| if col { j * ldc + i } else { i * ldc + j };
+                    let expected = reference(m, n, k, ga, gb, 2.0, 0.5, |i, j| c0[ci(i, j)]);
+
+                    let mut c = c0.clone();
+                    unsafe {
+                        cblas_sgemm(
+                            layout,
+                            transa,
+                            transb,
+                            m as c_int,
+                            n as c_int,
+                            k as c_int,
+                            2.0,
+                            a.as_ptr(),
+                            lda as c_int,
+                            b.as_ptr(),
+                            ldb as c_int,
+                            0.5,
+                            c.as_mut_ptr(),
+                            ldc as c_int,
+                        )
+                    };
+                    for i in 0..m {
+                        for j in 0..n {
+                            assert_eq!(c[ci(i, j)], expected[i * n + j]);
+                        }
+                    }
+                    // Padding is left untouched.
+                    let written = (0..m * n).map(|x| ci(x / n, x % n)).collect::<Vec<_>>();
+                    for (idx, (c, c0)) in c.iter().zip(&c0).enumerate() {
+                        if !written.contains(&idx) {
+                            assert_eq!(c, c0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let mut c = vec![f32::NAN; 4];
+        unsafe {
+            // lda too small for a (2, 2) row major A.
+            cblas_sgemm(
+                CBLAS_ROW_MAJOR,
+                CBLAS_NO_TRANS,
+                CBLAS_NO_TRANS,
+                2,
+                2,
+                2,
+                1.0,
+                [0.0; 4].as_ptr(),
+                1,
+                [0.0; 4].as_ptr(),
+                2,
+                0.0,
+                c.as_mut_ptr(),
+                2,
+            )
+        };
+        assert!(c.iter().all(|c| c.is_nan()));
+    }
+
+    #[test]
+    fn invalid_group() {
+        let a = [1.0; 4];
+        let mut c0 = [f32::NAN; 4];
+        let mut c1 = [f32::NAN; 4];
+        let a_array = [a.as_ptr(), a.as_ptr()];
+        let c_array = [c0.as_mut_ptr(), c1.as_mut_ptr()];
+        unsafe {
+            // The second group has a negative m.
+            cblas_sgemm_batch(
+                CBLAS_ROW_MAJOR,
+                [CBLAS_NO_TRANS; 2].as_ptr(),
+                [CBLAS_NO_TRANS; 2].as_ptr(),
+                [2, -2].as_ptr(),
+                [2; 2].as_ptr(),
+                [2; 2].as_ptr(),
+                [1.0; 2].as_ptr(),
+                a_array.as_ptr(),
+                [2; 2].as_ptr(),
+                a_array.as_ptr(),
+                [2; 2].as_ptr(),
+                [0.0; 2].as_ptr(),
+                c_array.as_ptr(),
+                [2; 2].as_ptr(),
+                2,
+                [1; 2].as_ptr(),
+            )
+        };
+        assert!(c0.iter().chain(&c1).all(|c| c.is_nan()));
+    }
+
+    #[test]
+    fn level1_negative_increments() {
+        let x = [1.0, 2.0, 3.0];
+        let mut y = [0.0; 3];
+        unsafe {
+            assert_eq!(cblas_sdot(3, x.as_ptr(), -1, x.as_ptr(), 1), 10.0);
+            cblas_scopy(3, x.as_ptr(), 1, y.as_mut_ptr(), -1);
+            assert_eq!(y, [3.0, 2.0, 1.0]);
+            cblas_saxpy(3, 1.0, x.as_ptr(), -1, y.as_mut_ptr(), 1);
+            assert_eq!(y, [6.0, 4.0, 2.0]);
+            assert_eq!(cblas_isamax(3, y.as_ptr(), 1), 0);
+            let z = [1.0, f32::NAN, 0.5];
+            assert_eq!(cblas_isamax(3, z.as_ptr(), 1), 1);
+        }
+    }
+}
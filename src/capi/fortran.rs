@@ -0,0 +1,144 @@
+//! Fortran BLAS symbols, so that ggblas can be the BLAS provider of a LAPACK
+//! build.
+//!
+//! Everything is column major and passed by pointer, with the usual trailing
+//! underscore. Compilers like gfortran append the lengths of the character
+//! arguments after the regular ones, those are never read and can be omitted
+//! by C callers.
+use super::{
+    GemmArgs, CBLAS_COL_MAJOR, CBLAS_CONJ_TRANS, CBLAS_LEFT, CBLAS_LOWER, CBLAS_NON_UNIT,
+    CBLAS_NO_TRANS, CBLAS_RIGHT, CBLAS_TRANS, CBLAS_UNIT, CBLAS_UPPER,
+};
+use std::ffi::{c_char, c_int};
+
+/// Maps a Fortran character flag to its CBLAS value, unknown flags map to 0
+/// which every routine rejects.
+unsafe fn flag(c: *const c_char, values: &[(u8, c_int)]) -> c_int {
+    let c = (*c as u8).to_ascii_uppercase();
+    values
+        .iter()
+        .find(|(k, _)| *k == c)
+        .map(|(_, v)| *v)
+        .unwrap_or(0)
+}
+
+unsafe fn trans(c: *const c_char) -> c_int {
+    flag(
+        c,
+        &[
+            (b'N', CBLAS_NO_TRANS),
+            (b'T', CBLAS_TRANS),
+            (b'C', CBLAS_CONJ_TRANS),
+        ],
+    )
+}
+
+/// `C = alpha * op(A) * op(B) + beta * C`
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any BLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn sgemm_(
+    transa: *const c_char,
+    transb: *const c_char,
+    m: *const c_int,
+    n: *const c_int,
+    k: *const c_int,
+    alpha: *const f32,
+    a: *const f32,
+    lda: *const c_int,
+    b: *const f32,
+    ldb: *const c_int,
+    beta: *const f32,
+    c: *mut f32,
+    ldc: *const c_int,
+) {
+    if let Some(args) = GemmArgs::new(
+        "SGEMM ",
+        [0, 1, 2, 3, 4, 5, 8, 10, 13],
+        CBLAS_COL_MAJOR,
+        trans(transa),
+        trans(transb),
+        (*m, *n, *k),
+        *alpha,
+        (*lda, *ldb, *ldc),
+        *beta,
+    ) {
+        args.run(&[a], &[b], &[c]);
+    }
+}
+
+/// `y = alpha * op(A) * x + beta * y`
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any BLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn sgemv_(
+    trans_: *const c_char,
+    m: *const c_int,
+    n: *const c_int,
+    alpha: *const f32,
+    a: *const f32,
+    lda: *const c_int,
+    x: *const f32,
+    incx: *const c_int,
+    beta: *const f32,
+    y: *mut f32,
+    incy: *const c_int,
+) {
+    super::sgemv(
+        "SGEMV ",
+        1,
+        CBLAS_COL_MAJOR,
+        trans(trans_),
+        *m,
+        *n,
+        *alpha,
+        a,
+        *lda,
+        x,
+        *incx,
+        *beta,
+        y,
+        *incy,
+    )
+}
+
+/// Solves `op(A) * X = alpha * B` or `X * op(A) = alpha * B`, `X` overwrites `B`.
+///
+/// # Safety
+/// The pointers must be valid for the sizes given by the other arguments, as
+/// for any BLAS implementation.
+#[no_mangle]
+pub unsafe extern "C" fn strsm_(
+    side: *const c_char,
+    uplo: *const c_char,
+    transa: *const c_char,
+    diag: *const c_char,
+    m: *const c_int,
+    n: *const c_int,
+    alpha: *const f32,
+    a: *const f32,
+    lda: *const c_int,
+    b: *mut f32,
+    ldb: *const c_int,
+) {
+    super::strsm(
+        "STRSM ",
+        1,
+        CBLAS_COL_MAJOR,
+        flag(side, &[(b'L', CBLAS_LEFT), (b'R', CBLAS_RIGHT)]),
+        flag(uplo, &[(b'U', CBLAS_UPPER), (b'L', CBLAS_LOWER)]),
+        trans(transa),
+        flag(diag, &[(b'N', CBLAS_NON_UNIT), (b'U', CBLAS_UNIT)]),
+        *m,
+        *n,
+        *alpha,
+        a,
+        *lda,
+        b,
+        *ldb,
+    )
+}
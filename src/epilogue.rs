@@ -0,0 +1,109 @@
+//! Element-wise operations fused into the GEMM kernels.
+//!
+//! Applying them while the output is still hot avoids a second pass over `C`
+//! for the usual `act(x * W.T + b) + residual` pattern.
+
+/// Activation function applied after the bias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Activation {
+    #[default]
+    Identity,
+    Relu,
+    /// Tanh approximation, as used by ggml.
+    Gelu,
+    Silu,
+}
+
+/// Bias broadcast over the output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bias<'a> {
+    /// One value per column, `bias[j]` is added to `C[.., i, j]`.
+    N(&'a [f32]),
+    /// One value per row, `bias[i]` is added to `C[.., i, j]`.
+    M(&'a [f32]),
+}
+
+/// Computes, for every output element,
+///
+/// ```latex
+/// C = activation(alpha * acc + bias) + residual
+/// ```
+///
+/// where `acc` is the freshly computed dot product. The bias is shared by
+/// every batch, while the residual has the same (batched) shape as `C`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Epilogue<'a> {
+    pub alpha: f32,
+    pub bias: Option<Bias<'a>>,
+    pub activation: Activation,
+    pub residual: Option<&'a [f32]>,
+}
+
+impl Default for Epilogue<'_> {
+    fn default() -> Self {
+        Self {
+            alpha: 1.0,
+            bias: None,
+            activation: Activation::Identity,
+            residual: None,
+        }
+    }
+}
+
+const GELU_COEF_A: f32 = 0.044715;
+const SQRT_2_OVER_PI: f32 = 0.797_884_6;
+
+impl Activation {
+    /// NaN goes through every activation, infinities are mapped to their
+    /// limits.
+    #[inline]
+    pub fn apply(&self, x: f32) -> f32 {
+        match self {
+            Activation::Identity => x,
+            // Unlike `f32::max`, keeps NaN.
+            Activation::Relu => {
+                if x > 0.0 || x.is_nan() {
+                    x
+                } else {
+                    0.0
+                }
+            }
+            // Both tend to -0.0, the formulas give `inf / inf` and `inf * 0`.
+            Activation::Gelu | Activation::Silu if x == f32::NEG_INFINITY => -0.0,
+            Activation::Gelu => {
+                0.5 * x * (1.0 + (SQRT_2_OVER_PI * x * (1.0 + GELU_COEF_A * x * x)).tanh())
+            }
+            Activation::Silu => x / (1.0 + (-x).exp()),
+        }
+    }
+}
+
+impl Epilogue<'_> {
+    /// Panics if the bias or residual don't match the output shape.
+    pub(crate) fn check(&self, m: usize, n: usize, c_len: usize) {
+        match self.bias {
+            Some(Bias::N(bias)) => assert_eq!(bias.len(), n),
+            Some(Bias::M(bias)) => assert_eq!(bias.len(), m),
+            None => {}
+        }
+        if let Some(residual) = self.residual {
+            assert_eq!(residual.len(), c_len);
+        }
+    }
+
+    /// `acc` is the value for row `i` and column `j`, stored at `C[c_index]`.
+    #[inline]
+    pub(crate) fn apply(&self, acc: f32, i: usize, j: usize, c_index: usize) -> f32 {
+        let mut v = self.alpha * acc;
+        match self.bias {
+            Some(Bias::N(bias)) => v += bias[j],
+            Some(Bias::M(bias)) => v += bias[i],
+            None => {}
+        }
+        v = self.activation.apply(v);
+        if let Some(residual) = self.residual {
+            v += residual[c_index];
+        }
+        v
+    }
+}
@@ -0,0 +1,153 @@
+//! [faer](https://docs.rs/faer/) integration.
+//!
+//! Any [`MatRef`] can be multiplied, whatever its strides. Contiguous
+//! matrices, column major like faer's own or row major like transposes of
+//! them, are read in place, other views are copied first.
+//!
+//! The `faer-rs` feature is about the older `faer-core` crate, see
+//! `backend::FaerCore`.
+//!
+//! ```
+//! use faer::mat;
+//!
+//! let a = mat![[1., 2.], [3., 4.]];
+//! let b = mat![[1., 2.], [3., 4.]];
+//! let c = ggblas::faer::matmul(a.as_ref(), b.transpose());
+//! assert_eq!(c, mat![[5., 11.], [11., 25.]]);
+//! ```
+use crate::strided::{self, Strided};
+use ::faer::{Mat, MatMut, MatRef};
+
+fn strided<P>(ptr: P, rows: usize, cols: usize, strides: (isize, isize)) -> Strided<P> {
+    Strided {
+        ptr,
+        dim: (1, rows, cols),
+        strides: (0, strides.0, strides.1),
+    }
+}
+
+/// Computes matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// with `A` of shape `(m, k)`, `B` of shape `(k, n)` and `C` of shape
+/// `(m, n)`. `C` is overwritten.
+///
+/// ```
+/// use faer::{mat, Mat};
+///
+/// let a = mat![[1., 2.], [3., 4.]];
+/// let mut c = Mat::zeros(2, 2);
+///
+/// ggblas::faer::sgemm(a.as_ref(), a.as_ref(), c.as_mut().transpose_mut());
+/// assert_eq!(c, mat![[7., 15.], [10., 22.]]);
+/// ```
+pub fn sgemm(a: MatRef<'_, f32>, b: MatRef<'_, f32>, c: MatMut<'_, f32>) {
+    // The borrows guarantee `c` does not alias the inputs.
+    unsafe {
+        strided::sgemm(
+            strided(
+                a.as_ptr(),
+                a.nrows(),
+                a.ncols(),
+                (a.row_stride(), a.col_stride()),
+            ),
+            strided(
+                b.as_ptr(),
+                b.nrows(),
+                b.ncols(),
+                (b.row_stride(), b.col_stride()),
+            ),
+            strided(
+                c.as_ptr_mut(),
+                c.nrows(),
+                c.ncols(),
+                (c.row_stride(), c.col_stride()),
+            ),
+        )
+    }
+}
+
+/// Computes `A * B` into a new matrix, see [`sgemm`].
+pub fn matmul(a: MatRef<'_, f32>, b: MatRef<'_, f32>) -> Mat<f32> {
+    let mut c = Mat::zeros(a.nrows(), b.ncols());
+    sgemm(a, b, c.as_mut());
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(rows: usize, cols: usize, seed: usize) -> Mat<f32> {
+        Mat::from_fn(rows, cols, |i, j| {
+            ((i * 7919 + j * 31 + seed) % 61) as f32 / 16.0 - 1.875
+        })
+    }
+
+    fn reference(a: MatRef<'_, f32>, b: MatRef<'_, f32>) -> Mat<f32> {
+        Mat::from_fn(a.nrows(), b.ncols(), |i, j| {
+            (0..a.ncols()).map(|l| a[(i, l)] * b[(l, j)]).sum()
+        })
+    }
+
+    fn close(a: MatRef<'_, f32>, b: MatRef<'_, f32>) {
+        assert_eq!((a.nrows(), a.ncols()), (b.nrows(), b.ncols()));
+        for i in 0..a.nrows() {
+            for j in 0..a.ncols() {
+                let (x, y) = (a[(i, j)], b[(i, j)]);
+                assert!(
+                    (x - y).abs() <= 1e-4 * x.abs().max(y.abs()).max(1.0),
+                    "{i} {j} {x} {y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn views() {
+        let (m, n, k) = (13, 17, 37);
+        let a = data(m, k, 1);
+        let b = data(k, n, 2);
+        let a_t = data(k, m, 3);
+        let b_t = data(n, k, 4);
+        for a in [a.as_ref(), a_t.transpose()] {
+            for b in [b.as_ref(), b_t.transpose()] {
+                let expected = reference(a, b);
+                close(matmul(a, b).as_ref(), expected.as_ref());
+
+                let mut c = Mat::from_fn(n, m, |_, _| f32::NAN);
+                sgemm(a, b, c.as_mut().transpose_mut());
+                close(c.transpose(), expected.as_ref());
+            }
+        }
+
+        // Stepped and reversed views.
+        let big = data(2 * m, k, 5);
+        let a = big.as_ref().subrows(0, 2 * m);
+        let a =
+            unsafe { MatRef::from_raw_parts(a.as_ptr(), m, k, 2 * a.row_stride(), a.col_stride()) };
+        let b = b.as_ref().reverse_rows();
+        let expected = reference(a, b);
+        let mut big_c = Mat::from_fn(m, 2 * n, |_, _| f32::NAN);
+        let c = unsafe {
+            MatMut::from_raw_parts_mut(
+                big_c.as_ptr_mut(),
+                m,
+                n,
+                big_c.row_stride(),
+                2 * big_c.col_stride(),
+            )
+        };
+        sgemm(a, b, c);
+        for j in 0..n {
+            for i in 0..m {
+                let (x, y) = (big_c[(i, 2 * j)], expected[(i, j)]);
+                assert!((x - y).abs() <= 1e-4 * y.abs().max(1.0));
+                assert!(big_c[(i, 2 * j + 1)].is_nan());
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Dot products trading speed for accuracy.
+//!
+//! With `u = 2^-24` the unit roundoff of `f32` and `S = sum(|a[i] * b[i]|)`,
+//! the result `r` of each kernel satisfies, up to second order terms:
+//!
+//! | kernel                   | bound on `abs(r - a.b)`                    |
+//! |--------------------------|--------------------------------------------|
+//! | [`super::vec_dot_f32`]   | `k * u * S`                                |
+//! | [`vec_dot_f32_pairwise`] | `(BLOCK + log2(k / BLOCK) + 1) * u * S`    |
+//! | [`vec_dot_f32_comp`]     | `u * abs(a.b) + (k * u)^2 * S`             |
+//! | [`vec_dot_f32_f64`]      | `u * abs(a.b) + k * 2^-53 * S`             |
+//! | [`vec_dot_f32_det`]      | `(k / LANES + log2(LANES) + 1) * u * S`    |
+//!
+//! The compensated and `f64` kernels are within one rounding of the exact
+//! result unless the sum cancels catastrophically.
+//!
+//! All but the pairwise kernel only use scalar operations with a fixed
+//! evaluation order, their results are bitwise identical on every target
+//! whatever its SIMD width.
+use super::vec_dot_f32;
+
+/// Size of the blocks summed directly by [`vec_dot_f32_pairwise`], a
+/// multiple of every SIMD step.
+pub const BLOCK: usize = 256;
+
+unsafe fn pairwise(a_row: *const f32, b_row: *const f32, k: usize) -> f32 {
+    if k <= BLOCK {
+        let mut c = 0.0;
+        vec_dot_f32(a_row, b_row, &mut c, k);
+        return c;
+    }
+    let half = (k / 2).next_multiple_of(BLOCK);
+    pairwise(a_row, b_row, half) + pairwise(a_row.add(half), b_row.add(half), k - half)
+}
+
+/// Computes `sum(a[i] * b[i])` by summing blocks of [`BLOCK`] elements with
+/// the regular kernel and adding the blocks pairwise.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_f32_pairwise(a_row: *const f32, b_row: *const f32, c: *mut f32, k: usize) {
+    *c = pairwise(a_row, b_row, k);
+}
+
+/// Computes `sum(a[i] * b[i])` with compensated summation (the `Dot2`
+/// algorithm of Ogita, Rump and Oishi): the rounding errors of every product
+/// and addition are accumulated separately and added back at the end, as if
+/// computed in twice the working precision.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_f32_comp(a_row: *const f32, b_row: *const f32, c: *mut f32, k: usize) {
+    let a = std::slice::from_raw_parts(a_row, k);
+    let b = std::slice::from_raw_parts(b_row, k);
+    let (mut sum, mut err) = (0.0f32, 0.0f32);
+    for (&a, &b) in a.iter().zip(b) {
+        let p = a * b;
+        let p_err = a.mul_add(b, -p);
+        let t = sum + p;
+        let z = t - sum;
+        let t_err = (sum - (t - z)) + (p - z);
+        sum = t;
+        err += p_err + t_err;
+    }
+    // Past an infinity the error terms are NaN, the plain sum is the result.
+    *c = if sum.is_finite() { sum + err } else { sum };
+}
+
+/// Computes `sum(a[i] * b[i])` in `f64`, where the products are exact, and
+/// rounds once at the end.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_f32_f64(a_row: *const f32, b_row: *const f32, c: *mut f32, k: usize) {
+    let a = std::slice::from_raw_parts(a_row, k);
+    let b = std::slice::from_raw_parts(b_row, k);
+    // Independent accumulators so that the loop vectorizes.
+    let mut sums = [0.0f64; 4];
+    let (a_chunks, b_chunks) = (a.chunks_exact(4), b.chunks_exact(4));
+    let (a_rest, b_rest) = (a_chunks.remainder(), b_chunks.remainder());
+    for (a, b) in a_chunks.zip(b_chunks) {
+        for (sum, (&a, &b)) in sums.iter_mut().zip(a.iter().zip(b)) {
+            *sum += a as f64 * b as f64;
+        }
+    }
+    let rest: f64 = a_rest
+        .iter()
+        .zip(b_rest)
+        .map(|(&a, &b)| a as f64 * b as f64)
+        .sum();
+    *c = ((sums[0] + sums[1]) + (sums[2] + sums[3]) + rest) as f32;
+}
+
+/// Number of accumulators of [`vec_dot_f32_det`], fixed whatever the SIMD
+/// width.
+pub const LANES: usize = 16;
+
+/// Computes `sum(a[i] * b[i])` with a fixed evaluation order: element `i`
+/// goes to accumulator `i % LANES` through a fused multiply-add, and the
+/// accumulators are then added pairwise. The loop vectorizes, but the result
+/// never depends on the target.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_f32_det(a_row: *const f32, b_row: *const f32, c: *mut f32, k: usize) {
+    let a = std::slice::from_raw_parts(a_row, k);
+    let b = std::slice::from_raw_parts(b_row, k);
+    let mut acc = [0.0f32; LANES];
+    let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (a_rest, b_rest) = (a_chunks.remainder(), b_chunks.remainder());
+    for (a, b) in a_chunks.zip(b_chunks) {
+        for (acc, (&a, &b)) in acc.iter_mut().zip(a.iter().zip(b)) {
+            *acc = a.mul_add(b, *acc);
+        }
+    }
+    for (acc, (&a, &b)) in acc.iter_mut().zip(a_rest.iter().zip(b_rest)) {
+        *acc = a.mul_add(b, *acc);
+    }
+    let mut width = LANES;
+    while width > 1 {
+        width /= 2;
+        for l in 0..width {
+            acc[l] += acc[l + width];
+        }
+    }
+    *c = acc[0];
+}
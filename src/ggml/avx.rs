@@ -41,6 +41,14 @@ impl Cpu<ARR> for CurrentCpu {
         _mm256_add_ps(_mm256_mul_ps(b, c), a)
     }
 
+    unsafe fn vec_abs(a: Self::Unit) -> Self::Unit {
+        _mm256_andnot_ps(_mm256_set1_ps(-0.0), a)
+    }
+
+    unsafe fn vec_swap_pairs(a: Self::Unit) -> Self::Unit {
+        _mm256_permute_ps(a, 0b10_11_00_01)
+    }
+
     unsafe fn vec_store(mem_addr: *mut f32, a: Self::Unit) {
         _mm256_storeu_ps(mem_addr, a);
     }
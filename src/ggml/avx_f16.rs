@@ -51,6 +51,10 @@ impl CpuF16<ARR> for CurrentCpuF16 {
         _mm256_add_ps(_mm256_mul_ps(b, c), a)
     }
 
+    unsafe fn vec_abs(a: Self::Unit) -> Self::Unit {
+        _mm256_andnot_ps(_mm256_set1_ps(-0.0), a)
+    }
+
     #[cfg(target_feature = "f16c")]
     unsafe fn vec_store(mem_addr: *mut f16, a: Self::Unit) {
         _mm_storeu_si128(mem_addr as *mut __m128i, _mm256_cvtps_ph(a, 0))
@@ -0,0 +1,321 @@
+use super::k_quants::{BlockQ4K, BlockQ5K, BlockQ6K, BlockQ8K, K_SCALE_SIZE, QK_K};
+use super::quants::{BlockQ4_0, BlockQ8_0};
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[inline(always)]
+pub(crate) unsafe fn hsum_float_8(x: __m256) -> f32 {
+    let mut res = _mm256_extractf128_ps(x, 1);
+    res = _mm_add_ps(res, _mm256_castps256_ps128(x));
+    res = _mm_add_ps(res, _mm_movehl_ps(res, res));
+    res = _mm_add_ss(res, _mm_movehdup_ps(res));
+    _mm_cvtss_f32(res)
+}
+
+/// Spreads 32 packed nibbles into 32 bytes: low nibbles first, then high nibbles.
+#[inline(always)]
+pub(crate) unsafe fn bytes_from_nibbles_32(rsi: *const u8) -> __m256i {
+    let tmp = _mm_loadu_si128(rsi as *const __m128i);
+    let bytes = _mm256_insertf128_si256(_mm256_castsi128_si256(tmp), _mm_srli_epi16(tmp, 4), 1);
+    let low_mask = _mm256_set1_epi8(0xF);
+    _mm256_and_si256(low_mask, bytes)
+}
+
+/// Multiplies signed bytes pairwise and sums adjacent products into 8 floats.
+#[inline(always)]
+pub(crate) unsafe fn mul_sum_i8_pairs_float(x: __m256i, y: __m256i) -> __m256 {
+    // `maddubs` wants its first operand unsigned, so move the sign of x onto y.
+    let ax = _mm256_sign_epi8(x, x);
+    let sy = _mm256_sign_epi8(y, x);
+    let dot = _mm256_maddubs_epi16(ax, sy);
+    let ones = _mm256_set1_epi16(1);
+    let summed_pairs = _mm256_madd_epi16(ones, dot);
+    _mm256_cvtepi32_ps(summed_pairs)
+}
+
+#[inline(never)]
+pub(crate) unsafe fn vec_dot_q4_0_q8_0(xs: &[BlockQ4_0], ys: &[BlockQ8_0]) -> f32 {
+    let mut acc = _mm256_setzero_ps();
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let d = _mm256_set1_ps(x.d.to_f32() * y.d.to_f32());
+        let bx = bytes_from_nibbles_32(x.qs.as_ptr());
+        let off = _mm256_set1_epi8(8);
+        let bx = _mm256_sub_epi8(bx, off);
+        let by = _mm256_loadu_si256(y.qs.as_ptr() as *const __m256i);
+        let q = mul_sum_i8_pairs_float(bx, by);
+        acc = _mm256_add_ps(_mm256_mul_ps(d, q), acc);
+    }
+    hsum_float_8(acc)
+}
+
+#[inline(never)]
+pub(crate) unsafe fn vec_dot_q8_0_q8_0(xs: &[BlockQ8_0], ys: &[BlockQ8_0]) -> f32 {
+    let mut acc = _mm256_setzero_ps();
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let d = _mm256_set1_ps(x.d.to_f32() * y.d.to_f32());
+        let bx = _mm256_loadu_si256(x.qs.as_ptr() as *const __m256i);
+        let by = _mm256_loadu_si256(y.qs.as_ptr() as *const __m256i);
+        let q = mul_sum_i8_pairs_float(bx, by);
+        acc = _mm256_add_ps(_mm256_mul_ps(d, q), acc);
+    }
+    hsum_float_8(acc)
+}
+
+const KMASK1: u32 = 0x3f3f3f3f;
+const KMASK2: u32 = 0x0f0f0f0f;
+const KMASK3: u32 = 0x03030303;
+
+/// Rearranges the 12 packed bytes of a Q4_K/Q5_K block into 8 scales
+/// followed by 8 mins, widened to 16 bits.
+#[inline(always)]
+unsafe fn mins_and_scales_k4(scales: &[u8; K_SCALE_SIZE]) -> __m256i {
+    let mut utmp = [0u32; 4];
+    std::ptr::copy_nonoverlapping(scales.as_ptr(), utmp.as_mut_ptr() as *mut u8, K_SCALE_SIZE);
+    utmp[3] = ((utmp[2] >> 4) & KMASK2) | (((utmp[1] >> 6) & KMASK3) << 4);
+    let uaux = utmp[1] & KMASK1;
+    utmp[1] = (utmp[2] & KMASK2) | (((utmp[0] >> 6) & KMASK3) << 4);
+    utmp[2] = uaux;
+    utmp[0] &= KMASK1;
+    _mm256_cvtepu8_epi16(_mm_set_epi32(
+        utmp[3] as i32,
+        utmp[2] as i32,
+        utmp[1] as i32,
+        utmp[0] as i32,
+    ))
+}
+
+/// `sum_j mins[j] * (bsums[2j] + bsums[2j + 1])` for the 8 sub-blocks.
+#[inline(always)]
+unsafe fn mins_dot_bsums(mins_and_scales: __m256i, y: &BlockQ8K) -> __m128i {
+    let q8sums = _mm256_loadu_si256(y.bsums.as_ptr() as *const __m256i);
+    let q8s = _mm_hadd_epi16(
+        _mm256_extracti128_si256(q8sums, 0),
+        _mm256_extracti128_si256(q8sums, 1),
+    );
+    _mm_madd_epi16(_mm256_extracti128_si256(mins_and_scales, 1), q8s)
+}
+
+/// Broadcasts the 16 bits scale `i` to every 16 bits lane.
+#[inline(always)]
+unsafe fn get_scale_shuffle_k4(i: usize) -> __m256i {
+    _mm256_set1_epi16(((2 * i) | ((2 * i + 1) << 8)) as i16)
+}
+
+/// Broadcasts the 8 bits scale `2i` to the first 8 bytes and `2i + 1` to the last 8.
+#[inline(always)]
+unsafe fn get_scale_shuffle(i: usize) -> __m128i {
+    let lo = (2 * i) as i64 * 0x0101010101010101;
+    let hi = (2 * i + 1) as i64 * 0x0101010101010101;
+    _mm_set_epi64x(hi, lo)
+}
+
+#[inline(never)]
+pub(crate) unsafe fn vec_dot_q4_k_q8_k(xs: &[BlockQ4K], ys: &[BlockQ8K]) -> f32 {
+    let m4 = _mm256_set1_epi8(0xF);
+    let mut acc = _mm256_setzero_ps();
+    let mut acc_m = _mm_setzero_ps();
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let d = y.d * x.d.to_f32();
+        let dmin = -y.d * x.dmin.to_f32();
+
+        let mins_and_scales = mins_and_scales_k4(&x.scales);
+        let prod = mins_dot_bsums(mins_and_scales, y);
+        acc_m = _mm_add_ps(_mm_mul_ps(_mm_set1_ps(dmin), _mm_cvtepi32_ps(prod)), acc_m);
+
+        let sc128 = _mm256_extracti128_si256(mins_and_scales, 0);
+        let scales = _mm256_set_m128i(sc128, sc128);
+
+        let mut q4 = x.qs.as_ptr();
+        let mut q8 = y.qs.as_ptr();
+        let mut sumi = _mm256_setzero_si256();
+        for j in 0..QK_K / 64 {
+            let scale_l = _mm256_shuffle_epi8(scales, get_scale_shuffle_k4(2 * j));
+            let scale_h = _mm256_shuffle_epi8(scales, get_scale_shuffle_k4(2 * j + 1));
+
+            let q4bits = _mm256_loadu_si256(q4 as *const __m256i);
+            q4 = q4.add(32);
+            let q4l = _mm256_and_si256(q4bits, m4);
+            let q4h = _mm256_and_si256(_mm256_srli_epi16(q4bits, 4), m4);
+
+            let q8l = _mm256_loadu_si256(q8 as *const __m256i);
+            q8 = q8.add(32);
+            let p16l = _mm256_madd_epi16(scale_l, _mm256_maddubs_epi16(q4l, q8l));
+
+            let q8h = _mm256_loadu_si256(q8 as *const __m256i);
+            q8 = q8.add(32);
+            let p16h = _mm256_madd_epi16(scale_h, _mm256_maddubs_epi16(q4h, q8h));
+
+            sumi = _mm256_add_epi32(sumi, _mm256_add_epi32(p16l, p16h));
+        }
+        let vd = _mm256_set1_ps(d);
+        acc = _mm256_add_ps(_mm256_mul_ps(vd, _mm256_cvtepi32_ps(sumi)), acc);
+    }
+    acc_m = _mm_add_ps(acc_m, _mm_movehl_ps(acc_m, acc_m));
+    acc_m = _mm_add_ss(acc_m, _mm_movehdup_ps(acc_m));
+    hsum_float_8(acc) + _mm_cvtss_f32(acc_m)
+}
+
+#[inline(never)]
+pub(crate) unsafe fn vec_dot_q5_k_q8_k(xs: &[BlockQ5K], ys: &[BlockQ8K]) -> f32 {
+    let m4 = _mm256_set1_epi8(0xF);
+    let mone = _mm256_set1_epi8(1);
+    let mut acc = _mm256_setzero_ps();
+    let mut summs = 0f32;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let d = y.d * x.d.to_f32();
+        let dmin = -y.d * x.dmin.to_f32();
+
+        let mins_and_scales = mins_and_scales_k4(&x.scales);
+        let prod = mins_dot_bsums(mins_and_scales, y);
+        let zero = _mm_setzero_si128();
+        let hsum = _mm_hadd_epi32(_mm_hadd_epi32(prod, zero), zero);
+        summs += dmin * _mm_extract_epi32(hsum, 0) as f32;
+
+        let sc128 = _mm256_extracti128_si256(mins_and_scales, 0);
+        let scales = _mm256_set_m128i(sc128, sc128);
+
+        let hbits = _mm256_loadu_si256(x.qh.as_ptr() as *const __m256i);
+        let mut hmask = mone;
+        let mut q5 = x.qs.as_ptr();
+        let mut q8 = y.qs.as_ptr();
+        let mut sumi = _mm256_setzero_si256();
+        for j in 0..QK_K / 64 {
+            let scale_0 = _mm256_shuffle_epi8(scales, get_scale_shuffle_k4(2 * j));
+            let scale_1 = _mm256_shuffle_epi8(scales, get_scale_shuffle_k4(2 * j + 1));
+
+            let q5bits = _mm256_loadu_si256(q5 as *const __m256i);
+            q5 = q5.add(32);
+
+            // The high bit of the sub-block lives at bit `2j` (resp. `2j + 1`) of qh.
+            let bit = _mm_cvtsi32_si128(2 * j as i32);
+            let q5l_0 = _mm256_and_si256(q5bits, m4);
+            let q5h_0 = _mm256_slli_epi16(_mm256_srl_epi16(_mm256_and_si256(hbits, hmask), bit), 4);
+            let q5_0 = _mm256_add_epi8(q5l_0, q5h_0);
+            hmask = _mm256_slli_epi16(hmask, 1);
+
+            let bit = _mm_cvtsi32_si128(2 * j as i32 + 1);
+            let q5l_1 = _mm256_and_si256(_mm256_srli_epi16(q5bits, 4), m4);
+            let q5h_1 = _mm256_slli_epi16(_mm256_srl_epi16(_mm256_and_si256(hbits, hmask), bit), 4);
+            let q5_1 = _mm256_add_epi8(q5l_1, q5h_1);
+            hmask = _mm256_slli_epi16(hmask, 1);
+
+            let q8_0 = _mm256_loadu_si256(q8 as *const __m256i);
+            q8 = q8.add(32);
+            let q8_1 = _mm256_loadu_si256(q8 as *const __m256i);
+            q8 = q8.add(32);
+
+            let p16_0 = _mm256_madd_epi16(scale_0, _mm256_maddubs_epi16(q5_0, q8_0));
+            let p16_1 = _mm256_madd_epi16(scale_1, _mm256_maddubs_epi16(q5_1, q8_1));
+
+            sumi = _mm256_add_epi32(sumi, _mm256_add_epi32(p16_0, p16_1));
+        }
+        let vd = _mm256_set1_ps(d);
+        acc = _mm256_add_ps(_mm256_mul_ps(vd, _mm256_cvtepi32_ps(sumi)), acc);
+    }
+    hsum_float_8(acc) + summs
+}
+
+#[inline(never)]
+pub(crate) unsafe fn vec_dot_q6_k_q8_k(xs: &[BlockQ6K], ys: &[BlockQ8K]) -> f32 {
+    let m4 = _mm256_set1_epi8(0xF);
+    let m2 = _mm256_set1_epi8(3);
+    let m32s = _mm256_set1_epi8(32);
+    let mut acc = _mm256_setzero_ps();
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let d = y.d * x.d.to_f32();
+        let mut q4 = x.ql.as_ptr();
+        let mut qh = x.qh.as_ptr();
+        let mut q8 = y.qs.as_ptr();
+
+        let scales = _mm_loadu_si128(x.scales.as_ptr() as *const __m128i);
+        let mut sumi = _mm256_setzero_si256();
+        for j in 0..QK_K / 128 {
+            let is = 4 * j;
+            let scale_0 = _mm_shuffle_epi8(scales, get_scale_shuffle(is));
+            let scale_1 = _mm_shuffle_epi8(scales, get_scale_shuffle(is + 1));
+            let scale_2 = _mm_shuffle_epi8(scales, get_scale_shuffle(is + 2));
+            let scale_3 = _mm_shuffle_epi8(scales, get_scale_shuffle(is + 3));
+
+            let q4bits1 = _mm256_loadu_si256(q4 as *const __m256i);
+            q4 = q4.add(32);
+            let q4bits2 = _mm256_loadu_si256(q4 as *const __m256i);
+            q4 = q4.add(32);
+            let q4bits_h = _mm256_loadu_si256(qh as *const __m256i);
+            qh = qh.add(32);
+
+            let q4h_0 = _mm256_slli_epi16(_mm256_and_si256(q4bits_h, m2), 4);
+            let q4h_1 = _mm256_slli_epi16(_mm256_and_si256(_mm256_srli_epi16(q4bits_h, 2), m2), 4);
+            let q4h_2 = _mm256_slli_epi16(_mm256_and_si256(_mm256_srli_epi16(q4bits_h, 4), m2), 4);
+            let q4h_3 = _mm256_slli_epi16(_mm256_and_si256(_mm256_srli_epi16(q4bits_h, 6), m2), 4);
+
+            let q4_0 = _mm256_or_si256(_mm256_and_si256(q4bits1, m4), q4h_0);
+            let q4_1 = _mm256_or_si256(_mm256_and_si256(q4bits2, m4), q4h_1);
+            let q4_2 = _mm256_or_si256(_mm256_and_si256(_mm256_srli_epi16(q4bits1, 4), m4), q4h_2);
+            let q4_3 = _mm256_or_si256(_mm256_and_si256(_mm256_srli_epi16(q4bits2, 4), m4), q4h_3);
+
+            let mut q8s = [_mm256_setzero_si256(); 4];
+            for q in q8s.iter_mut() {
+                *q = _mm256_loadu_si256(q8 as *const __m256i);
+                q8 = q8.add(32);
+            }
+
+            // The quants are stored with a +32 offset, maddubs needs them unsigned
+            // so the offset is removed afterwards by subtracting 32 * q8.
+            let mut p16 = [
+                _mm256_maddubs_epi16(q4_0, q8s[0]),
+                _mm256_maddubs_epi16(q4_1, q8s[1]),
+                _mm256_maddubs_epi16(q4_2, q8s[2]),
+                _mm256_maddubs_epi16(q4_3, q8s[3]),
+            ];
+            let scales = [scale_0, scale_1, scale_2, scale_3];
+            for ((p, q8), scale) in p16.iter_mut().zip(q8s.iter()).zip(scales.iter()) {
+                *p = _mm256_sub_epi16(*p, _mm256_maddubs_epi16(m32s, *q8));
+                *p = _mm256_madd_epi16(_mm256_cvtepi8_epi16(*scale), *p);
+            }
+            sumi = _mm256_add_epi32(sumi, _mm256_add_epi32(p16[0], p16[1]));
+            sumi = _mm256_add_epi32(sumi, _mm256_add_epi32(p16[2], p16[3]));
+        }
+        acc = _mm256_add_ps(
+            _mm256_mul_ps(_mm256_set1_ps(d), _mm256_cvtepi32_ps(sumi)),
+            acc,
+        );
+    }
+    hsum_float_8(acc)
+}
+
+#[inline(always)]
+unsafe fn hsum_i32_8(a: __m256i) -> i32 {
+    let sum128 = _mm_add_epi32(_mm256_castsi256_si128(a), _mm256_extracti128_si256(a, 1));
+    let hi64 = _mm_unpackhi_epi64(sum128, sum128);
+    let sum64 = _mm_add_epi32(hi64, sum128);
+    let hi32 = _mm_shuffle_epi32(sum64, 0b10_11_00_01);
+    _mm_cvtsi128_si32(_mm_add_epi32(sum64, hi32))
+}
+
+/// Exact i8 dot product. The inputs are sign extended to 16 bits and multiplied
+/// with `vpmaddwd`: `maddubs` would be faster but gets -128 * -128 wrong since
+/// it needs one operand unsigned.
+#[inline(never)]
+pub(crate) unsafe fn vec_dot_i8(a_row: *const i8, b_row: *const i8, k: usize) -> i32 {
+    let np = k & !31;
+    let mut acc = _mm256_setzero_si256();
+    for i in (0..np).step_by(32) {
+        let va = _mm256_loadu_si256(a_row.add(i) as *const __m256i);
+        let vb = _mm256_loadu_si256(b_row.add(i) as *const __m256i);
+        let a_lo = _mm256_cvtepi8_epi16(_mm256_castsi256_si128(va));
+        let a_hi = _mm256_cvtepi8_epi16(_mm256_extracti128_si256(va, 1));
+        let b_lo = _mm256_cvtepi8_epi16(_mm256_castsi256_si128(vb));
+        let b_hi = _mm256_cvtepi8_epi16(_mm256_extracti128_si256(vb, 1));
+        acc = _mm256_add_epi32(acc, _mm256_madd_epi16(a_lo, b_lo));
+        acc = _mm256_add_epi32(acc, _mm256_madd_epi16(a_hi, b_hi));
+    }
+    let mut sum = hsum_i32_8(acc);
+
+    // leftovers
+    for i in np..k {
+        sum = sum.wrapping_add(*a_row.add(i) as i32 * *b_row.add(i) as i32);
+    }
+    sum
+}
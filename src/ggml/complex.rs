@@ -0,0 +1,176 @@
+//! Complex dot products on interleaved `(re, im)` storage.
+//!
+//! `Complex<T>` is `#[repr(C)]`, so a slice of complex numbers can be read as
+//! a slice of `T` with the real and imaginary parts alternating. The `f32`
+//! kernel accumulates `a * b` and `a * swap_pairs(b)` with the regular SIMD
+//! loads, and the conjugation flags only change how the lanes get combined
+//! at the end.
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+))]
+use super::{Cpu, CurrentCpu};
+use num_complex::{Complex, Complex32, Complex64};
+use std::ops::Neg;
+
+#[inline]
+fn conj_if<T: Neg<Output = T>>(v: Complex<T>, conj: bool) -> Complex<T> {
+    if conj {
+        Complex::new(v.re, -v.im)
+    } else {
+        v
+    }
+}
+
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+))]
+/// Computes `sum(op(a[i]) * op(b[i]))` where `op` conjugates when the
+/// matching flag is set.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_c32(
+    a_row: *const Complex32,
+    b_row: *const Complex32,
+    k: usize,
+    conj_a: bool,
+    conj_b: bool,
+) -> Complex32 {
+    // Alternating signs, long enough for any `EPR`.
+    const SIGNS: [f32; 16] = [
+        1., -1., 1., -1., 1., -1., 1., -1., 1., -1., 1., -1., 1., -1., 1., -1.,
+    ];
+    let a = a_row as *const f32;
+    let b = b_row as *const f32;
+    let np = (2 * k) & !(CurrentCpu::STEP - 1);
+
+    // Lanes of `prod` hold `ar * br, ai * bi`, lanes of `cross` `ar * bi, ai * br`.
+    let mut prod = CurrentCpu::zero_array();
+    let mut cross = CurrentCpu::zero_array();
+
+    for i in (0..np).step_by(CurrentCpu::STEP) {
+        for j in 0..CurrentCpu::n() {
+            let ax = CurrentCpu::load(a.add(i + j * CurrentCpu::EPR));
+            let ay = CurrentCpu::load(b.add(i + j * CurrentCpu::EPR));
+            prod[j] = CurrentCpu::vec_fma(prod[j], ax, ay);
+            cross[j] = CurrentCpu::vec_fma(cross[j], ax, CurrentCpu::vec_swap_pairs(ay));
+        }
+    }
+
+    let signs = CurrentCpu::load(SIGNS.as_ptr());
+    let zero = CurrentCpu::zero();
+    let mut prod_alt = CurrentCpu::zero_array();
+    let mut cross_alt = CurrentCpu::zero_array();
+    for j in 0..CurrentCpu::n() {
+        prod_alt[j] = CurrentCpu::vec_fma(zero, prod[j], signs);
+        cross_alt[j] = CurrentCpu::vec_fma(zero, cross[j], signs);
+    }
+    // `*_sum` add every lane, `*_diff` subtract the odd lanes from the even ones.
+    let (mut prod_sum, mut prod_diff, mut cross_sum, mut cross_diff) = (0.0, 0.0, 0.0, 0.0);
+    CurrentCpu::vec_reduce(prod, &mut prod_sum);
+    CurrentCpu::vec_reduce(prod_alt, &mut prod_diff);
+    CurrentCpu::vec_reduce(cross, &mut cross_sum);
+    CurrentCpu::vec_reduce(cross_alt, &mut cross_diff);
+
+    let mut sum = match (conj_a, conj_b) {
+        (false, false) => Complex32::new(prod_diff, cross_sum),
+        (true, false) => Complex32::new(prod_sum, cross_diff),
+        (false, true) => Complex32::new(prod_sum, -cross_diff),
+        (true, true) => Complex32::new(prod_diff, -cross_sum),
+    };
+
+    // leftovers
+    for i in np / 2..k {
+        sum += conj_if(*a_row.add(i), conj_a) * conj_if(*b_row.add(i), conj_b);
+    }
+    sum
+}
+
+#[cfg(not(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+)))]
+/// Computes `sum(op(a[i]) * op(b[i]))` where `op` conjugates when the
+/// matching flag is set.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_c32(
+    a_row: *const Complex32,
+    b_row: *const Complex32,
+    k: usize,
+    conj_a: bool,
+    conj_b: bool,
+) -> Complex32 {
+    let mut sum = Complex32::new(0.0, 0.0);
+    for i in 0..k {
+        sum += conj_if(*a_row.add(i), conj_a) * conj_if(*b_row.add(i), conj_b);
+    }
+    sum
+}
+
+/// Computes `sum(op(a[i]) * op(b[i]))` where `op` conjugates when the
+/// matching flag is set.
+///
+/// There is no `f64` SIMD trait, this relies on the compiler vectorizing the
+/// loop.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_c64(
+    a_row: *const Complex64,
+    b_row: *const Complex64,
+    k: usize,
+    conj_a: bool,
+    conj_b: bool,
+) -> Complex64 {
+    let a = std::slice::from_raw_parts(a_row, k);
+    let b = std::slice::from_raw_parts(b_row, k);
+    a.iter()
+        .zip(b)
+        .fold(Complex64::new(0.0, 0.0), |sum, (a, b)| {
+            sum + conj_if(*a, conj_a) * conj_if(*b, conj_b)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_c32_matches_scalar() {
+        // Not a multiple of the SIMD step so the leftovers get exercised.
+        let k = 45;
+        let a: Vec<Complex32> = (0..k)
+            .map(|i| Complex32::new((i % 7) as f32 - 3.0, (i % 5) as f32 - 2.0))
+            .collect();
+        let b: Vec<Complex32> = (0..k)
+            .map(|i| Complex32::new((i % 3) as f32 - 1.0, (i % 11) as f32 - 5.0))
+            .collect();
+        for conj_a in [false, true] {
+            for conj_b in [false, true] {
+                let expected = a
+                    .iter()
+                    .zip(&b)
+                    .map(|(a, b)| conj_if(*a, conj_a) * conj_if(*b, conj_b))
+                    .sum::<Complex32>();
+                let got = unsafe { vec_dot_c32(a.as_ptr(), b.as_ptr(), k, conj_a, conj_b) };
+                assert_eq!(got, expected, "{conj_a} {conj_b}");
+            }
+        }
+    }
+}
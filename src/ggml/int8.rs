@@ -0,0 +1,32 @@
+//! Integer dot products used by the i8 x i8 -> i32 GEMM.
+
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+#[inline(never)]
+pub unsafe fn vec_dot_i8(a_row: *const i8, b_row: *const i8, k: usize) -> i32 {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[cfg(all(target_feature = "avx2", not(miri)))]
+    {
+        super::avx_quants::vec_dot_i8(a_row, b_row, k)
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "avx2",
+        not(miri)
+    )))]
+    {
+        vec_dot_i8_ref(a_row, b_row, k)
+    }
+}
+
+/// Scalar version of [`vec_dot_i8`], used as a fallback and as a reference.
+///
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
+pub unsafe fn vec_dot_i8_ref(a_row: *const i8, b_row: *const i8, k: usize) -> i32 {
+    let mut sum = 0i32;
+    for i in 0..k {
+        sum = sum.wrapping_add(*a_row.add(i) as i32 * *b_row.add(i) as i32);
+    }
+    sum
+}
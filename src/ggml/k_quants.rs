@@ -0,0 +1,798 @@
+//! Super-block ("k-quant") formats ported from llama.cpp.
+//!
+//! Each super-block holds `QK_K` values split in sub-blocks that carry their own
+//! 6 or 8 bits scales, on top of an f16 scale for the whole super-block. The
+//! structs below are byte-for-byte identical to `block_q4_K`, `block_q5_K`,
+//! `block_q6_K` and `block_q8_K` when llama.cpp is built with `QK_K == 256`.
+use super::quants::GgmlType;
+use half::f16;
+
+pub const QK_K: usize = 256;
+pub const K_SCALE_SIZE: usize = 12;
+
+// Values with a smaller magnitude are considered zero when computing scales.
+const GROUP_MAX_EPS: f32 = 1e-15;
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQ4K {
+    pub d: f16,
+    pub dmin: f16,
+    pub scales: [u8; K_SCALE_SIZE],
+    pub qs: [u8; QK_K / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ4K>() == 144);
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQ5K {
+    pub d: f16,
+    pub dmin: f16,
+    pub scales: [u8; K_SCALE_SIZE],
+    pub qh: [u8; QK_K / 8],
+    pub qs: [u8; QK_K / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ5K>() == 176);
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQ6K {
+    pub ql: [u8; QK_K / 2],
+    pub qh: [u8; QK_K / 4],
+    pub scales: [i8; QK_K / 16],
+    pub d: f16,
+}
+const _: () = assert!(std::mem::size_of::<BlockQ6K>() == 210);
+
+/// Activation format used by the k-quant dot products. Unlike the other
+/// blocks the scale is a plain f32, and `bsums` caches the sum of each group
+/// of 16 quants.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQ8K {
+    pub d: f32,
+    pub qs: [i8; QK_K],
+    pub bsums: [i16; QK_K / 16],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ8K>() == 292);
+
+/// Round to nearest, ties to even, like ggml's `nearest_int`.
+#[inline(always)]
+fn nearest_int(v: f32) -> i32 {
+    v.round_ties_even() as i32
+}
+
+/// Unpacks the 6 bits scale and min of sub-block `j` from the 12 packed bytes.
+#[inline(always)]
+pub(crate) fn get_scale_min_k4(j: usize, q: &[u8; K_SCALE_SIZE]) -> (u8, u8) {
+    if j < 4 {
+        (q[j] & 63, q[j + 4] & 63)
+    } else {
+        (
+            (q[j + 4] & 0xF) | ((q[j - 4] >> 6) << 4),
+            (q[j + 4] >> 4) | ((q[j] >> 6) << 4),
+        )
+    }
+}
+
+/// Fits `x ≈ scale * L - min` with `L` in `0..=nmax`, trying a few scales around
+/// the naive one and keeping the one with the lowest weighted error.
+fn make_qkx2_quants(
+    nmax: i32,
+    x: &[f32],
+    weights: &[f32],
+    l: &mut [u8],
+    rmin: f32,
+    rdelta: f32,
+    nstep: usize,
+) -> (f32, f32) {
+    let n = x.len();
+    let mut l_aux = [0u8; 32];
+    let mut min = x.iter().copied().fold(x[0], f32::min);
+    let max = x.iter().copied().fold(x[0], f32::max);
+    let sum_w: f32 = weights.iter().sum();
+    let sum_x: f32 = weights.iter().zip(x).map(|(w, x)| w * x).sum();
+    if min > 0.0 {
+        min = 0.0;
+    }
+    if max == min {
+        l.iter_mut().for_each(|l| *l = 0);
+        return (0.0, -min);
+    }
+    let mut iscale = nmax as f32 / (max - min);
+    let mut scale = 1.0 / iscale;
+    let mut best_mad = 0.0;
+    for i in 0..n {
+        let li = nearest_int(iscale * (x[i] - min)).clamp(0, nmax);
+        l[i] = li as u8;
+        let diff = scale * l[i] as f32 + min - x[i];
+        best_mad += weights[i] * diff * diff;
+    }
+    for is in 0..=nstep {
+        iscale = (rmin + rdelta * is as f32 + nmax as f32) / (max - min);
+        let (mut sum_l, mut sum_l2, mut sum_xl) = (0.0, 0.0, 0.0);
+        for i in 0..n {
+            let li = nearest_int(iscale * (x[i] - min)).clamp(0, nmax);
+            l_aux[i] = li as u8;
+            let li = li as f32;
+            let w = weights[i];
+            sum_l += w * li;
+            sum_l2 += w * li * li;
+            sum_xl += w * li * x[i];
+        }
+        let det = sum_w * sum_l2 - sum_l * sum_l;
+        if det > 0.0 {
+            let mut this_scale = (sum_w * sum_xl - sum_x * sum_l) / det;
+            let mut this_min = (sum_l2 * sum_x - sum_l * sum_xl) / det;
+            if this_min > 0.0 {
+                this_min = 0.0;
+                this_scale = sum_xl / sum_l2;
+            }
+            let mut mad = 0.0;
+            for i in 0..n {
+                let diff = this_scale * l_aux[i] as f32 + this_min - x[i];
+                mad += weights[i] * diff * diff;
+            }
+            if mad < best_mad {
+                l.copy_from_slice(&l_aux[..n]);
+                best_mad = mad;
+                scale = this_scale;
+                min = this_min;
+            }
+        }
+    }
+    (scale, -min)
+}
+
+/// Fits `x ≈ scale * L` with `L` in `-nmax..nmax`, weighting by `x²`.
+fn make_qx_quants(nmax: i32, x: &[f32], l: &mut [i8]) -> f32 {
+    let mut max = 0f32;
+    let mut amax = 0f32;
+    for &v in x {
+        if v.abs() > amax {
+            amax = v.abs();
+            max = v;
+        }
+    }
+    if amax < GROUP_MAX_EPS {
+        l.iter_mut().for_each(|l| *l = 0);
+        return 0.0;
+    }
+    let quantize = |iscale: f32, l: &mut [i8]| {
+        let (mut sumlx, mut suml2) = (0.0, 0.0);
+        for (li, &x) in l.iter_mut().zip(x) {
+            let q = nearest_int(iscale * x).clamp(-nmax, nmax - 1);
+            *li = (q + nmax) as i8;
+            let w = x * x;
+            sumlx += w * x * q as f32;
+            suml2 += w * (q * q) as f32;
+        }
+        (sumlx, suml2)
+    };
+    let (sumlx, suml2) = quantize(-(nmax as f32) / max, l);
+    let mut scale = if suml2 != 0.0 { sumlx / suml2 } else { 0.0 };
+    let mut best = scale * sumlx;
+    let mut l_aux = [0i8; 16];
+    for is in -9..=9 {
+        if is == 0 {
+            continue;
+        }
+        let iscale = -(nmax as f32 + 0.1 * is as f32) / max;
+        let (sumlx, suml2) = quantize(iscale, &mut l_aux[..x.len()]);
+        if suml2 > 0.0 && sumlx * sumlx > best * suml2 {
+            l.copy_from_slice(&l_aux[..x.len()]);
+            scale = sumlx / suml2;
+            best = scale * sumlx;
+        }
+    }
+    scale
+}
+
+/// Quantizes the per sub-block `(scale, min)` pairs to 6 bits and packs them
+/// the way [`get_scale_min_k4`] expects. Returns the super-block `d` and `dmin`.
+fn pack_scales_min_k4(
+    scales: &[f32; QK_K / 32],
+    mins: &[f32; QK_K / 32],
+    packed: &mut [u8; K_SCALE_SIZE],
+) -> (f16, f16) {
+    let max_scale = scales.iter().copied().fold(0f32, f32::max);
+    let max_min = mins.iter().copied().fold(0f32, f32::max);
+    let inv_scale = if max_scale > 0.0 {
+        63.0 / max_scale
+    } else {
+        0.0
+    };
+    let inv_min = if max_min > 0.0 { 63.0 / max_min } else { 0.0 };
+    for j in 0..QK_K / 32 {
+        let ls = nearest_int(inv_scale * scales[j]).min(63) as u8;
+        let lm = nearest_int(inv_min * mins[j]).min(63) as u8;
+        if j < 4 {
+            packed[j] = ls;
+            packed[j + 4] = lm;
+        } else {
+            packed[j + 4] = (ls & 0xF) | ((lm & 0xF) << 4);
+            packed[j - 4] |= (ls >> 4) << 6;
+            packed[j] |= (lm >> 4) << 6;
+        }
+    }
+    (
+        f16::from_f32(max_scale / 63.0),
+        f16::from_f32(max_min / 63.0),
+    )
+}
+
+/// Runs the scale/min search on each of the 8 sub-blocks of 32 values and
+/// returns the final quants in `0..=nmax` once the scales have been packed.
+fn quantize_scales_min_k4(
+    x: &[f32],
+    nmax: i32,
+    rmin: f32,
+    nstep: usize,
+    packed: &mut [u8; K_SCALE_SIZE],
+) -> (f16, f16, [u8; QK_K]) {
+    let mut l = [0u8; QK_K];
+    let mut scales = [0f32; QK_K / 32];
+    let mut mins = [0f32; QK_K / 32];
+    let mut weights = [0f32; 32];
+    for (j, x) in x.chunks_exact(32).enumerate() {
+        let av_x = (x.iter().map(|v| v * v).sum::<f32>() / 32.0).sqrt();
+        weights
+            .iter_mut()
+            .zip(x)
+            .for_each(|(w, v)| *w = av_x + v.abs());
+        let (scale, min) = make_qkx2_quants(
+            nmax,
+            x,
+            &weights,
+            &mut l[32 * j..32 * (j + 1)],
+            rmin,
+            0.1,
+            nstep,
+        );
+        scales[j] = scale;
+        mins[j] = min;
+    }
+    let (d, dmin) = pack_scales_min_k4(&scales, &mins, packed);
+    for (j, x) in x.chunks_exact(32).enumerate() {
+        let (sc, m) = get_scale_min_k4(j, packed);
+        let d = d.to_f32() * sc as f32;
+        if d == 0.0 {
+            continue;
+        }
+        let dm = dmin.to_f32() * m as f32;
+        for (ii, &v) in x.iter().enumerate() {
+            l[32 * j + ii] = nearest_int((v + dm) / d).clamp(0, nmax) as u8;
+        }
+    }
+    (d, dmin, l)
+}
+
+pub fn quantize_row_q4_k(xs: &[f32], ys: &mut [BlockQ4K]) {
+    assert_eq!(xs.len(), ys.len() * QK_K);
+    for (x, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+        let (d, dmin, l) = quantize_scales_min_k4(x, 15, -1.0, 20, &mut y.scales);
+        y.d = d;
+        y.dmin = dmin;
+        for (j, q) in (0..QK_K).step_by(64).zip(y.qs.chunks_exact_mut(32)) {
+            for (i, q) in q.iter_mut().enumerate() {
+                *q = l[j + i] | (l[j + i + 32] << 4);
+            }
+        }
+    }
+}
+
+pub fn dequantize_row_q4_k(xs: &[BlockQ4K], ys: &mut [f32]) {
+    assert_eq!(ys.len(), xs.len() * QK_K);
+    for (x, y) in xs.iter().zip(ys.chunks_exact_mut(QK_K)) {
+        let d = x.d.to_f32();
+        let min = x.dmin.to_f32();
+        for (j, (q, y)) in
+            x.qs.chunks_exact(32)
+                .zip(y.chunks_exact_mut(64))
+                .enumerate()
+        {
+            let (sc, m) = get_scale_min_k4(2 * j, &x.scales);
+            let (d1, m1) = (d * sc as f32, min * m as f32);
+            let (sc, m) = get_scale_min_k4(2 * j + 1, &x.scales);
+            let (d2, m2) = (d * sc as f32, min * m as f32);
+            for l in 0..32 {
+                y[l] = d1 * (q[l] & 0xF) as f32 - m1;
+                y[l + 32] = d2 * (q[l] >> 4) as f32 - m2;
+            }
+        }
+    }
+}
+
+pub fn quantize_row_q5_k(xs: &[f32], ys: &mut [BlockQ5K]) {
+    assert_eq!(xs.len(), ys.len() * QK_K);
+    for (x, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+        let (d, dmin, l) = quantize_scales_min_k4(x, 31, -0.5, 15, &mut y.scales);
+        y.d = d;
+        y.dmin = dmin;
+        y.qh = [0; QK_K / 8];
+        let (mut m1, mut m2) = (1u8, 2u8);
+        for (n, ql) in (0..QK_K).step_by(64).zip(y.qs.chunks_exact_mut(32)) {
+            for j in 0..32 {
+                let mut l1 = l[n + j];
+                if l1 > 15 {
+                    l1 -= 16;
+                    y.qh[j] |= m1;
+                }
+                let mut l2 = l[n + j + 32];
+                if l2 > 15 {
+                    l2 -= 16;
+                    y.qh[j] |= m2;
+                }
+                ql[j] = l1 | (l2 << 4);
+            }
+            m1 <<= 2;
+            m2 <<= 2;
+        }
+    }
+}
+
+pub fn dequantize_row_q5_k(xs: &[BlockQ5K], ys: &mut [f32]) {
+    assert_eq!(ys.len(), xs.len() * QK_K);
+    for (x, y) in xs.iter().zip(ys.chunks_exact_mut(QK_K)) {
+        let d = x.d.to_f32();
+        let min = x.dmin.to_f32();
+        let (mut u1, mut u2) = (1u8, 2u8);
+        for (j, (ql, y)) in
+            x.qs.chunks_exact(32)
+                .zip(y.chunks_exact_mut(64))
+                .enumerate()
+        {
+            let (sc, m) = get_scale_min_k4(2 * j, &x.scales);
+            let (d1, m1) = (d * sc as f32, min * m as f32);
+            let (sc, m) = get_scale_min_k4(2 * j + 1, &x.scales);
+            let (d2, m2) = (d * sc as f32, min * m as f32);
+            for l in 0..32 {
+                let h1 = if x.qh[l] & u1 != 0 { 16 } else { 0 };
+                let h2 = if x.qh[l] & u2 != 0 { 16 } else { 0 };
+                y[l] = d1 * ((ql[l] & 0xF) + h1) as f32 - m1;
+                y[l + 32] = d2 * ((ql[l] >> 4) + h2) as f32 - m2;
+            }
+            u1 <<= 2;
+            u2 <<= 2;
+        }
+    }
+}
+
+pub fn quantize_row_q6_k(xs: &[f32], ys: &mut [BlockQ6K]) {
+    assert_eq!(xs.len(), ys.len() * QK_K);
+    for (x, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+        let mut l = [0i8; QK_K];
+        let mut scales = [0f32; QK_K / 16];
+        let mut max_scale = 0f32;
+        let mut max_abs_scale = 0f32;
+        for (ib, x) in x.chunks_exact(16).enumerate() {
+            let scale = make_qx_quants(32, x, &mut l[16 * ib..16 * (ib + 1)]);
+            scales[ib] = scale;
+            if scale.abs() > max_abs_scale {
+                max_abs_scale = scale.abs();
+                max_scale = scale;
+            }
+        }
+        if max_abs_scale < GROUP_MAX_EPS {
+            *y = BlockQ6K::zeros();
+            continue;
+        }
+        let iscale = -128.0 / max_scale;
+        y.d = f16::from_f32(1.0 / iscale);
+        for (sc, &scale) in y.scales.iter_mut().zip(scales.iter()) {
+            *sc = nearest_int(iscale * scale).min(127) as i8;
+        }
+        for (j, x) in x.chunks_exact(16).enumerate() {
+            let d = y.d.to_f32() * y.scales[j] as f32;
+            if d == 0.0 {
+                continue;
+            }
+            for (ii, &v) in x.iter().enumerate() {
+                l[16 * j + ii] = (nearest_int(v / d).clamp(-32, 31) + 32) as i8;
+            }
+        }
+        for (j, (ql, qh)) in (0..QK_K)
+            .step_by(128)
+            .zip(y.ql.chunks_exact_mut(64).zip(y.qh.chunks_exact_mut(32)))
+        {
+            for i in 0..32 {
+                let q1 = l[j + i] as u8;
+                let q2 = l[j + i + 32] as u8;
+                let q3 = l[j + i + 64] as u8;
+                let q4 = l[j + i + 96] as u8;
+                ql[i] = (q1 & 0xF) | ((q3 & 0xF) << 4);
+                ql[i + 32] = (q2 & 0xF) | ((q4 & 0xF) << 4);
+                qh[i] = (q1 >> 4) | ((q2 >> 4) << 2) | ((q3 >> 4) << 4) | ((q4 >> 4) << 6);
+            }
+        }
+    }
+}
+
+pub fn dequantize_row_q6_k(xs: &[BlockQ6K], ys: &mut [f32]) {
+    assert_eq!(ys.len(), xs.len() * QK_K);
+    for (x, y) in xs.iter().zip(ys.chunks_exact_mut(QK_K)) {
+        let d = x.d.to_f32();
+        for (n, y) in y.chunks_exact_mut(128).enumerate() {
+            let ql = &x.ql[64 * n..];
+            let qh = &x.qh[32 * n..];
+            let sc = &x.scales[8 * n..];
+            for l in 0..32 {
+                let is = l / 16;
+                let q1 = ((ql[l] & 0xF) | ((qh[l] & 3) << 4)) as i8 - 32;
+                let q2 = ((ql[l + 32] & 0xF) | (((qh[l] >> 2) & 3) << 4)) as i8 - 32;
+                let q3 = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i8 - 32;
+                let q4 = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i8 - 32;
+                y[l] = d * sc[is] as f32 * q1 as f32;
+                y[l + 32] = d * sc[is + 2] as f32 * q2 as f32;
+                y[l + 64] = d * sc[is + 4] as f32 * q3 as f32;
+                y[l + 96] = d * sc[is + 6] as f32 * q4 as f32;
+            }
+        }
+    }
+}
+
+pub fn quantize_row_q8_k(xs: &[f32], ys: &mut [BlockQ8K]) {
+    assert_eq!(xs.len(), ys.len() * QK_K);
+    for (x, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+        let mut max = 0f32;
+        let mut amax = 0f32;
+        for &v in x {
+            if v.abs() > amax {
+                amax = v.abs();
+                max = v;
+            }
+        }
+        if amax == 0.0 {
+            *y = BlockQ8K::zeros();
+            continue;
+        }
+        let iscale = -128.0 / max;
+        for (q, &v) in y.qs.iter_mut().zip(x) {
+            *q = nearest_int(iscale * v).min(127) as i8;
+        }
+        for (sum, q) in y.bsums.iter_mut().zip(y.qs.chunks_exact(16)) {
+            *sum = q.iter().map(|&q| q as i16).sum();
+        }
+        y.d = 1.0 / iscale;
+    }
+}
+
+pub fn dequantize_row_q8_k(xs: &[BlockQ8K], ys: &mut [f32]) {
+    assert_eq!(ys.len(), xs.len() * QK_K);
+    for (x, y) in xs.iter().zip(ys.chunks_exact_mut(QK_K)) {
+        for (v, &q) in y.iter_mut().zip(x.qs.iter()) {
+            *v = x.d * q as f32;
+        }
+    }
+}
+
+macro_rules! vec_dot_k {
+    ($name:ident, $reference:ident, $ty:ty) => {
+        pub fn $name(n: usize, xs: &[$ty], ys: &[BlockQ8K]) -> f32 {
+            assert_eq!(n % QK_K, 0);
+            let nb = n / QK_K;
+            assert!(xs.len() >= nb && ys.len() >= nb);
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            #[cfg(all(target_feature = "avx2", not(miri)))]
+            unsafe {
+                super::avx_quants::$name(&xs[..nb], &ys[..nb])
+            }
+            #[cfg(not(all(
+                any(target_arch = "x86", target_arch = "x86_64"),
+                target_feature = "avx2",
+                not(miri)
+            )))]
+            $reference(&xs[..nb], &ys[..nb])
+        }
+    };
+}
+
+vec_dot_k!(vec_dot_q4_k_q8_k, vec_dot_q4_k_q8_k_ref, BlockQ4K);
+vec_dot_k!(vec_dot_q5_k_q8_k, vec_dot_q5_k_q8_k_ref, BlockQ5K);
+vec_dot_k!(vec_dot_q6_k_q8_k, vec_dot_q6_k_q8_k_ref, BlockQ6K);
+
+/// Shared scalar kernel for Q4_K and Q5_K once the quants have been unpacked.
+fn vec_dot_scale_min_k4(
+    q: &[u8; QK_K],
+    scales: &[u8; K_SCALE_SIZE],
+    d: f16,
+    dmin: f16,
+    y: &BlockQ8K,
+) -> f32 {
+    let mut sumi = 0i32;
+    let mut summ = 0i32;
+    for j in 0..QK_K / 32 {
+        let (sc, m) = get_scale_min_k4(j, scales);
+        let dot: i32 = q[32 * j..32 * (j + 1)]
+            .iter()
+            .zip(&y.qs[32 * j..32 * (j + 1)])
+            .map(|(&q, &y)| q as i32 * y as i32)
+            .sum();
+        sumi += sc as i32 * dot;
+        summ += m as i32 * (y.bsums[2 * j] as i32 + y.bsums[2 * j + 1] as i32);
+    }
+    d.to_f32() * y.d * sumi as f32 - dmin.to_f32() * y.d * summ as f32
+}
+
+/// Scalar version of [`vec_dot_q4_k_q8_k`], used as a fallback and as a reference.
+pub fn vec_dot_q4_k_q8_k_ref(xs: &[BlockQ4K], ys: &[BlockQ8K]) -> f32 {
+    let mut q = [0u8; QK_K];
+    xs.iter()
+        .zip(ys.iter())
+        .map(|(x, y)| {
+            for (j, qs) in x.qs.chunks_exact(32).enumerate() {
+                for l in 0..32 {
+                    q[64 * j + l] = qs[l] & 0xF;
+                    q[64 * j + l + 32] = qs[l] >> 4;
+                }
+            }
+            vec_dot_scale_min_k4(&q, &x.scales, x.d, x.dmin, y)
+        })
+        .sum()
+}
+
+/// Scalar version of [`vec_dot_q5_k_q8_k`], used as a fallback and as a reference.
+pub fn vec_dot_q5_k_q8_k_ref(xs: &[BlockQ5K], ys: &[BlockQ8K]) -> f32 {
+    let mut q = [0u8; QK_K];
+    xs.iter()
+        .zip(ys.iter())
+        .map(|(x, y)| {
+            let (mut u1, mut u2) = (1u8, 2u8);
+            for (j, qs) in x.qs.chunks_exact(32).enumerate() {
+                for l in 0..32 {
+                    let h1 = if x.qh[l] & u1 != 0 { 16 } else { 0 };
+                    let h2 = if x.qh[l] & u2 != 0 { 16 } else { 0 };
+                    q[64 * j + l] = (qs[l] & 0xF) + h1;
+                    q[64 * j + l + 32] = (qs[l] >> 4) + h2;
+                }
+                u1 <<= 2;
+                u2 <<= 2;
+            }
+            vec_dot_scale_min_k4(&q, &x.scales, x.d, x.dmin, y)
+        })
+        .sum()
+}
+
+/// Scalar version of [`vec_dot_q6_k_q8_k`], used as a fallback and as a reference.
+pub fn vec_dot_q6_k_q8_k_ref(xs: &[BlockQ6K], ys: &[BlockQ8K]) -> f32 {
+    let mut q = [0i8; QK_K];
+    xs.iter()
+        .zip(ys.iter())
+        .map(|(x, y)| {
+            for n in 0..QK_K / 128 {
+                let ql = &x.ql[64 * n..];
+                let qh = &x.qh[32 * n..];
+                let q = &mut q[128 * n..];
+                for l in 0..32 {
+                    q[l] = ((ql[l] & 0xF) | ((qh[l] & 3) << 4)) as i8 - 32;
+                    q[l + 32] = ((ql[l + 32] & 0xF) | (((qh[l] >> 2) & 3) << 4)) as i8 - 32;
+                    q[l + 64] = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i8 - 32;
+                    q[l + 96] = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i8 - 32;
+                }
+            }
+            let sumi: i32 = x
+                .scales
+                .iter()
+                .enumerate()
+                .map(|(j, &sc)| {
+                    let dot: i32 = q[16 * j..16 * (j + 1)]
+                        .iter()
+                        .zip(&y.qs[16 * j..16 * (j + 1)])
+                        .map(|(&q, &y)| q as i32 * y as i32)
+                        .sum();
+                    sc as i32 * dot
+                })
+                .sum();
+            x.d.to_f32() * y.d * sumi as f32
+        })
+        .sum()
+}
+
+pub fn vec_dot_q8_k_q8_k(n: usize, xs: &[BlockQ8K], ys: &[BlockQ8K]) -> f32 {
+    assert_eq!(n % QK_K, 0);
+    xs.iter()
+        .zip(ys.iter())
+        .take(n / QK_K)
+        .map(|(x, y)| {
+            let sumi: i32 =
+                x.qs.iter()
+                    .zip(y.qs.iter())
+                    .map(|(&a, &b)| a as i32 * b as i32)
+                    .sum();
+            x.d * y.d * sumi as f32
+        })
+        .sum()
+}
+
+impl GgmlType for BlockQ4K {
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            dmin: f16::ZERO,
+            scales: [0; K_SCALE_SIZE],
+            qs: [0; QK_K / 2],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) {
+        dequantize_row_q4_k(xs, ys)
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) {
+        quantize_row_q4_k(xs, ys)
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[BlockQ8K]) -> f32 {
+        vec_dot_q4_k_q8_k(n, xs, ys)
+    }
+}
+
+impl GgmlType for BlockQ5K {
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            dmin: f16::ZERO,
+            scales: [0; K_SCALE_SIZE],
+            qh: [0; QK_K / 8],
+            qs: [0; QK_K / 2],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) {
+        dequantize_row_q5_k(xs, ys)
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) {
+        quantize_row_q5_k(xs, ys)
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[BlockQ8K]) -> f32 {
+        vec_dot_q5_k_q8_k(n, xs, ys)
+    }
+}
+
+impl GgmlType for BlockQ6K {
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            ql: [0; QK_K / 2],
+            qh: [0; QK_K / 4],
+            scales: [0; QK_K / 16],
+            d: f16::ZERO,
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) {
+        dequantize_row_q6_k(xs, ys)
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) {
+        quantize_row_q6_k(xs, ys)
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[BlockQ8K]) -> f32 {
+        vec_dot_q6_k_q8_k(n, xs, ys)
+    }
+}
+
+impl GgmlType for BlockQ8K {
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            d: 0.0,
+            qs: [0; QK_K],
+            bsums: [0; QK_K / 16],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) {
+        dequantize_row_q8_k(xs, ys)
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) {
+        quantize_row_q8_k(xs, ys)
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[BlockQ8K]) -> f32 {
+        vec_dot_q8_k_q8_k(n, xs, ys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(n: usize, seed: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| (((i + seed) * 7919) % 113) as f32 / 40.0 - 1.3)
+            .collect()
+    }
+
+    /// Quantizes and dequantizes `xs`, returning the largest absolute error.
+    fn roundtrip<T: GgmlType>(xs: &[f32]) -> f32 {
+        let mut blocks = vec![T::zeros(); xs.len() / T::BLCK_SIZE];
+        T::from_float(xs, &mut blocks);
+        let mut ys = vec![0.0; xs.len()];
+        T::to_float(&blocks, &mut ys);
+        xs.iter()
+            .zip(ys.iter())
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn k_quants_roundtrip() {
+        // Values span [-1.3, 1.5], so these bounds are a fraction of the range
+        // proportional to the number of bits.
+        let xs = data(4 * QK_K, 0);
+        assert!(roundtrip::<BlockQ4K>(&xs) < 0.15);
+        assert!(roundtrip::<BlockQ5K>(&xs) < 0.07);
+        assert!(roundtrip::<BlockQ6K>(&xs) < 0.04);
+        assert!(roundtrip::<BlockQ8K>(&xs) < 0.01);
+    }
+
+    #[test]
+    fn k_quants_zeros() {
+        let xs = vec![0.0; QK_K];
+        assert_eq!(roundtrip::<BlockQ4K>(&xs), 0.0);
+        assert_eq!(roundtrip::<BlockQ5K>(&xs), 0.0);
+        assert_eq!(roundtrip::<BlockQ6K>(&xs), 0.0);
+        assert_eq!(roundtrip::<BlockQ8K>(&xs), 0.0);
+    }
+
+    #[test]
+    fn q4_k_scales_layout() {
+        // Sub-blocks 4..8 spread their 6 bits over the low nibbles of bytes 8..12
+        // and the top 2 bits of bytes 0..8.
+        let scales = [0b11_000001, 2, 3, 4, 0b10_000101, 6, 7, 8, 0x9A, 0, 0, 0];
+        assert_eq!(get_scale_min_k4(0, &scales), (1, 5));
+        assert_eq!(
+            get_scale_min_k4(4, &scales),
+            (0xA | (3 << 4), 0x9 | (2 << 4))
+        );
+    }
+
+    fn check_vec_dot<T: GgmlType<VecDotType = BlockQ8K>>(reference: fn(&[T], &[BlockQ8K]) -> f32) {
+        let k = 3 * QK_K;
+        let xs = data(k, 0);
+        let ys = data(k, 5);
+        let mut x_q = vec![T::zeros(); k / QK_K];
+        let mut y_q = vec![BlockQ8K::zeros(); k / QK_K];
+        T::from_float(&xs, &mut x_q);
+        BlockQ8K::from_float(&ys, &mut y_q);
+
+        let expected = reference(&x_q, &y_q);
+        let got = T::vec_dot(k, &x_q, &y_q);
+        assert!(
+            (expected - got).abs() <= 1e-4 * expected.abs().max(1.0),
+            "{expected} != {got}"
+        );
+
+        let mut x_deq = vec![0.0; k];
+        let mut y_deq = vec![0.0; k];
+        T::to_float(&x_q, &mut x_deq);
+        BlockQ8K::to_float(&y_q, &mut y_deq);
+        let dequantized: f32 = x_deq.iter().zip(y_deq.iter()).map(|(x, y)| x * y).sum();
+        assert!(
+            (dequantized - got).abs() <= 1e-3 * dequantized.abs().max(1.0),
+            "{dequantized} != {got}"
+        );
+    }
+
+    #[test]
+    fn vec_dot_matches_reference() {
+        check_vec_dot::<BlockQ4K>(vec_dot_q4_k_q8_k_ref);
+        check_vec_dot::<BlockQ5K>(vec_dot_q5_k_q8_k_ref);
+        check_vec_dot::<BlockQ6K>(vec_dot_q6_k_q8_k_ref);
+    }
+}
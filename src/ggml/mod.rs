@@ -1,3 +1,11 @@
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+))]
 trait Cpu<const ARR: usize> {
     type Unit;
     type Array;
@@ -9,36 +17,49 @@ trait Cpu<const ARR: usize> {
     unsafe fn zero_array() -> Self::Array;
     unsafe fn load(mem_addr: *const f32) -> Self::Unit;
     unsafe fn vec_fma(a: Self::Unit, b: Self::Unit, c: Self::Unit) -> Self::Unit;
+    unsafe fn vec_abs(a: Self::Unit) -> Self::Unit;
+    /// Swaps every pair of lanes, `[a0, a1, a2, a3]` becomes `[a1, a0, a3, a2]`.
+    unsafe fn vec_swap_pairs(a: Self::Unit) -> Self::Unit;
     unsafe fn vec_reduce(x: Self::Array, y: *mut f32);
     unsafe fn from_f32(v: f32) -> Self::Unit;
     unsafe fn vec_store(mem_addr: *mut f32, a: Self::Unit);
 }
 
+// Miri does not run the intrinsics, it gets the scalar fallbacks.
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[cfg(target_feature = "avx")]
+#[cfg(not(miri))]
 pub mod avx;
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[cfg(target_feature = "avx")]
+#[cfg(not(miri))]
 pub use avx::CurrentCpu;
 
 #[cfg(target_arch = "wasm32")]
 #[cfg(target_feature = "simd128")]
+#[cfg(not(miri))]
 pub mod simd128;
 #[cfg(target_arch = "wasm32")]
 #[cfg(target_feature = "simd128")]
+#[cfg(not(miri))]
 pub use simd128::CurrentCpu;
 
 #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
 #[cfg(target_feature = "neon")]
+#[cfg(not(miri))]
 pub mod neon;
 #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
 #[cfg(target_feature = "neon")]
+#[cfg(not(miri))]
 pub use neon::CurrentCpu;
 
-#[cfg(any(
-    target_feature = "neon",
-    target_feature = "avx",
-    target_feature = "simd128"
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
 ))]
 #[inline(never)]
 /// # Safety
@@ -67,10 +88,13 @@ pub unsafe fn vec_dot_f32(a_row: *const f32, b_row: *const f32, c: *mut f32, k:
     }
 }
 
-#[cfg(any(
-    target_feature = "neon",
-    target_feature = "avx",
-    target_feature = "simd128"
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
 ))]
 /// # Safety
 /// This requires the user to check that `n` is actually valid  for all pointers
@@ -96,38 +120,159 @@ pub unsafe fn vec_mad_f32(b_row: *const f32, c_row: *mut f32, v: f32, n: usize)
     }
 }
 
-#[cfg(not(any(
-    target_feature = "neon",
-    target_feature = "avx",
-    target_feature = "simd128"
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+))]
+/// # Safety
+/// This requires the user to check that `n` is actually valid  for all pointers
+pub unsafe fn vec_scale_f32(x: *mut f32, v: f32, n: usize) {
+    let np = n & !(CurrentCpu::STEP - 1);
+
+    // -0.0 is the additive identity that keeps the sign of zeros.
+    let zero = CurrentCpu::from_f32(-0.0);
+    let vx = CurrentCpu::from_f32(v);
+    for i in (0..np).step_by(CurrentCpu::STEP) {
+        for j in 0..CurrentCpu::n() {
+            let ax = CurrentCpu::load(x.add(i + j * CurrentCpu::EPR));
+            let ax = CurrentCpu::vec_fma(zero, ax, vx);
+            CurrentCpu::vec_store(x.add(i + j * CurrentCpu::EPR), ax);
+        }
+    }
+
+    // leftovers
+    for i in np..n {
+        *x.add(i) *= v;
+    }
+}
+
+#[cfg(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+))]
+/// # Safety
+/// This requires the user to check that `n` is actually valid  for all pointers
+pub unsafe fn vec_asum_f32(x: *const f32, n: usize) -> f32 {
+    let np = n & !(CurrentCpu::STEP - 1);
+
+    let one = CurrentCpu::from_f32(1.0);
+    let mut sum = CurrentCpu::zero_array();
+    let mut ax = CurrentCpu::zero_array();
+
+    for i in (0..np).step_by(CurrentCpu::STEP) {
+        for j in 0..CurrentCpu::n() {
+            ax[j] = CurrentCpu::vec_abs(CurrentCpu::load(x.add(i + j * CurrentCpu::EPR)));
+            sum[j] = CurrentCpu::vec_fma(sum[j], ax[j], one);
+        }
+    }
+
+    let mut sumf = 0.0;
+    CurrentCpu::vec_reduce(sum, &mut sumf);
+
+    // leftovers
+    for i in np..n {
+        sumf += (*x.add(i)).abs();
+    }
+    sumf
+}
+
+#[cfg(not(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
 )))]
+/// # Safety
+/// This requires the user to check that `k` is actually valid  for all pointers
 #[inline(never)]
 pub unsafe fn vec_dot_f32(a_row: *const f32, b_row: *const f32, c: *mut f32, k: usize) {
-    // leftovers
+    let mut sum = 0.0;
     for i in 0..k {
-        *c += *a_row.add(i) * (*b_row.add(i));
+        sum += *a_row.add(i) * (*b_row.add(i));
     }
+    *c = sum;
 }
 
-#[cfg(not(any(
-    target_feature = "neon",
-    target_feature = "avx",
-    target_feature = "simd128"
+#[cfg(not(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
 )))]
+/// # Safety
+/// This requires the user to check that `n` is actually valid  for all pointers
 pub unsafe fn vec_mad_f32(a_row: *const f32, c_row: *mut f32, v: f32, n: usize) {
     for i in 0..n {
         *c_row.add(i) += *a_row.add(i) * v;
     }
 }
 
+#[cfg(not(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+)))]
+/// # Safety
+/// This requires the user to check that `n` is actually valid  for all pointers
+pub unsafe fn vec_scale_f32(x: *mut f32, v: f32, n: usize) {
+    for i in 0..n {
+        *x.add(i) *= v;
+    }
+}
+
+#[cfg(not(all(
+    not(miri),
+    any(
+        target_feature = "neon",
+        target_feature = "avx",
+        target_feature = "simd128"
+    )
+)))]
+/// # Safety
+/// This requires the user to check that `n` is actually valid  for all pointers
+pub unsafe fn vec_asum_f32(x: *const f32, n: usize) -> f32 {
+    let mut sum = 0.0;
+    for i in 0..n {
+        sum += (*x.add(i)).abs();
+    }
+    sum
+}
+
+pub mod accurate;
+pub mod complex;
+pub mod int8;
+pub mod k_quants;
+pub mod quants;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[cfg(all(target_feature = "avx2", not(miri)))]
+mod avx_quants;
+
 #[cfg(feature = "f16")]
 #[cfg(target_arch = "wasm32")]
 #[cfg(target_feature = "simd128")]
+#[cfg(not(miri))]
 pub mod simd128_f16;
 
 #[cfg(feature = "f16")]
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[cfg(target_feature = "avx")]
+#[cfg(not(miri))]
 mod avx_f16;
 
 #[cfg(feature = "f16")]
@@ -135,14 +280,17 @@ mod avx_f16;
 pub mod f16 {
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     #[cfg(target_feature = "avx")]
+    #[cfg(not(miri))]
     pub use super::avx_f16::CurrentCpuF16;
 
     #[cfg(target_arch = "wasm32")]
     #[cfg(target_feature = "simd128")]
+    #[cfg(not(miri))]
     pub use super::simd128_f16::CurrentCpuF16;
 
     use half::f16;
 
+    #[allow(clippy::missing_safety_doc)]
     pub trait CpuF16<const ARR: usize> {
         type Unit;
         type Array;
@@ -154,16 +302,22 @@ pub mod f16 {
         unsafe fn zero_array() -> Self::Array;
         unsafe fn load(mem_addr: *const f16) -> Self::Unit;
         unsafe fn vec_fma(a: Self::Unit, b: Self::Unit, c: Self::Unit) -> Self::Unit;
+        unsafe fn vec_abs(a: Self::Unit) -> Self::Unit;
         unsafe fn vec_reduce(x: Self::Array, y: *mut f32);
         unsafe fn from_f32(v: f32) -> Self::Unit;
         unsafe fn vec_store(mem_addr: *mut f16, a: Self::Unit);
     }
 
-    #[cfg(any(
-        target_feature = "neon",
-        target_feature = "avx",
-        target_feature = "simd128"
+    #[cfg(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
     ))]
+    /// # Safety
+    /// This requires the user to check that `k` is actually valid  for all pointers
     #[inline(never)]
     pub unsafe fn vec_dot_f16(a_row: *const f16, b_row: *const f16, c: *mut f32, k: usize) {
         let mut sumf = 0.0f32;
@@ -191,11 +345,16 @@ pub mod f16 {
         *c = sumf;
     }
 
-    #[cfg(not(any(
-        target_feature = "neon",
-        target_feature = "avx",
-        target_feature = "simd128"
+    #[cfg(not(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
     )))]
+    /// # Safety
+    /// This requires the user to check that `k` is actually valid  for all pointers
     #[inline(never)]
     pub unsafe fn vec_dot_f16(a_row: *const f16, b_row: *const f16, c: *mut f32, k: usize) {
         // leftovers
@@ -206,6 +365,157 @@ pub mod f16 {
         *c = sum;
     }
 
+    #[cfg(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
+    ))]
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
+    pub unsafe fn vec_mad_f16(b_row: *const f16, c_row: *mut f16, v: f32, n: usize) {
+        let np = n & !(CurrentCpuF16::STEP - 1);
+
+        let vx = CurrentCpuF16::from_f32(v);
+        let mut ax = CurrentCpuF16::zero_array();
+        let mut ay = CurrentCpuF16::zero_array();
+
+        for i in (0..np).step_by(CurrentCpuF16::STEP) {
+            for j in 0..CurrentCpuF16::n() {
+                ax[j] = CurrentCpuF16::load(b_row.add(i + j * CurrentCpuF16::EPR));
+                ay[j] = CurrentCpuF16::load(c_row.add(i + j * CurrentCpuF16::EPR));
+                ay[j] = CurrentCpuF16::vec_fma(ay[j], ax[j], vx);
+                CurrentCpuF16::vec_store(c_row.add(i + j * CurrentCpuF16::EPR), ay[j]);
+            }
+        }
+
+        // leftovers
+        for i in np..n {
+            let c = (*c_row.add(i)).to_f32() + (*b_row.add(i)).to_f32() * v;
+            *c_row.add(i) = f16::from_f32(c);
+        }
+    }
+
+    #[cfg(not(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
+    )))]
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
+    pub unsafe fn vec_mad_f16(b_row: *const f16, c_row: *mut f16, v: f32, n: usize) {
+        for i in 0..n {
+            let c = (*c_row.add(i)).to_f32() + (*b_row.add(i)).to_f32() * v;
+            *c_row.add(i) = f16::from_f32(c);
+        }
+    }
+
+    #[cfg(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
+    ))]
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
+    pub unsafe fn vec_scale_f16(x: *mut f16, v: f32, n: usize) {
+        let np = n & !(CurrentCpuF16::STEP - 1);
+
+        // -0.0 is the additive identity that keeps the sign of zeros.
+        let zero = CurrentCpuF16::from_f32(-0.0);
+        let vx = CurrentCpuF16::from_f32(v);
+        for i in (0..np).step_by(CurrentCpuF16::STEP) {
+            for j in 0..CurrentCpuF16::n() {
+                let ax = CurrentCpuF16::load(x.add(i + j * CurrentCpuF16::EPR));
+                let ax = CurrentCpuF16::vec_fma(zero, ax, vx);
+                CurrentCpuF16::vec_store(x.add(i + j * CurrentCpuF16::EPR), ax);
+            }
+        }
+
+        // leftovers
+        for i in np..n {
+            *x.add(i) = f16::from_f32((*x.add(i)).to_f32() * v);
+        }
+    }
+
+    #[cfg(not(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
+    )))]
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
+    pub unsafe fn vec_scale_f16(x: *mut f16, v: f32, n: usize) {
+        for i in 0..n {
+            *x.add(i) = f16::from_f32((*x.add(i)).to_f32() * v);
+        }
+    }
+
+    #[cfg(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
+    ))]
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
+    pub unsafe fn vec_asum_f16(x: *const f16, n: usize) -> f32 {
+        let np = n & !(CurrentCpuF16::STEP - 1);
+
+        let one = CurrentCpuF16::from_f32(1.0);
+        let mut sum = CurrentCpuF16::zero_array();
+        let mut ax = CurrentCpuF16::zero_array();
+
+        for i in (0..np).step_by(CurrentCpuF16::STEP) {
+            for j in 0..CurrentCpuF16::n() {
+                ax[j] =
+                    CurrentCpuF16::vec_abs(CurrentCpuF16::load(x.add(i + j * CurrentCpuF16::EPR)));
+                sum[j] = CurrentCpuF16::vec_fma(sum[j], ax[j], one);
+            }
+        }
+
+        let mut sumf = 0.0;
+        CurrentCpuF16::vec_reduce(sum, &mut sumf);
+
+        // leftovers
+        for i in np..n {
+            sumf += (*x.add(i)).to_f32().abs();
+        }
+        sumf
+    }
+
+    #[cfg(not(all(
+        not(miri),
+        any(
+            target_feature = "neon",
+            target_feature = "avx",
+            target_feature = "simd128"
+        )
+    )))]
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
+    pub unsafe fn vec_asum_f16(x: *const f16, n: usize) -> f32 {
+        let mut sum = 0.0;
+        for i in 0..n {
+            sum += (*x.add(i)).to_f32().abs();
+        }
+        sum
+    }
+
+    /// # Safety
+    /// This requires the user to check that `n` is actually valid  for all pointers
     pub unsafe fn f32_to_f16(x: *const f32, y: *mut f16, n: usize) {
         let mut i = 0;
         #[cfg(target_feature = "f16c")]
@@ -235,3 +545,76 @@ pub mod f16 {
         }
     }
 }
+
+/// The same checks run on every kernel set, SIMD or scalar, over lengths
+/// crossing the steps and their leftovers. Small integers keep every result
+/// exact.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LENGTHS: [usize; 9] = [0, 1, 3, 4, 15, 16, 17, 33, 70];
+
+    fn data(n: usize, seed: usize) -> Vec<f32> {
+        (0..n).map(|i| ((i * 7 + seed) % 9) as f32 - 4.0).collect()
+    }
+
+    #[test]
+    fn f32_kernels() {
+        for n in LENGTHS {
+            let (x, y) = (data(n, 1), data(n, 2));
+
+            let mut dot = f32::NAN;
+            unsafe { vec_dot_f32(x.as_ptr(), y.as_ptr(), &mut dot, n) };
+            assert_eq!(dot, x.iter().zip(&y).map(|(x, y)| x * y).sum::<f32>());
+
+            let mut mad = y.clone();
+            unsafe { vec_mad_f32(x.as_ptr(), mad.as_mut_ptr(), 3.0, n) };
+            let expected: Vec<f32> = x.iter().zip(&y).map(|(x, y)| x * 3.0 + y).collect();
+            assert_eq!(mad, expected);
+
+            let mut scaled = x.clone();
+            unsafe { vec_scale_f32(scaled.as_mut_ptr(), -0.5, n) };
+            let expected: Vec<f32> = x.iter().map(|x| x * -0.5).collect();
+            assert_eq!(scaled, expected);
+
+            let asum = unsafe { vec_asum_f32(x.as_ptr(), n) };
+            assert_eq!(asum, x.iter().map(|x| x.abs()).sum::<f32>());
+        }
+    }
+
+    #[cfg(feature = "f16")]
+    #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+    #[test]
+    fn f16_kernels() {
+        use super::f16::*;
+        use half::f16;
+
+        let half = |x: &[f32]| -> Vec<f16> { x.iter().map(|&x| f16::from_f32(x)).collect() };
+        for n in LENGTHS {
+            let (x, y) = (data(n, 1), data(n, 2));
+            let (x16, y16) = (half(&x), half(&y));
+
+            let mut dot = f32::NAN;
+            unsafe { vec_dot_f16(x16.as_ptr(), y16.as_ptr(), &mut dot, n) };
+            assert_eq!(dot, x.iter().zip(&y).map(|(x, y)| x * y).sum::<f32>());
+
+            let mut mad = y16.clone();
+            unsafe { vec_mad_f16(x16.as_ptr(), mad.as_mut_ptr(), 3.0, n) };
+            let expected: Vec<f32> = x.iter().zip(&y).map(|(x, y)| x * 3.0 + y).collect();
+            assert_eq!(mad, half(&expected));
+
+            let mut scaled = x16.clone();
+            unsafe { vec_scale_f16(scaled.as_mut_ptr(), -0.5, n) };
+            let expected: Vec<f32> = x.iter().map(|x| x * -0.5).collect();
+            assert_eq!(scaled, half(&expected));
+
+            let asum = unsafe { vec_asum_f16(x16.as_ptr(), n) };
+            assert_eq!(asum, x.iter().map(|x| x.abs()).sum::<f32>());
+
+            let mut converted = vec![f16::ZERO; n];
+            unsafe { f32_to_f16(x.as_ptr(), converted.as_mut_ptr(), n) };
+            assert_eq!(converted, x16);
+        }
+    }
+}
@@ -54,6 +54,14 @@ impl Cpu<ARR> for CurrentCpu {
         vfmaq_f32(a, b, c)
     }
 
+    unsafe fn vec_abs(a: Self::Unit) -> Self::Unit {
+        vabsq_f32(a)
+    }
+
+    unsafe fn vec_swap_pairs(a: Self::Unit) -> Self::Unit {
+        vrev64q_f32(a)
+    }
+
     unsafe fn vec_store(mem_addr: *mut f32, a: Self::Unit) {
         vst1q_f32(mem_addr, a);
     }
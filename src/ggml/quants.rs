@@ -0,0 +1,297 @@
+//! Block quantization formats ported from ggml.
+//!
+//! The blocks are `#[repr(C)]` and share the exact bit layout of their ggml
+//! counterparts, so weights read from a ggml/GGUF file can be reinterpreted
+//! directly as slices of these types.
+use half::f16;
+
+pub const QK4_0: usize = 32;
+pub const QK8_0: usize = 32;
+
+/// Describes a ggml block format: how to go from and to `f32`, and how to
+/// compute a dot product against the format used for the activations.
+pub trait GgmlType: Sized + Clone + Send + Sync {
+    /// Number of values stored in a single block.
+    const BLCK_SIZE: usize;
+    /// Format the activations get quantized to before calling [`GgmlType::vec_dot`].
+    type VecDotType: GgmlType;
+
+    fn zeros() -> Self;
+    /// `ys.len()` must be `xs.len() * Self::BLCK_SIZE`.
+    fn to_float(xs: &[Self], ys: &mut [f32]);
+    /// `xs.len()` must be `ys.len() * Self::BLCK_SIZE`.
+    fn from_float(xs: &[f32], ys: &mut [Self]);
+    /// Dot product of `n` values stored in `xs` and `ys`.
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> f32;
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQ4_0 {
+    pub d: f16,
+    pub qs: [u8; QK4_0 / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ4_0>() == 18);
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQ8_0 {
+    pub d: f16,
+    pub qs: [i8; QK8_0],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ8_0>() == 34);
+
+impl GgmlType for BlockQ4_0 {
+    const BLCK_SIZE: usize = QK4_0;
+    type VecDotType = BlockQ8_0;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            qs: [0; QK4_0 / 2],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) {
+        dequantize_row_q4_0(xs, ys)
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) {
+        quantize_row_q4_0(xs, ys)
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[BlockQ8_0]) -> f32 {
+        vec_dot_q4_0_q8_0(n, xs, ys)
+    }
+}
+
+impl GgmlType for BlockQ8_0 {
+    const BLCK_SIZE: usize = QK8_0;
+    type VecDotType = BlockQ8_0;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            qs: [0; QK8_0],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) {
+        dequantize_row_q8_0(xs, ys)
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) {
+        quantize_row_q8_0(xs, ys)
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[BlockQ8_0]) -> f32 {
+        vec_dot_q8_0_q8_0(n, xs, ys)
+    }
+}
+
+pub fn quantize_row_q4_0(xs: &[f32], ys: &mut [BlockQ4_0]) {
+    let qk = QK4_0;
+    assert_eq!(xs.len(), ys.len() * qk);
+    for (x, y) in xs.chunks_exact(qk).zip(ys.iter_mut()) {
+        // Keep the sign of the value with the largest magnitude so that it maps
+        // exactly onto -8.
+        let mut amax = 0f32;
+        let mut max = 0f32;
+        for &v in x {
+            if amax < v.abs() {
+                amax = v.abs();
+                max = v;
+            }
+        }
+        let d = max / -8.0;
+        let id = if d != 0.0 { 1. / d } else { 0. };
+        y.d = f16::from_f32(d);
+        for j in 0..qk / 2 {
+            let x0 = x[j] * id;
+            let x1 = x[qk / 2 + j] * id;
+            let xi0 = u8::min(15, (x0 + 8.5) as u8);
+            let xi1 = u8::min(15, (x1 + 8.5) as u8);
+            y.qs[j] = xi0 | (xi1 << 4);
+        }
+    }
+}
+
+pub fn dequantize_row_q4_0(xs: &[BlockQ4_0], ys: &mut [f32]) {
+    let qk = QK4_0;
+    assert_eq!(ys.len(), xs.len() * qk);
+    for (x, y) in xs.iter().zip(ys.chunks_exact_mut(qk)) {
+        let d = x.d.to_f32();
+        for j in 0..qk / 2 {
+            let x0 = (x.qs[j] & 0x0F) as i16 - 8;
+            let x1 = (x.qs[j] >> 4) as i16 - 8;
+            y[j] = x0 as f32 * d;
+            y[j + qk / 2] = x1 as f32 * d;
+        }
+    }
+}
+
+pub fn quantize_row_q8_0(xs: &[f32], ys: &mut [BlockQ8_0]) {
+    let qk = QK8_0;
+    assert_eq!(xs.len(), ys.len() * qk);
+    for (x, y) in xs.chunks_exact(qk).zip(ys.iter_mut()) {
+        let amax = x.iter().fold(0f32, |acc, &v| acc.max(v.abs()));
+        let d = amax / ((1 << 7) - 1) as f32;
+        let id = if d != 0.0 { 1. / d } else { 0. };
+        y.d = f16::from_f32(d);
+        for (q, &v) in y.qs.iter_mut().zip(x.iter()) {
+            *q = (v * id).round() as i8;
+        }
+    }
+}
+
+pub fn dequantize_row_q8_0(xs: &[BlockQ8_0], ys: &mut [f32]) {
+    let qk = QK8_0;
+    assert_eq!(ys.len(), xs.len() * qk);
+    for (x, y) in xs.iter().zip(ys.chunks_exact_mut(qk)) {
+        let d = x.d.to_f32();
+        for (v, &q) in y.iter_mut().zip(x.qs.iter()) {
+            *v = q as f32 * d;
+        }
+    }
+}
+
+pub fn vec_dot_q4_0_q8_0(n: usize, xs: &[BlockQ4_0], ys: &[BlockQ8_0]) -> f32 {
+    assert_eq!(n % QK8_0, 0);
+    let nb = n / QK8_0;
+    assert!(xs.len() >= nb && ys.len() >= nb);
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[cfg(all(target_feature = "avx2", not(miri)))]
+    unsafe {
+        super::avx_quants::vec_dot_q4_0_q8_0(&xs[..nb], &ys[..nb])
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "avx2",
+        not(miri)
+    )))]
+    vec_dot_q4_0_q8_0_ref(&xs[..nb], &ys[..nb])
+}
+
+pub fn vec_dot_q8_0_q8_0(n: usize, xs: &[BlockQ8_0], ys: &[BlockQ8_0]) -> f32 {
+    assert_eq!(n % QK8_0, 0);
+    let nb = n / QK8_0;
+    assert!(xs.len() >= nb && ys.len() >= nb);
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[cfg(all(target_feature = "avx2", not(miri)))]
+    unsafe {
+        super::avx_quants::vec_dot_q8_0_q8_0(&xs[..nb], &ys[..nb])
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "avx2",
+        not(miri)
+    )))]
+    vec_dot_q8_0_q8_0_ref(&xs[..nb], &ys[..nb])
+}
+
+/// Scalar version of [`vec_dot_q4_0_q8_0`], used as a fallback and as a reference.
+pub fn vec_dot_q4_0_q8_0_ref(xs: &[BlockQ4_0], ys: &[BlockQ8_0]) -> f32 {
+    let mut sumf = 0f32;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let mut sumi = 0i32;
+        for j in 0..QK8_0 / 2 {
+            let v0 = (x.qs[j] & 0x0F) as i32 - 8;
+            let v1 = (x.qs[j] >> 4) as i32 - 8;
+            sumi += v0 * y.qs[j] as i32 + v1 * y.qs[j + QK8_0 / 2] as i32;
+        }
+        sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32();
+    }
+    sumf
+}
+
+/// Scalar version of [`vec_dot_q8_0_q8_0`], used as a fallback and as a reference.
+pub fn vec_dot_q8_0_q8_0_ref(xs: &[BlockQ8_0], ys: &[BlockQ8_0]) -> f32 {
+    let mut sumf = 0f32;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let sumi: i32 =
+            x.qs.iter()
+                .zip(y.qs.iter())
+                .map(|(&a, &b)| a as i32 * b as i32)
+                .sum();
+        sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32();
+    }
+    sumf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| ((i * 7919) % 61) as f32 / 30.0 - 1.0)
+            .collect()
+    }
+
+    #[test]
+    fn q4_0_roundtrip() {
+        let xs = data(4 * QK4_0);
+        let mut blocks = vec![BlockQ4_0::zeros(); 4];
+        quantize_row_q4_0(&xs, &mut blocks);
+        let mut ys = vec![0.0; xs.len()];
+        dequantize_row_q4_0(&blocks, &mut ys);
+        for (block, (x, y)) in xs.chunks(QK4_0).zip(ys.chunks(QK4_0)).enumerate() {
+            let d = blocks[block].d.to_f32().abs();
+            x.iter()
+                .zip(y)
+                .for_each(|(x, y)| assert!((x - y).abs() <= d));
+        }
+    }
+
+    #[test]
+    fn q8_0_roundtrip() {
+        let xs = data(4 * QK8_0);
+        let mut blocks = vec![BlockQ8_0::zeros(); 4];
+        quantize_row_q8_0(&xs, &mut blocks);
+        let mut ys = vec![0.0; xs.len()];
+        dequantize_row_q8_0(&blocks, &mut ys);
+        for (block, (x, y)) in xs.chunks(QK8_0).zip(ys.chunks(QK8_0)).enumerate() {
+            let d = blocks[block].d.to_f32();
+            x.iter()
+                .zip(y)
+                .for_each(|(x, y)| assert!((x - y).abs() <= d));
+        }
+    }
+
+    #[test]
+    fn q4_0_layout() {
+        // Low nibble j holds x[j], high nibble j holds x[j + 16], both offset by 8.
+        let mut xs = vec![0.0; QK4_0];
+        xs[0] = -8.0;
+        xs[16] = 7.0;
+        let mut blocks = vec![BlockQ4_0::zeros()];
+        quantize_row_q4_0(&xs, &mut blocks);
+        assert_eq!(blocks[0].d.to_f32(), 1.0);
+        assert_eq!(blocks[0].qs[0], 0xF0);
+        assert_eq!(blocks[0].qs[1], 0x88);
+    }
+
+    #[test]
+    fn vec_dot_matches_reference() {
+        let k = 8 * QK8_0;
+        let xs = data(k);
+        let ys: Vec<f32> = data(k + 3)[3..].to_vec();
+        let mut x4 = vec![BlockQ4_0::zeros(); k / QK4_0];
+        let mut x8 = vec![BlockQ8_0::zeros(); k / QK8_0];
+        let mut y8 = vec![BlockQ8_0::zeros(); k / QK8_0];
+        quantize_row_q4_0(&xs, &mut x4);
+        quantize_row_q8_0(&xs, &mut x8);
+        quantize_row_q8_0(&ys, &mut y8);
+
+        let expected = vec_dot_q4_0_q8_0_ref(&x4, &y8);
+        let got = vec_dot_q4_0_q8_0(k, &x4, &y8);
+        assert!((expected - got).abs() <= 1e-4 * expected.abs().max(1.0));
+
+        let expected = vec_dot_q8_0_q8_0_ref(&x8, &y8);
+        let got = vec_dot_q8_0_q8_0(k, &x8, &y8);
+        assert!((expected - got).abs() <= 1e-4 * expected.abs().max(1.0));
+
+        let exact: f32 = xs.iter().zip(ys.iter()).map(|(x, y)| x * y).sum();
+        assert!((exact - got).abs() < 0.1, "{exact} {got}");
+    }
+}
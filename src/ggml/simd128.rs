@@ -7,6 +7,20 @@ const STEP: usize = 16;
 const EPR: usize = 4;
 const ARR: usize = STEP / EPR;
 
+/// `a * b + c`, fused or not as the engine prefers with relaxed SIMD. Both
+/// stay within the error bound of the dot products.
+#[cfg(target_feature = "relaxed-simd")]
+#[inline(always)]
+pub(super) unsafe fn madd(a: v128, b: v128, c: v128) -> v128 {
+    f32x4_relaxed_madd(a, b, c)
+}
+
+#[cfg(not(target_feature = "relaxed-simd"))]
+#[inline(always)]
+pub(super) unsafe fn madd(a: v128, b: v128, c: v128) -> v128 {
+    f32x4_add(f32x4_mul(a, b), c)
+}
+
 impl Cpu<ARR> for CurrentCpu {
     type Unit = v128;
     type Array = [v128; ARR];
@@ -35,7 +49,15 @@ impl Cpu<ARR> for CurrentCpu {
     }
 
     unsafe fn vec_fma(a: Self::Unit, b: Self::Unit, c: Self::Unit) -> Self::Unit {
-        f32x4_add(f32x4_mul(b, c), a)
+        madd(b, c, a)
+    }
+
+    unsafe fn vec_abs(a: Self::Unit) -> Self::Unit {
+        f32x4_abs(a)
+    }
+
+    unsafe fn vec_swap_pairs(a: Self::Unit) -> Self::Unit {
+        i32x4_shuffle::<1, 0, 3, 2>(a, a)
     }
 
     unsafe fn vec_store(mem_addr: *mut f32, a: Self::Unit) {
@@ -1,4 +1,5 @@
 use super::f16::CpuF16;
+use super::simd128::madd;
 use core::arch::wasm32::*;
 use half::f16;
 
@@ -33,21 +34,25 @@ impl CpuF16<ARR> for CurrentCpuF16 {
 
     unsafe fn load(mem_addr: *const f16) -> Self::Unit {
         let mut tmp = [0.0f32; 4];
-        for i in 0..4 {
-            tmp[i] = (*mem_addr.add(i)).to_f32();
+        for (i, t) in tmp.iter_mut().enumerate() {
+            *t = (*mem_addr.add(i)).to_f32();
         }
         v128_load(tmp.as_ptr() as *const v128)
     }
 
     unsafe fn vec_fma(a: Self::Unit, b: Self::Unit, c: Self::Unit) -> Self::Unit {
-        f32x4_add(f32x4_mul(b, c), a)
+        madd(b, c, a)
+    }
+
+    unsafe fn vec_abs(a: Self::Unit) -> Self::Unit {
+        f32x4_abs(a)
     }
 
     unsafe fn vec_store(mem_addr: *mut f16, a: Self::Unit) {
         let mut tmp = [0.0f32; 4];
         v128_store(tmp.as_mut_ptr() as *mut v128, a);
-        for i in 0..4 {
-            *mem_addr.add(i) = f16::from_f32(tmp[i]);
+        for (i, t) in tmp.into_iter().enumerate() {
+            *mem_addr.add(i) = f16::from_f32(t);
         }
     }
 
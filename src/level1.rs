@@ -0,0 +1,435 @@
+//! BLAS level-1 routines on top of the ggml SIMD kernels.
+//!
+//! Vectors are described as in BLAS by a length `n`, a slice and a stride `inc`:
+//! element `i` lives at `x[i * inc]`. Strides must be strictly positive.
+//! Contiguous vectors (`inc == 1`) go through the SIMD kernels, strided ones
+//! fall back to scalar loops.
+use crate::ggml::{vec_asum_f32, vec_dot_f32, vec_mad_f32, vec_scale_f32};
+
+fn check(name: &str, n: usize, x: &[impl Sized], inc: usize) {
+    assert!(inc > 0, "{name}: stride must be positive");
+    if n > 0 {
+        assert!(
+            (n - 1).checked_mul(inc).is_some_and(|last| last < x.len()),
+            "{name}: {n} elements with stride {inc} don't fit in {}",
+            x.len()
+        );
+    }
+}
+
+/// Computes the dot product of `x` and `y`.
+///
+/// ```
+/// use ggblas::sdot;
+///
+/// let x = vec![1., 2., 3.];
+/// let y = vec![4., 0., 5., 0., 6.];
+/// assert_eq!(sdot(3, &x, 1, &y, 2), 32.);
+/// ```
+pub fn sdot(n: usize, x: &[f32], incx: usize, y: &[f32], incy: usize) -> f32 {
+    check("sdot", n, x, incx);
+    check("sdot", n, y, incy);
+    if incx == 1 && incy == 1 {
+        let mut c = 0.0;
+        unsafe { vec_dot_f32(x.as_ptr(), y.as_ptr(), &mut c, n) };
+        c
+    } else {
+        (0..n).map(|i| x[i * incx] * y[i * incy]).sum()
+    }
+}
+
+/// Computes `y = alpha * x + y`.
+///
+/// ```
+/// use ggblas::saxpy;
+///
+/// let x = vec![1., 2., 3.];
+/// let mut y = vec![1., 1., 1.];
+/// saxpy(3, 2., &x, 1, &mut y, 1);
+/// assert_eq!(y, &[3., 5., 7.]);
+/// ```
+pub fn saxpy(n: usize, alpha: f32, x: &[f32], incx: usize, y: &mut [f32], incy: usize) {
+    check("saxpy", n, x, incx);
+    check("saxpy", n, y, incy);
+    if incx == 1 && incy == 1 {
+        unsafe { vec_mad_f32(x.as_ptr(), y.as_mut_ptr(), alpha, n) };
+    } else {
+        (0..n).for_each(|i| y[i * incy] += alpha * x[i * incx]);
+    }
+}
+
+/// Computes `x = alpha * x`.
+///
+/// ```
+/// use ggblas::sscal;
+///
+/// let mut x = vec![1., 2., 3., 4.];
+/// sscal(2, 3., &mut x, 2);
+/// assert_eq!(x, &[3., 2., 9., 4.]);
+/// ```
+pub fn sscal(n: usize, alpha: f32, x: &mut [f32], incx: usize) {
+    check("sscal", n, x, incx);
+    if incx == 1 {
+        unsafe { vec_scale_f32(x.as_mut_ptr(), alpha, n) };
+    } else {
+        (0..n).for_each(|i| x[i * incx] *= alpha);
+    }
+}
+
+/// Computes the euclidean norm of `x`.
+///
+/// The sum of squares is computed directly, when it overflows or underflows
+/// the norm is recomputed with a scaled sum of squares as in LAPACK's `slassq`.
+///
+/// ```
+/// use ggblas::snrm2;
+///
+/// let x = vec![3., 4.];
+/// assert_eq!(snrm2(2, &x, 1), 5.);
+/// assert_eq!(snrm2(2, &[3e30, 4e30], 1), 5e30);
+/// ```
+pub fn snrm2(n: usize, x: &[f32], incx: usize) -> f32 {
+    let ssq = sdot(n, x, incx, x, incx);
+    if ssq.is_finite() && ssq >= f32::MIN_POSITIVE {
+        ssq.sqrt()
+    } else {
+        scaled_nrm2((0..n).map(|i| x[i * incx]))
+    }
+}
+
+fn scaled_nrm2(x: impl Iterator<Item = f32>) -> f32 {
+    let mut scale = 0f32;
+    let mut ssq = 1f32;
+    for v in x {
+        if v.is_nan() {
+            return f32::NAN;
+        }
+        if v != 0.0 {
+            let a = v.abs();
+            if scale < a {
+                ssq = 1.0 + ssq * (scale / a) * (scale / a);
+                scale = a;
+            } else {
+                ssq += (a / scale) * (a / scale);
+            }
+        }
+    }
+    scale * ssq.sqrt()
+}
+
+/// Computes the sum of the absolute values of `x`.
+///
+/// ```
+/// use ggblas::sasum;
+///
+/// assert_eq!(sasum(3, &[1., -2., 3.], 1), 6.);
+/// ```
+pub fn sasum(n: usize, x: &[f32], incx: usize) -> f32 {
+    check("sasum", n, x, incx);
+    if incx == 1 {
+        unsafe { vec_asum_f32(x.as_ptr(), n) }
+    } else {
+        (0..n).map(|i| x[i * incx].abs()).sum()
+    }
+}
+
+/// Returns the (0-based) index of the first element with the largest absolute
+/// value, `None` when `n == 0`. A NaN counts as larger than any number, so
+/// the index of the first NaN is returned when there is one.
+///
+/// ```
+/// use ggblas::isamax;
+///
+/// assert_eq!(isamax(4, &[1., -3., 3., 2.], 1), Some(1));
+/// assert_eq!(isamax(3, &[1., f32::NAN, 0.5], 1), Some(1));
+/// assert_eq!(isamax(0, &[], 1), None);
+/// ```
+pub fn isamax(n: usize, x: &[f32], incx: usize) -> Option<usize> {
+    check("isamax", n, x, incx);
+    iamax((0..n).map(|i| x[i * incx].abs()))
+}
+
+fn iamax(x: impl Iterator<Item = f32>) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (i, v) in x.enumerate() {
+        // No later value can replace a NaN, `v <= NaN` being false.
+        if v.is_nan() {
+            return Some(i);
+        }
+        match best {
+            Some((_, max)) if v <= max => {}
+            _ => best = Some((i, v)),
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+/// Copies `x` into `y`.
+///
+/// ```
+/// use ggblas::scopy;
+///
+/// let mut y = vec![0.; 4];
+/// scopy(2, &[1., 2.], 1, &mut y, 2);
+/// assert_eq!(y, &[1., 0., 2., 0.]);
+/// ```
+pub fn scopy(n: usize, x: &[f32], incx: usize, y: &mut [f32], incy: usize) {
+    check("scopy", n, x, incx);
+    check("scopy", n, y, incy);
+    if incx == 1 && incy == 1 {
+        y[..n].copy_from_slice(&x[..n]);
+    } else {
+        (0..n).for_each(|i| y[i * incy] = x[i * incx]);
+    }
+}
+
+/// f16 variants of the level-1 routines, results are accumulated in f32.
+#[cfg(feature = "f16")]
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+pub mod f16 {
+    use super::{check, iamax, scaled_nrm2};
+    use crate::ggml::f16::{vec_asum_f16, vec_dot_f16, vec_mad_f16, vec_scale_f16};
+    use half::f16;
+
+    /// Computes the dot product of `x` and `y`.
+    pub fn hdot(n: usize, x: &[f16], incx: usize, y: &[f16], incy: usize) -> f32 {
+        check("hdot", n, x, incx);
+        check("hdot", n, y, incy);
+        if incx == 1 && incy == 1 {
+            let mut c = 0.0;
+            unsafe { vec_dot_f16(x.as_ptr(), y.as_ptr(), &mut c, n) };
+            c
+        } else {
+            (0..n)
+                .map(|i| x[i * incx].to_f32() * y[i * incy].to_f32())
+                .sum()
+        }
+    }
+
+    /// Computes `y = alpha * x + y`.
+    pub fn haxpy(n: usize, alpha: f16, x: &[f16], incx: usize, y: &mut [f16], incy: usize) {
+        check("haxpy", n, x, incx);
+        check("haxpy", n, y, incy);
+        let alpha = alpha.to_f32();
+        if incx == 1 && incy == 1 {
+            unsafe { vec_mad_f16(x.as_ptr(), y.as_mut_ptr(), alpha, n) };
+        } else {
+            (0..n).for_each(|i| {
+                let v = y[i * incy].to_f32() + alpha * x[i * incx].to_f32();
+                y[i * incy] = f16::from_f32(v);
+            });
+        }
+    }
+
+    /// Computes `x = alpha * x`.
+    pub fn hscal(n: usize, alpha: f16, x: &mut [f16], incx: usize) {
+        check("hscal", n, x, incx);
+        let alpha = alpha.to_f32();
+        if incx == 1 {
+            unsafe { vec_scale_f16(x.as_mut_ptr(), alpha, n) };
+        } else {
+            (0..n).for_each(|i| x[i * incx] = f16::from_f32(x[i * incx].to_f32() * alpha));
+        }
+    }
+
+    /// Computes the euclidean norm of `x`.
+    pub fn hnrm2(n: usize, x: &[f16], incx: usize) -> f32 {
+        let ssq = hdot(n, x, incx, x, incx);
+        if ssq.is_finite() && ssq >= f32::MIN_POSITIVE {
+            ssq.sqrt()
+        } else {
+            scaled_nrm2((0..n).map(|i| x[i * incx].to_f32()))
+        }
+    }
+
+    /// Computes the sum of the absolute values of `x`.
+    pub fn hasum(n: usize, x: &[f16], incx: usize) -> f32 {
+        check("hasum", n, x, incx);
+        if incx == 1 {
+            unsafe { vec_asum_f16(x.as_ptr(), n) }
+        } else {
+            (0..n).map(|i| x[i * incx].to_f32().abs()).sum()
+        }
+    }
+
+    /// Returns the (0-based) index of the first element with the largest
+    /// absolute value, or of the first NaN, `None` when `n == 0`.
+    pub fn ihamax(n: usize, x: &[f16], incx: usize) -> Option<usize> {
+        check("ihamax", n, x, incx);
+        iamax((0..n).map(|i| x[i * incx].to_f32().abs()))
+    }
+
+    /// Copies `x` into `y`.
+    pub fn hcopy(n: usize, x: &[f16], incx: usize, y: &mut [f16], incy: usize) {
+        check("hcopy", n, x, incx);
+        check("hcopy", n, y, incy);
+        if incx == 1 && incy == 1 {
+            y[..n].copy_from_slice(&x[..n]);
+        } else {
+            (0..n).for_each(|i| y[i * incy] = x[i * incx]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Not a multiple of any SIMD step so the leftovers get exercised.
+    const N: usize = 77;
+
+    fn data(n: usize, seed: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| ((i * 7919 + seed) % 61) as f32 / 8.0 - 3.75)
+            .collect()
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-5 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn contiguous() {
+        let x = data(N, 0);
+        let y = data(N, 13);
+
+        let expected: f32 = x.iter().zip(&y).map(|(a, b)| a * b).sum();
+        assert!(close(sdot(N, &x, 1, &y, 1), expected));
+
+        let expected: f32 = x.iter().map(|a| a.abs()).sum();
+        assert!(close(sasum(N, &x, 1), expected));
+
+        let expected: f32 = x.iter().map(|a| a * a).sum::<f32>().sqrt();
+        assert!(close(snrm2(N, &x, 1), expected));
+
+        let mut got = y.clone();
+        saxpy(N, 1.5, &x, 1, &mut got, 1);
+        for i in 0..N {
+            assert!(close(got[i], y[i] + 1.5 * x[i]));
+        }
+
+        let mut got = x.clone();
+        sscal(N, -0.5, &mut got, 1);
+        for i in 0..N {
+            assert_eq!(got[i], -0.5 * x[i]);
+        }
+
+        let mut got = vec![0.0; N];
+        scopy(N, &x, 1, &mut got, 1);
+        assert_eq!(got, x);
+    }
+
+    #[test]
+    fn strided() {
+        let x = data(3 * N, 0);
+        let y = data(2 * N, 13);
+
+        let expected: f32 = (0..N).map(|i| x[3 * i] * y[2 * i]).sum();
+        assert!(close(sdot(N, &x, 3, &y, 2), expected));
+
+        let mut got = y.clone();
+        saxpy(N, 2.0, &x, 3, &mut got, 2);
+        for i in 0..2 * N {
+            let expected = if i % 2 == 0 {
+                y[i] + 2.0 * x[3 * i / 2]
+            } else {
+                y[i]
+            };
+            assert!(close(got[i], expected));
+        }
+
+        let mut got = x.clone();
+        sscal(N, 2.0, &mut got, 3);
+        for i in 0..3 * N {
+            let expected = if i % 3 == 0 { 2.0 * x[i] } else { x[i] };
+            assert_eq!(got[i], expected);
+        }
+
+        let expected: f32 = (0..N).map(|i| x[3 * i].abs()).sum();
+        assert!(close(sasum(N, &x, 3), expected));
+
+        let mut got = vec![0.0; N];
+        scopy(N, &x, 3, &mut got, 1);
+        assert_eq!(got, (0..N).map(|i| x[3 * i]).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn nrm2_extremes() {
+        assert_eq!(snrm2(2, &[3e30, 4e30], 1), 5e30);
+        assert!(close(snrm2(2, &[3e-30, 4e-30], 1) / 1e-30, 5.0));
+        assert_eq!(snrm2(0, &[], 1), 0.0);
+        assert!(snrm2(2, &[1.0, f32::NAN], 1).is_nan());
+        assert_eq!(snrm2(2, &[1.0, f32::INFINITY], 1), f32::INFINITY);
+    }
+
+    #[test]
+    fn amax() {
+        let mut x = data(N, 0);
+        x[40] = -10.0;
+        x[50] = 10.0;
+        assert_eq!(isamax(N, &x, 1), Some(40));
+        assert_eq!(isamax(N / 2, &x, 2), Some(20));
+        assert_eq!(isamax(0, &x, 1), None);
+
+        // The first NaN wins, whatever follows it.
+        x[60] = f32::NAN;
+        x[70] = f32::NAN;
+        assert_eq!(isamax(N, &x, 1), Some(60));
+        assert_eq!(isamax(3, &[1., f32::NAN, 0.5], 1), Some(1));
+        assert_eq!(isamax(3, &[f32::NAN, 2., 3.], 1), Some(0));
+    }
+
+    #[test]
+    fn scal_keeps_signed_zero() {
+        let mut x = vec![0.0; N];
+        sscal(N, -1.0, &mut x, 1);
+        assert!(x.iter().all(|v| *v == 0.0 && v.is_sign_negative()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        sdot(4, &[0.0; 6], 2, &[0.0; 4], 1);
+    }
+
+    #[cfg(feature = "f16")]
+    #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+    #[test]
+    fn half() {
+        use super::f16::*;
+        use half::f16;
+
+        let x = data(N, 0);
+        let y = data(N, 13);
+        let xh: Vec<f16> = x.iter().map(|v| f16::from_f32(*v)).collect();
+        let yh: Vec<f16> = y.iter().map(|v| f16::from_f32(*v)).collect();
+
+        // The data is exactly representable in f16.
+        assert!(close(hdot(N, &xh, 1, &yh, 1), sdot(N, &x, 1, &y, 1)));
+        assert!(close(
+            hdot(N / 2, &xh, 2, &yh, 2),
+            sdot(N / 2, &x, 2, &y, 2)
+        ));
+        assert!(close(hasum(N, &xh, 1), sasum(N, &x, 1)));
+        assert!(close(hnrm2(N, &xh, 1), snrm2(N, &x, 1)));
+        assert_eq!(ihamax(N, &xh, 1), isamax(N, &x, 1));
+
+        let mut got = yh.clone();
+        haxpy(N, f16::from_f32(0.5), &xh, 1, &mut got, 1);
+        let mut expected = y.clone();
+        saxpy(N, 0.5, &x, 1, &mut expected, 1);
+        for (g, e) in got.iter().zip(expected) {
+            assert_eq!(*g, f16::from_f32(e));
+        }
+
+        let mut got = xh.clone();
+        hscal(N, f16::from_f32(-2.0), &mut got, 1);
+        for (g, e) in got.iter().zip(&x) {
+            assert_eq!(g.to_f32(), -2.0 * e);
+        }
+
+        let mut got = vec![f16::ZERO; 2 * N];
+        hcopy(N, &xh, 1, &mut got, 2);
+        assert!((0..N).all(|i| got[2 * i] == xh[i]));
+    }
+}
@@ -0,0 +1,304 @@
+//! BLAS level-2 routines.
+//!
+//! Matrices are row major and contiguous, vectors are contiguous.
+//! Symmetric and triangular matrices are given as full `(n, n)` buffers of
+//! which only the triangle selected by [`Uplo`] is read.
+use crate::ggml::vec_dot_f32;
+use crate::raw::level2::{
+    ggml_compute_forward_ger, ggml_compute_forward_symv, ggml_compute_forward_trmv,
+};
+use crate::{get_pool, size};
+
+/// Which triangle of a symmetric or triangular matrix is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Uplo {
+    Upper,
+    Lower,
+}
+
+/// Whether the diagonal of a triangular matrix is read or assumed to be ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diag {
+    Unit,
+    NonUnit,
+}
+
+/// Computes the rank-1 update
+///
+/// ```latex
+/// A = A + alpha * x * y.T
+/// ```
+///
+/// with `A` of shape `(m, n)`.
+///
+/// ```
+/// use ggblas::sger;
+///
+/// let mut a = vec![0., 0., 0., 0., 0., 0.];
+/// sger(2., &[1., 2.], &[1., 2., 3.], &mut a, 2, 3);
+/// assert_eq!(a, &[2., 4., 6., 4., 8., 12.]);
+/// ```
+pub fn sger(alpha: f32, x: &[f32], y: &[f32], a: &mut [f32], m: usize, n: usize) {
+    assert_eq!(x.len(), m);
+    assert_eq!(y.len(), n);
+    assert_eq!(a.len(), size(&[m, n]));
+    unsafe {
+        ggml_compute_forward_ger(
+            alpha,
+            x,
+            y,
+            a,
+            m,
+            n,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Computes the symmetric matrix-vector product
+///
+/// ```latex
+/// y = alpha * A * x + beta * y
+/// ```
+///
+/// with `A` of shape `(n, n)` and only its `uplo` triangle being read.
+/// When `beta` is 0, `y` is overwritten without being read.
+///
+/// ```
+/// use ggblas::{ssymv, Uplo};
+///
+/// // The lower triangle is garbage and ignored.
+/// let a = vec![1., 2., f32::NAN, 3.];
+/// let mut y = vec![0., 0.];
+/// ssymv(Uplo::Upper, 1., &a, &[1., 1.], 0., &mut y, 2);
+/// assert_eq!(y, &[3., 5.]);
+/// ```
+pub fn ssymv(uplo: Uplo, alpha: f32, a: &[f32], x: &[f32], beta: f32, y: &mut [f32], n: usize) {
+    assert_eq!(a.len(), size(&[n, n]));
+    assert_eq!(x.len(), n);
+    assert_eq!(y.len(), n);
+    unsafe {
+        ggml_compute_forward_symv(
+            uplo,
+            alpha,
+            a,
+            x,
+            beta,
+            y,
+            n,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Computes the triangular matrix-vector product in place
+///
+/// ```latex
+/// x = A * x
+/// ```
+///
+/// with `A` of shape `(n, n)`, only its `uplo` triangle being read, and its
+/// diagonal replaced by ones for [`Diag::Unit`].
+///
+/// ```
+/// use ggblas::{strmv, Diag, Uplo};
+///
+/// let a = vec![2., 1., f32::NAN, 3.];
+/// let mut x = vec![1., 1.];
+/// strmv(Uplo::Upper, Diag::NonUnit, &a, &mut x, 2);
+/// assert_eq!(x, &[3., 3.]);
+/// ```
+pub fn strmv(uplo: Uplo, diag: Diag, a: &[f32], x: &mut [f32], n: usize) {
+    assert_eq!(a.len(), size(&[n, n]));
+    assert_eq!(x.len(), n);
+    unsafe {
+        ggml_compute_forward_trmv(
+            uplo,
+            diag,
+            a,
+            x,
+            n,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Solves the triangular system in place
+///
+/// ```latex
+/// A * x = b
+/// ```
+///
+/// with `x` holding `b` on entry, `A` of shape `(n, n)`, only its `uplo`
+/// triangle being read, and its diagonal replaced by ones for [`Diag::Unit`].
+///
+/// Every row depends on the previous ones so the substitution runs on the
+/// calling thread.
+///
+/// ```
+/// use ggblas::{strsv, Diag, Uplo};
+///
+/// let a = vec![2., 1., f32::NAN, 3.];
+/// let mut x = vec![3., 3.];
+/// strsv(Uplo::Upper, Diag::NonUnit, &a, &mut x, 2);
+/// assert_eq!(x, &[1., 1.]);
+/// ```
+pub fn strsv(uplo: Uplo, diag: Diag, a: &[f32], x: &mut [f32], n: usize) {
+    assert_eq!(a.len(), size(&[n, n]));
+    assert_eq!(x.len(), n);
+    let mut solve = |i: usize| {
+        let a_row = &a[i * n..(i + 1) * n];
+        let (start, len) = match uplo {
+            Uplo::Upper => (i + 1, n - i - 1),
+            Uplo::Lower => (0, i),
+        };
+        let mut dot = 0.0;
+        unsafe { vec_dot_f32(a_row[start..].as_ptr(), x[start..].as_ptr(), &mut dot, len) };
+        let v = x[i] - dot;
+        x[i] = match diag {
+            Diag::Unit => v,
+            Diag::NonUnit => v / a_row[i],
+        };
+    };
+    match uplo {
+        Uplo::Upper => (0..n).rev().for_each(&mut solve),
+        Uplo::Lower => (0..n).for_each(&mut solve),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(n: usize, seed: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| ((i * 7919 + seed) % 61) as f32 / 16.0 - 1.875)
+            .collect()
+    }
+
+    fn close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!(
+                (x - y).abs() <= 1e-4 * x.abs().max(y.abs()).max(1.0),
+                "{a:?} {b:?}"
+            );
+        }
+    }
+
+    /// Expands the `uplo` triangle into a dense matrix, the other triangle is
+    /// either mirrored (symmetric) or zeroed (triangular).
+    fn dense(a: &[f32], n: usize, uplo: Uplo, symmetric: bool, diag: Diag) -> Vec<f32> {
+        let mut d = vec![0.0; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let stored = match uplo {
+                    Uplo::Upper => j >= i,
+                    Uplo::Lower => j <= i,
+                };
+                d[i * n + j] = if stored {
+                    a[i * n + j]
+                } else if symmetric {
+                    a[j * n + i]
+                } else {
+                    0.0
+                };
+            }
+            if diag == Diag::Unit {
+                d[i * n + i] = 1.0;
+            }
+        }
+        d
+    }
+
+    fn gemv(a: &[f32], x: &[f32], m: usize, n: usize) -> Vec<f32> {
+        (0..m)
+            .map(|i| (0..n).map(|j| a[i * n + j] * x[j]).sum())
+            .collect()
+    }
+
+    /// Fills the unused triangle with NaN to make sure it is never read.
+    fn poisoned(n: usize, uplo: Uplo) -> Vec<f32> {
+        let mut a = data(n * n, 3);
+        for i in 0..n {
+            for j in 0..n {
+                let unused = match uplo {
+                    Uplo::Upper => j < i,
+                    Uplo::Lower => j > i,
+                };
+                if unused {
+                    a[i * n + j] = f32::NAN;
+                }
+            }
+        }
+        a
+    }
+
+    const N: usize = 37;
+
+    #[test]
+    fn ger() {
+        let (m, n) = (13, N);
+        let x = data(m, 1);
+        let y = data(n, 2);
+        let a0 = data(m * n, 3);
+        let mut a = a0.clone();
+        sger(0.5, &x, &y, &mut a, m, n);
+        let expected: Vec<f32> = (0..m * n)
+            .map(|idx| a0[idx] + 0.5 * x[idx / n] * y[idx % n])
+            .collect();
+        close(&a, &expected);
+    }
+
+    #[test]
+    fn symv() {
+        let x = data(N, 1);
+        let y0 = data(N, 2);
+        for uplo in [Uplo::Upper, Uplo::Lower] {
+            let a = poisoned(N, uplo);
+            let full = dense(&a, N, uplo, true, Diag::NonUnit);
+            let ax = gemv(&full, &x, N, N);
+
+            let mut y = y0.clone();
+            ssymv(uplo, 2.0, &a, &x, 0.5, &mut y, N);
+            let expected: Vec<f32> = (0..N).map(|i| 2.0 * ax[i] + 0.5 * y0[i]).collect();
+            close(&y, &expected);
+
+            let mut y = vec![f32::NAN; N];
+            ssymv(uplo, 1.0, &a, &x, 0.0, &mut y, N);
+            close(&y, &ax);
+        }
+    }
+
+    #[test]
+    fn trmv_trsv() {
+        let x0 = data(N, 1);
+        for uplo in [Uplo::Upper, Uplo::Lower] {
+            for diag in [Diag::Unit, Diag::NonUnit] {
+                let mut a = poisoned(N, uplo);
+                // Keep the system well conditioned.
+                (0..N).for_each(|i| a[i * N + i] = 4.0 + i as f32 / N as f32);
+                if diag == Diag::Unit {
+                    (0..N).for_each(|i| a[i * N + i] = f32::NAN);
+                }
+                let full = dense(&a, N, uplo, false, diag);
+
+                let mut x = x0.clone();
+                strmv(uplo, diag, &a, &mut x, N);
+                close(&x, &gemv(&full, &x0, N, N));
+
+                strsv(uplo, diag, &a, &mut x, N);
+                close(&x, &x0);
+            }
+        }
+    }
+}
@@ -0,0 +1,420 @@
+//! BLAS level-3 routines besides GEMM.
+//!
+//! As for [`batched_sgemm`](crate::batched_sgemm), the buffers are row major and
+//! the `batched_*` functions infer the batching from the size of the slices,
+//! every batch having its own `A`. Symmetric and triangular matrices are given
+//! as full square buffers of which only the triangle selected by [`Uplo`] is
+//! read.
+use crate::level2::{Diag, Uplo};
+use crate::raw::level3::{
+    ggml_compute_forward_syrk, ggml_compute_forward_trmm, ggml_compute_forward_trsm,
+};
+use crate::raw::{ggml_compute_forward_mul_mat, ggml_compute_forward_mul_mat_t};
+use crate::{batching, get_pool, size};
+
+/// Whether the symmetric or triangular `A` is applied on the left or on the
+/// right of `B`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Computes batched symmetric rank-k update
+///
+/// ```latex
+/// C = A * A.T
+/// ```
+///
+/// with `A` of shape `(n, k)` and `C` of shape `(n, n)`. Only the `uplo`
+/// triangle of `C` is computed and written, the other one is left untouched.
+///
+/// ```
+/// use ggblas::{batched_ssyrk, Uplo};
+///
+/// let a = vec![1., 2., 3., 4.];
+/// let mut c = vec![0., -1., 0., 0.];
+/// batched_ssyrk(Uplo::Lower, &a, &mut c, 2, 2);
+/// assert_eq!(c, &[5., -1., 11., 25.]);
+/// ```
+pub fn batched_ssyrk(uplo: Uplo, ap: &[f32], cp: &mut [f32], n: usize, k: usize) {
+    let a_skip = size(&[n, k]);
+    let c_skip = size(&[n, n]);
+    let batching = batching([("A", ap.len(), a_skip), ("C", cp.len(), c_skip)]);
+    unsafe {
+        ggml_compute_forward_syrk(
+            uplo,
+            ap,
+            a_skip,
+            cp,
+            c_skip,
+            n,
+            k,
+            batching,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Computes batched symmetric matrixmultiplication
+///
+/// ```latex
+/// C = A * B  (left)
+/// C = B * A  (right)
+/// ```
+///
+/// with `B` and `C` of shape `(m, n)`, and `A` symmetric of shape `(m, m)` on
+/// the left or `(n, n)` on the right.
+///
+/// `A` is expanded to a full matrix before going through the GEMM kernels.
+///
+/// ```
+/// use ggblas::{batched_ssymm, Side, Uplo};
+///
+/// let a = vec![1., 2., f32::NAN, 3.];
+/// let b = vec![1., 0., 0., 1.];
+/// let mut c = vec![0., 0., 0., 0.];
+/// batched_ssymm(Side::Left, Uplo::Upper, &a, &b, &mut c, 2, 2);
+/// assert_eq!(c, &[1., 2., 2., 3.]);
+/// ```
+pub fn batched_ssymm(
+    side: Side,
+    uplo: Uplo,
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+) {
+    let o = match side {
+        Side::Left => m,
+        Side::Right => n,
+    };
+    let a_skip = size(&[o, o]);
+    let b_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), b_skip),
+    ]);
+    // `A` is only empty along with `C`.
+    if a_skip == 0 {
+        return;
+    }
+
+    let mut full = ap.to_vec();
+    full.chunks_exact_mut(a_skip).for_each(|a| {
+        for i in 0..o {
+            for j in 0..i {
+                match uplo {
+                    Uplo::Upper => a[i * o + j] = a[j * o + i],
+                    Uplo::Lower => a[j * o + i] = a[i * o + j],
+                }
+            }
+        }
+    });
+
+    #[cfg(target_arch = "wasm32")]
+    let pool = &get_pool().unwrap();
+    #[cfg(not(target_arch = "wasm32"))]
+    let pool = unsafe { get_pool().unwrap() };
+    unsafe {
+        match side {
+            Side::Left => ggml_compute_forward_mul_mat(
+                &full, a_skip, false, bp, b_skip, cp, b_skip, m, n, m, batching, None, pool,
+            ),
+            // A being symmetric, B * A = B * A.T
+            Side::Right => ggml_compute_forward_mul_mat_t(
+                bp, b_skip, &full, a_skip, cp, b_skip, m, n, n, batching, None, pool,
+            ),
+        }
+    }
+}
+
+/// Computes batched triangular matrixmultiplication in place
+///
+/// ```latex
+/// B = A * B  (left)
+/// B = B * A  (right)
+/// ```
+///
+/// with `B` of shape `(m, n)`, and `A` triangular of shape `(m, m)` on the left
+/// or `(n, n)` on the right. The diagonal of `A` is replaced by ones for
+/// [`Diag::Unit`].
+///
+/// ```
+/// use ggblas::{batched_strmm, Diag, Side, Uplo};
+///
+/// let a = vec![2., 1., f32::NAN, 3.];
+/// let mut b = vec![1., 1., 1., 1.];
+/// batched_strmm(Side::Left, Uplo::Upper, Diag::NonUnit, &a, &mut b, 2, 2);
+/// assert_eq!(b, &[3., 3., 3., 3.]);
+/// ```
+pub fn batched_strmm(
+    side: Side,
+    uplo: Uplo,
+    diag: Diag,
+    ap: &[f32],
+    bp: &mut [f32],
+    m: usize,
+    n: usize,
+) {
+    let (a_skip, b_skip, batching) = triangular_batching(side, ap, bp, m, n);
+    unsafe {
+        ggml_compute_forward_trmm(
+            side,
+            uplo,
+            diag,
+            ap,
+            a_skip,
+            bp,
+            b_skip,
+            m,
+            n,
+            batching,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Solves batched triangular systems with multiple right-hand sides in place
+///
+/// ```latex
+/// A * X = B  (left)
+/// X * A = B  (right)
+/// ```
+///
+/// with `X` overwriting `B` of shape `(m, n)`, and `A` triangular of shape
+/// `(m, m)` on the left or `(n, n)` on the right. The diagonal of `A` is
+/// replaced by ones for [`Diag::Unit`].
+///
+/// ```
+/// use ggblas::{batched_strsm, Diag, Side, Uplo};
+///
+/// let a = vec![2., 1., f32::NAN, 3.];
+/// let mut b = vec![3., 3., 3., 3.];
+/// batched_strsm(Side::Left, Uplo::Upper, Diag::NonUnit, &a, &mut b, 2, 2);
+/// assert_eq!(b, &[1., 1., 1., 1.]);
+/// ```
+pub fn batched_strsm(
+    side: Side,
+    uplo: Uplo,
+    diag: Diag,
+    ap: &[f32],
+    bp: &mut [f32],
+    m: usize,
+    n: usize,
+) {
+    let (a_skip, b_skip, batching) = triangular_batching(side, ap, bp, m, n);
+    unsafe {
+        ggml_compute_forward_trsm(
+            side,
+            uplo,
+            diag,
+            ap,
+            a_skip,
+            bp,
+            b_skip,
+            m,
+            n,
+            batching,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+fn triangular_batching(
+    side: Side,
+    ap: &[f32],
+    bp: &[f32],
+    m: usize,
+    n: usize,
+) -> (usize, usize, usize) {
+    let o = match side {
+        Side::Left => m,
+        Side::Right => n,
+    };
+    let a_skip = size(&[o, o]);
+    let b_skip = size(&[m, n]);
+    let batching = batching([("A", ap.len(), a_skip), ("B", bp.len(), b_skip)]);
+    (a_skip, b_skip, batching)
+}
+
+/// Single matrix version of [`batched_ssyrk`].
+pub fn ssyrk(uplo: Uplo, ap: &[f32], cp: &mut [f32], n: usize, k: usize) {
+    assert_eq!(ap.len(), size(&[n, k]));
+    assert_eq!(cp.len(), size(&[n, n]));
+    batched_ssyrk(uplo, ap, cp, n, k)
+}
+
+/// Single matrix version of [`batched_ssymm`].
+pub fn ssymm(side: Side, uplo: Uplo, ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize) {
+    assert_eq!(bp.len(), size(&[m, n]));
+    batched_ssymm(side, uplo, ap, bp, cp, m, n)
+}
+
+/// Single matrix version of [`batched_strmm`].
+pub fn strmm(side: Side, uplo: Uplo, diag: Diag, ap: &[f32], bp: &mut [f32], m: usize, n: usize) {
+    assert_eq!(bp.len(), size(&[m, n]));
+    batched_strmm(side, uplo, diag, ap, bp, m, n)
+}
+
+/// Single matrix version of [`batched_strsm`].
+pub fn strsm(side: Side, uplo: Uplo, diag: Diag, ap: &[f32], bp: &mut [f32], m: usize, n: usize) {
+    assert_eq!(bp.len(), size(&[m, n]));
+    batched_strsm(side, uplo, diag, ap, bp, m, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BATCHING: usize = 3;
+
+    fn data(n: usize, seed: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| ((i * 7919 + seed) % 61) as f32 / 64.0 - 0.46875)
+            .collect()
+    }
+
+    fn close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!(
+                (x - y).abs() <= 1e-4 * x.abs().max(y.abs()).max(1.0),
+                "{x} {y}"
+            );
+        }
+    }
+
+    fn stored(uplo: Uplo, i: usize, j: usize) -> bool {
+        match uplo {
+            Uplo::Upper => j >= i,
+            Uplo::Lower => j <= i,
+        }
+    }
+
+    /// Random `(o, o)` matrices with the unused triangle set to NaN, and their
+    /// dense symmetric or triangular expansions.
+    fn matrices(o: usize, uplo: Uplo, symmetric: bool, diag: Diag) -> (Vec<f32>, Vec<f32>) {
+        let mut a = data(BATCHING * o * o, 5);
+        let mut full = vec![0.0; a.len()];
+        for (a, full) in a.chunks_exact_mut(o * o).zip(full.chunks_exact_mut(o * o)) {
+            for i in 0..o {
+                // Keep the systems well conditioned.
+                a[i * o + i] = 2.0 + i as f32 / o as f32;
+                for j in 0..o {
+                    full[i * o + j] = if stored(uplo, i, j) {
+                        a[i * o + j]
+                    } else if symmetric {
+                        a[j * o + i]
+                    } else {
+                        0.0
+                    };
+                }
+                if diag == Diag::Unit {
+                    full[i * o + i] = 1.0;
+                }
+            }
+            for i in 0..o {
+                for j in 0..o {
+                    if !stored(uplo, i, j) || (diag == Diag::Unit && i == j) {
+                        a[i * o + j] = f32::NAN;
+                    }
+                }
+            }
+        }
+        (a, full)
+    }
+
+    fn gemm(a: &[f32], b: &[f32], m: usize, n: usize, k: usize) -> Vec<f32> {
+        let mut c = vec![0.0; BATCHING * m * n];
+        for s in 0..BATCHING {
+            for i in 0..m {
+                for j in 0..n {
+                    c[s * m * n + i * n + j] = (0..k)
+                        .map(|l| a[s * m * k + i * k + l] * b[s * k * n + l * n + j])
+                        .sum();
+                }
+            }
+        }
+        c
+    }
+
+    const M: usize = 19;
+    const N: usize = 23;
+
+    #[test]
+    fn syrk() {
+        let k = 37;
+        let a = data(BATCHING * N * k, 1);
+        let mut at = vec![0.0; a.len()];
+        for s in 0..BATCHING {
+            for i in 0..N {
+                for l in 0..k {
+                    at[s * N * k + l * N + i] = a[s * N * k + i * k + l];
+                }
+            }
+        }
+        let expected = gemm(&a, &at, N, N, k);
+        for uplo in [Uplo::Upper, Uplo::Lower] {
+            let mut c = vec![f32::NAN; BATCHING * N * N];
+            batched_ssyrk(uplo, &a, &mut c, N, k);
+            for (idx, (c, e)) in c.iter().zip(&expected).enumerate() {
+                let (i, j) = ((idx / N) % N, idx % N);
+                if stored(uplo, i, j) {
+                    close(&[*c], &[*e]);
+                } else {
+                    assert!(c.is_nan());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symm() {
+        let b = data(BATCHING * M * N, 2);
+        for uplo in [Uplo::Upper, Uplo::Lower] {
+            let (a, full) = matrices(M, uplo, true, Diag::NonUnit);
+            let mut c = vec![f32::NAN; b.len()];
+            batched_ssymm(Side::Left, uplo, &a, &b, &mut c, M, N);
+            close(&c, &gemm(&full, &b, M, N, M));
+
+            let (a, full) = matrices(N, uplo, true, Diag::NonUnit);
+            let mut c = vec![f32::NAN; b.len()];
+            batched_ssymm(Side::Right, uplo, &a, &b, &mut c, M, N);
+            close(&c, &gemm(&b, &full, M, N, N));
+        }
+    }
+
+    #[test]
+    fn trmm_trsm() {
+        let b0 = data(BATCHING * M * N, 2);
+        for uplo in [Uplo::Upper, Uplo::Lower] {
+            for diag in [Diag::Unit, Diag::NonUnit] {
+                let (a, full) = matrices(M, uplo, false, diag);
+                let mut b = b0.clone();
+                batched_strmm(Side::Left, uplo, diag, &a, &mut b, M, N);
+                close(&b, &gemm(&full, &b0, M, N, M));
+                batched_strsm(Side::Left, uplo, diag, &a, &mut b, M, N);
+                close(&b, &b0);
+
+                let (a, full) = matrices(N, uplo, false, diag);
+                let mut b = b0.clone();
+                batched_strmm(Side::Right, uplo, diag, &a, &mut b, M, N);
+                close(&b, &gemm(&b0, &full, M, N, N));
+                batched_strsm(Side::Right, uplo, diag, &a, &mut b, M, N);
+                close(&b, &b0);
+            }
+        }
+    }
+}
@@ -30,10 +30,34 @@
 //! assert_eq!(c, &[1.0, 2.0, 2.0, 4.0, 9.0, 12.0, 12.0, 16.0]);
 //! ```
 //!
+//! # WebAssembly
+//!
+//! The kernels use `simd128` when it is enabled, with the fused multiply-adds
+//! of relaxed SIMD on top of it when `relaxed-simd` is, and plain scalar loops
+//! otherwise. wasm32 builds run on a single thread unless the `wasi-threads`
+//! feature is enabled on `wasm32-wasip1-threads`, where the products are
+//! spread over `init_thread_pool` threads. The feature is WASI only: browsers
+//! need Web Workers, which the crate does not spawn. Every configuration runs
+//! the tests under [wasmtime](https://wasmtime.dev/):
+//!
+//! ```bash
+//! rustup target add wasm32-wasip1 wasm32-wasip1-threads
+//! cargo test --target wasm32-wasip1
+//! RUSTFLAGS="-C target-feature=+simd128" cargo test --target wasm32-wasip1
+//! RUSTFLAGS="-C target-feature=+simd128,+relaxed-simd" cargo test --target wasm32-wasip1
+//! cargo test --target wasm32-wasip1-threads --features wasi-threads
+//! ```
+//!
 //! # Performance
 //!
 //! Current performance can be see [here](https://nodata.dev/ggblas/dev/bench/)
 //!
+//! To compare the backends on your own machine and shapes:
+//!
+//! ```bash
+//! cargo run --release --features matrixmultiply --bin ggblas-bench -- --m 1,64 --n 4096 --k 4096
+//! ```
+//!
 //! ## Intel
 //!
 //! i5-9300 (avx2)
@@ -65,21 +89,58 @@
 //!
 #![allow(clippy::reversed_empty_ranges)]
 #![allow(clippy::too_many_arguments)]
+#[cfg(all(feature = "capi", any(feature = "cblas", feature = "intel-mkl")))]
+compile_error!(
+    "`capi` exports the CBLAS symbols and can't be combined with `cblas` or `intel-mkl`"
+);
+
+pub mod accuracy;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod autotune;
+pub mod backend;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod epilogue;
+#[cfg(feature = "faer")]
+pub mod faer;
 pub mod ggml;
+pub mod level1;
+pub mod level2;
+pub mod level3;
+#[cfg(feature = "nalgebra")]
+pub mod nalgebra;
+#[cfg(feature = "ndarray")]
+pub mod ndarray;
 mod raw;
+pub mod reference;
+#[cfg(any(feature = "ndarray", feature = "nalgebra", feature = "faer"))]
+mod strided;
+pub use accuracy::{batched_sgemm_accuracy, batched_sgemm_t_accuracy, Accuracy};
+pub use backend::Backend;
+pub use epilogue::{Activation, Bias, Epilogue};
+pub use level1::{isamax, sasum, saxpy, scopy, sdot, snrm2, sscal};
+pub use level2::{sger, ssymv, strmv, strsv, Diag, Uplo};
+pub use level3::{
+    batched_ssymm, batched_ssyrk, batched_strmm, batched_strsm, ssymm, ssyrk, strmm, strsm, Side,
+};
 use raw::{ggml_compute_forward_mul_mat, ggml_compute_forward_mul_mat_t};
 
 #[cfg(target_arch = "wasm32")]
 mod wasm_pool;
-#[cfg(target_arch = "wasm32")]
-use wasm_pool::Once;
+#[cfg(all(target_arch = "wasm32", feature = "wasi-threads"))]
+pub use wasm_pool::init_thread_pool;
+#[cfg(all(
+    target_arch = "wasm32",
+    feature = "wasi-threads",
+    not(target_os = "wasi")
+))]
+compile_error!("the `wasi-threads` feature only runs on WASI targets");
 #[cfg(target_arch = "wasm32")]
 use wasm_pool::ThreadPool;
 
 #[cfg(target_arch = "wasm32")]
 fn get_pool() -> Option<ThreadPool> {
-    let pool = ThreadPool::new(1);
-    Some(pool)
+    Some(wasm_pool::global())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -105,7 +166,33 @@ unsafe fn get_pool() -> Option<&'static ThreadPool> {
 
         HANDLE = Some(pool);
     });
-    HANDLE.as_ref()
+    (*std::ptr::addr_of!(HANDLE)).as_ref()
+}
+
+/// Product of matrix dimensions, panicking instead of overflowing.
+pub(crate) fn size(dims: &[usize]) -> usize {
+    dims.iter()
+        .try_fold(1usize, |size, &dim| size.checked_mul(dim))
+        .expect("matrix size overflows usize")
+}
+
+/// Number of batches held by the operands, given as `(name, len, skip)`.
+///
+/// Panics unless every operand holds exactly that many matrices of `skip`
+/// elements, which is what keeps the raw kernels within the slices.
+pub(crate) fn batching<const N: usize>(operands: [(&str, usize, usize); N]) -> usize {
+    let batching = operands
+        .iter()
+        .find(|(_, _, skip)| *skip > 0)
+        .map(|(_, len, skip)| len / skip)
+        .unwrap_or(0);
+    for (name, len, skip) in operands {
+        assert!(
+            batching.checked_mul(skip) == Some(len),
+            "{name} holds {len} elements instead of {batching} matrices of {skip}"
+        );
+    }
+    batching
 }
 
 /// Computes batched matrixmultiplication
@@ -118,8 +205,13 @@ unsafe fn get_pool() -> Option<&'static ThreadPool> {
 /// The function will infer the batching based on `m`, `n` and `k`
 /// and the size of the slices.
 ///
-/// The sizes are **not** thoroughly checked, and the function will
-/// panic if sizes don't match.
+/// The function panics unless every slice holds the same number of
+/// whole matrices.
+///
+/// `C` is overwritten without being read, it may hold anything, NaN
+/// included. NaN and infinities in `A` and `B` propagate as in IEEE 754
+/// arithmetic (`0 * inf` is NaN), and an element whose products are all
+/// zeros is `+0.0`, even if they are `-0.0`.
 ///
 /// ```
 /// use ggblas::batched_sgemm_t;
@@ -133,12 +225,14 @@ unsafe fn get_pool() -> Option<&'static ThreadPool> {
 /// assert_eq!(c, &[5., 11., 11., 25.]);
 /// ```
 pub fn batched_sgemm_t(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
-    let a_skip = m * k;
-    let b_skip = k * n;
-    let c_skip = m * n;
-    let batching = ap.len() / a_skip;
-    assert_eq!(batching, bp.len() / b_skip);
-    assert_eq!(batching, cp.len() / c_skip);
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
     unsafe {
         ggml_compute_forward_mul_mat_t(
             ap,
@@ -151,6 +245,7 @@ pub fn batched_sgemm_t(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usiz
             n,
             k,
             batching,
+            None,
             #[cfg(target_arch = "wasm32")]
             &get_pool().unwrap(),
             #[cfg(not(target_arch = "wasm32"))]
@@ -169,8 +264,13 @@ pub fn batched_sgemm_t(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usiz
 /// The function will infer the batching based on `m`, `n` and `k`
 /// and the size of the slices.
 ///
-/// The sizes are **not** thoroughly checked, and the function will
-/// panic if sizes don't match.
+/// The function panics unless every slice holds the same number of
+/// whole matrices.
+///
+/// `C` is overwritten without being read, it may hold anything, NaN
+/// included. NaN and infinities in `A` and `B` propagate as in IEEE 754
+/// arithmetic (`0 * inf` is NaN), and an element whose products are all
+/// zeros is `+0.0`, even if they are `-0.0`.
 ///
 /// ```
 /// use ggblas::batched_sgemm;
@@ -184,16 +284,19 @@ pub fn batched_sgemm_t(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usiz
 /// assert_eq!(c, &[7., 10., 15., 22.]);
 /// ```
 pub fn batched_sgemm(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
-    let a_skip = m * k;
-    let b_skip = k * n;
-    let c_skip = m * n;
-    let batching = ap.len() / a_skip;
-    assert_eq!(batching, bp.len() / b_skip);
-    assert_eq!(batching, cp.len() / c_skip);
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
     unsafe {
         ggml_compute_forward_mul_mat(
             ap,
             a_skip,
+            false,
             bp,
             b_skip,
             cp,
@@ -202,6 +305,225 @@ pub fn batched_sgemm(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize,
             n,
             k,
             batching,
+            None,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Memory layout of the matrices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    #[default]
+    RowMajor,
+    ColMajor,
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// Works like [`batched_sgemm`] with the buffers stored in `layout`.
+/// A column major problem is the row major problem `C.T = B.T * A.T`,
+/// so it runs on the same kernels with the operands swapped and without
+/// copying anything.
+///
+/// ```
+/// use ggblas::{batched_sgemm_layout, Layout};
+///
+/// // [[1, 2], [3, 4]] in column major.
+/// let a = vec![1., 3., 2., 4.];
+/// let b = vec![1., 3., 2., 4.];
+/// let mut c = vec![0., 0., 0., 0.];
+///
+/// batched_sgemm_layout(Layout::ColMajor, &a, &b, &mut c, 2, 2, 2);
+/// assert_eq!(c, &[7., 15., 10., 22.]);
+/// ```
+pub fn batched_sgemm_layout(
+    layout: Layout,
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+) {
+    match layout {
+        Layout::RowMajor => batched_sgemm(ap, bp, cp, m, n, k),
+        Layout::ColMajor => batched_sgemm(bp, ap, cp, n, m, k),
+    }
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B.T
+/// ```
+///
+/// Works like [`batched_sgemm_t`] with the buffers stored in `layout`.
+/// A column major problem is the row major problem `C.T = B * A.T` where
+/// the buffer of `B` reads as `B.T`, the kernel then walks that operand
+/// transposed instead of copying it.
+///
+/// ```
+/// use ggblas::{batched_sgemm_t_layout, Layout};
+///
+/// // [[1, 2], [3, 4]] in column major.
+/// let a = vec![1., 3., 2., 4.];
+/// let b = vec![1., 3., 2., 4.];
+/// let mut c = vec![0., 0., 0., 0.];
+///
+/// batched_sgemm_t_layout(Layout::ColMajor, &a, &b, &mut c, 2, 2, 2);
+/// assert_eq!(c, &[5., 11., 11., 25.]);
+/// ```
+pub fn batched_sgemm_t_layout(
+    layout: Layout,
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+) {
+    if layout == Layout::RowMajor {
+        return batched_sgemm_t(ap, bp, cp, m, n, k);
+    }
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
+    unsafe {
+        ggml_compute_forward_mul_mat(
+            bp,
+            b_skip,
+            true,
+            ap,
+            a_skip,
+            cp,
+            c_skip,
+            n,
+            m,
+            k,
+            batching,
+            None,
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Computes batched matrixmultiplication with a fused [`Epilogue`]
+///
+/// ```latex
+/// C = activation(alpha * A * B.T + bias) + residual
+/// ```
+///
+/// Works like [`batched_sgemm_t`], `C` is overwritten.
+///
+/// ```
+/// use ggblas::{batched_sgemm_t_epilogue, Activation, Bias, Epilogue};
+///
+/// let a = vec![1., 2., 3., 4.];
+/// let b = vec![1., 2., 3., 4.];
+/// let mut c = vec![0., 0., 0., 0.];
+///
+/// let epilogue = Epilogue {
+///     bias: Some(Bias::N(&[-10., -30.])),
+///     activation: Activation::Relu,
+///     ..Default::default()
+/// };
+/// batched_sgemm_t_epilogue(&a, &b, &mut c, 2, 2, 2, &epilogue);
+/// assert_eq!(c, &[0., 0., 1., 0.]);
+/// ```
+pub fn batched_sgemm_t_epilogue(
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+    epilogue: &Epilogue,
+) {
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
+    epilogue.check(m, n, cp.len());
+    unsafe {
+        ggml_compute_forward_mul_mat_t(
+            ap,
+            a_skip,
+            bp,
+            b_skip,
+            cp,
+            c_skip,
+            m,
+            n,
+            k,
+            batching,
+            Some(epilogue),
+            #[cfg(target_arch = "wasm32")]
+            &get_pool().unwrap(),
+            #[cfg(not(target_arch = "wasm32"))]
+            get_pool().unwrap(),
+        );
+    }
+}
+
+/// Computes batched matrixmultiplication with a fused [`Epilogue`]
+///
+/// ```latex
+/// C = activation(alpha * A * B + bias) + residual
+/// ```
+///
+/// Works like [`batched_sgemm`].
+pub fn batched_sgemm_epilogue(
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+    epilogue: &Epilogue,
+) {
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
+    epilogue.check(m, n, cp.len());
+    unsafe {
+        ggml_compute_forward_mul_mat(
+            ap,
+            a_skip,
+            false,
+            bp,
+            b_skip,
+            cp,
+            c_skip,
+            m,
+            n,
+            k,
+            batching,
+            Some(epilogue),
             #[cfg(target_arch = "wasm32")]
             &get_pool().unwrap(),
             #[cfg(not(target_arch = "wasm32"))]
@@ -213,10 +535,10 @@ pub fn batched_sgemm(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize,
 #[cfg(feature = "f16")]
 #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
 pub mod f16 {
-    use super::get_pool;
     use super::raw::f16::{
         ggml_compute_forward_mul_mat_t_f16_mixed, ggml_compute_forward_mul_mat_t_f16_pure,
     };
+    use super::{batching, get_pool, size};
     use half::f16;
 
     pub fn batched_sgemm_t_f16_mixed(
@@ -227,12 +549,14 @@ pub mod f16 {
         n: usize,
         k: usize,
     ) {
-        let a_skip = m * k;
-        let b_skip = k * n;
-        let c_skip = m * n;
-        let batching = ap.len() / a_skip;
-        assert_eq!(batching, bp.len() / b_skip);
-        assert_eq!(batching, cp.len() / c_skip);
+        let a_skip = size(&[m, k]);
+        let b_skip = size(&[k, n]);
+        let c_skip = size(&[m, n]);
+        let batching = batching([
+            ("A", ap.len(), a_skip),
+            ("B", bp.len(), b_skip),
+            ("C", cp.len(), c_skip),
+        ]);
         unsafe {
             ggml_compute_forward_mul_mat_t_f16_mixed(
                 ap,
@@ -261,12 +585,14 @@ pub mod f16 {
         n: usize,
         k: usize,
     ) {
-        let a_skip = m * k;
-        let b_skip = k * n;
-        let c_skip = m * n;
-        let batching = ap.len() / a_skip;
-        assert_eq!(batching, bp.len() / b_skip);
-        assert_eq!(batching, cp.len() / c_skip);
+        let a_skip = size(&[m, k]);
+        let b_skip = size(&[k, n]);
+        let c_skip = size(&[m, n]);
+        let batching = batching([
+            ("A", ap.len(), a_skip),
+            ("B", bp.len(), b_skip),
+            ("C", cp.len(), c_skip),
+        ]);
         unsafe {
             ggml_compute_forward_mul_mat_t_f16_pure(
                 ap,
@@ -288,6 +614,535 @@ pub mod f16 {
     }
 }
 
+pub mod quantized {
+    use super::raw::quantized::ggml_compute_forward_mul_mat_t_quantized;
+    use super::{batching, get_pool, size};
+    pub use crate::ggml::k_quants::{BlockQ4K, BlockQ5K, BlockQ6K, BlockQ8K};
+    pub use crate::ggml::quants::{BlockQ4_0, BlockQ8_0, GgmlType};
+
+    /// Computes batched matrixmultiplication against quantized weights
+    ///
+    /// ```latex
+    /// C = A * B.T
+    /// ```
+    ///
+    /// `B` holds `n` rows of `k / T::BLCK_SIZE` blocks, exactly as they are laid out
+    /// in a ggml file. `A` is quantized on the fly to `T::VecDotType` before
+    /// computing the dot products, so `k` must be a multiple of `T::BLCK_SIZE`.
+    ///
+    /// ```
+    /// use ggblas::quantized::{batched_sgemm_t_quantized, BlockQ4_0, GgmlType};
+    ///
+    /// let (m, n, k) = (1, 2, 32);
+    /// let a = vec![1.0; m * k];
+    /// let b: Vec<f32> = (0..n * k).map(|i| (i / k) as f32 + 1.0).collect();
+    /// let mut b_q = vec![BlockQ4_0::zeros(); n * k / BlockQ4_0::BLCK_SIZE];
+    /// BlockQ4_0::from_float(&b, &mut b_q);
+    ///
+    /// let mut c = vec![0.0; m * n];
+    /// batched_sgemm_t_quantized(&a, &b_q, &mut c, m, n, k);
+    /// assert!((c[0] - 32.0).abs() < 0.1);
+    /// assert!((c[1] - 64.0).abs() < 0.1);
+    /// ```
+    pub fn batched_sgemm_t_quantized<T: GgmlType>(
+        ap: &[f32],
+        bp: &[T],
+        cp: &mut [f32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        assert_eq!(k % T::BLCK_SIZE, 0);
+        let a_skip = size(&[m, k]);
+        let b_skip = size(&[n, k / T::BLCK_SIZE]);
+        let c_skip = size(&[m, n]);
+        let batching = batching([
+            ("A", ap.len(), a_skip),
+            ("B", bp.len(), b_skip),
+            ("C", cp.len(), c_skip),
+        ]);
+        unsafe {
+            ggml_compute_forward_mul_mat_t_quantized(
+                ap,
+                a_skip,
+                bp,
+                b_skip,
+                cp,
+                c_skip,
+                m,
+                n,
+                k,
+                batching,
+                #[cfg(target_arch = "wasm32")]
+                &get_pool().unwrap(),
+                #[cfg(not(target_arch = "wasm32"))]
+                get_pool().unwrap(),
+            );
+        }
+    }
+}
+
+pub mod int8 {
+    //! Integer matrix multiplication for symmetric int8 quantization.
+    //!
+    //! The products are accumulated exactly in i32, and can optionally go
+    //! through a [`Requantize`] epilogue to produce `f32` or `i8` outputs.
+    use super::raw::int8::ggml_compute_forward_mul_mat_t_i8;
+    use super::{batching, get_pool, size};
+
+    /// Scales (and output zero points) applied to the i32 accumulators.
+    ///
+    /// The output is `acc * scale` for `f32`, and
+    /// `clamp(round(acc * scale) + zero_point, -128, 127)` for `i8`.
+    /// Per row values are indexed along `m` and per column values along `n`,
+    /// and are shared by every batch.
+    #[derive(Debug, Clone, Copy)]
+    pub enum Requantize<'a> {
+        PerTensor {
+            scale: f32,
+            zero_point: i32,
+        },
+        PerRow {
+            scales: &'a [f32],
+            zero_points: Option<&'a [i32]>,
+        },
+        PerColumn {
+            scales: &'a [f32],
+            zero_points: Option<&'a [i32]>,
+        },
+    }
+
+    impl Requantize<'_> {
+        #[inline]
+        fn params(&self, i: usize, j: usize) -> (f32, i32) {
+            match self {
+                Requantize::PerTensor { scale, zero_point } => (*scale, *zero_point),
+                Requantize::PerRow {
+                    scales,
+                    zero_points,
+                } => (scales[i], zero_points.map(|z| z[i]).unwrap_or(0)),
+                Requantize::PerColumn {
+                    scales,
+                    zero_points,
+                } => (scales[j], zero_points.map(|z| z[j]).unwrap_or(0)),
+            }
+        }
+
+        fn check(&self, m: usize, n: usize) {
+            match self {
+                Requantize::PerTensor { .. } => {}
+                Requantize::PerRow {
+                    scales,
+                    zero_points,
+                } => {
+                    assert_eq!(scales.len(), m);
+                    assert!(zero_points.map(|z| z.len() == m).unwrap_or(true));
+                }
+                Requantize::PerColumn {
+                    scales,
+                    zero_points,
+                } => {
+                    assert_eq!(scales.len(), n);
+                    assert!(zero_points.map(|z| z.len() == n).unwrap_or(true));
+                }
+            }
+        }
+    }
+
+    /// Output types the [`Requantize`] epilogue can write.
+    pub trait RequantizeOutput: Copy + Send + Sync {
+        fn requantize(acc: i32, scale: f32, zero_point: i32) -> Self;
+    }
+
+    impl RequantizeOutput for f32 {
+        #[inline]
+        fn requantize(acc: i32, scale: f32, _zero_point: i32) -> Self {
+            acc as f32 * scale
+        }
+    }
+
+    impl RequantizeOutput for i8 {
+        #[inline]
+        fn requantize(acc: i32, scale: f32, zero_point: i32) -> Self {
+            let v = (acc as f32 * scale).round() as i32 + zero_point;
+            v.clamp(i8::MIN as i32, i8::MAX as i32) as i8
+        }
+    }
+
+    /// Lays out each batch of `B` as `(n, k)` so that the `A * B` products can
+    /// reuse the dot product kernel.
+    fn transpose_b(bp: &[i8], n: usize, k: usize) -> Vec<i8> {
+        let mut bt = vec![0; bp.len()];
+        let b_skip = size(&[k, n]);
+        if b_skip == 0 {
+            return bt;
+        }
+        bp.chunks_exact(b_skip)
+            .zip(bt.chunks_exact_mut(b_skip))
+            .for_each(|(b, bt)| {
+                for kk in 0..k {
+                    for j in 0..n {
+                        bt[j * k + kk] = b[kk * n + j];
+                    }
+                }
+            });
+        bt
+    }
+
+    fn gemm_i8_impl<T, F>(
+        ap: &[i8],
+        bp: &[i8],
+        cp: &mut [T],
+        m: usize,
+        n: usize,
+        k: usize,
+        epilogue: F,
+    ) where
+        F: Fn(i32, usize, usize) -> T + Sync,
+    {
+        let a_skip = size(&[m, k]);
+        let b_skip = size(&[k, n]);
+        let c_skip = size(&[m, n]);
+        let batching = batching([
+            ("A", ap.len(), a_skip),
+            ("B", bp.len(), b_skip),
+            ("C", cp.len(), c_skip),
+        ]);
+        unsafe {
+            ggml_compute_forward_mul_mat_t_i8(
+                ap,
+                a_skip,
+                bp,
+                b_skip,
+                cp,
+                c_skip,
+                m,
+                n,
+                k,
+                batching,
+                &epilogue,
+                #[cfg(target_arch = "wasm32")]
+                &get_pool().unwrap(),
+                #[cfg(not(target_arch = "wasm32"))]
+                get_pool().unwrap(),
+            );
+        }
+    }
+
+    /// Computes batched integer matrixmultiplication
+    ///
+    /// ```latex
+    /// C = A * B.T
+    /// ```
+    ///
+    /// with exact i32 accumulation. Batching is inferred like [`crate::batched_sgemm_t`].
+    ///
+    /// ```
+    /// use ggblas::int8::gemm_i8_t;
+    ///
+    /// let a = vec![1, 2, 3, 4];
+    /// let b = vec![1, 2, -128, 4];
+    /// let mut c = vec![0; 4];
+    ///
+    /// gemm_i8_t(&a, &b, &mut c, 2, 2, 2);
+    /// assert_eq!(c, &[5, -120, 11, -368]);
+    /// ```
+    pub fn gemm_i8_t(ap: &[i8], bp: &[i8], cp: &mut [i32], m: usize, n: usize, k: usize) {
+        gemm_i8_impl(ap, bp, cp, m, n, k, |acc, _, _| acc)
+    }
+
+    /// Computes batched integer matrixmultiplication
+    ///
+    /// ```latex
+    /// C = A * B
+    /// ```
+    ///
+    /// with exact i32 accumulation. Batching is inferred like [`crate::batched_sgemm`].
+    ///
+    /// ```
+    /// use ggblas::int8::gemm_i8;
+    ///
+    /// let a = vec![1, 2, 3, 4];
+    /// let b = vec![1, 2, 3, 4];
+    /// let mut c = vec![0; 4];
+    ///
+    /// gemm_i8(&a, &b, &mut c, 2, 2, 2);
+    /// assert_eq!(c, &[7, 10, 15, 22]);
+    /// ```
+    pub fn gemm_i8(ap: &[i8], bp: &[i8], cp: &mut [i32], m: usize, n: usize, k: usize) {
+        let bt = transpose_b(bp, n, k);
+        gemm_i8_t(ap, &bt, cp, m, n, k)
+    }
+
+    /// Same as [`gemm_i8_t`], but the accumulators go through `requantize`
+    /// before being written to `C`.
+    ///
+    /// ```
+    /// use ggblas::int8::{gemm_i8_t_requantize, Requantize};
+    ///
+    /// let a = vec![1, 2, 3, 4];
+    /// let b = vec![1, 2, 3, 4];
+    /// let mut c = vec![0i8; 4];
+    /// let requantize = Requantize::PerColumn {
+    ///     scales: &[1.0, 0.1],
+    ///     zero_points: Some(&[0, -5]),
+    /// };
+    ///
+    /// gemm_i8_t_requantize(&a, &b, &mut c, 2, 2, 2, &requantize);
+    /// assert_eq!(c, &[5, -4, 11, -2]);
+    /// ```
+    pub fn gemm_i8_t_requantize<T: RequantizeOutput>(
+        ap: &[i8],
+        bp: &[i8],
+        cp: &mut [T],
+        m: usize,
+        n: usize,
+        k: usize,
+        requantize: &Requantize,
+    ) {
+        requantize.check(m, n);
+        gemm_i8_impl(ap, bp, cp, m, n, k, |acc, i, j| {
+            let (scale, zero_point) = requantize.params(i, j);
+            T::requantize(acc, scale, zero_point)
+        })
+    }
+
+    /// Same as [`gemm_i8`], but the accumulators go through `requantize`
+    /// before being written to `C`.
+    pub fn gemm_i8_requantize<T: RequantizeOutput>(
+        ap: &[i8],
+        bp: &[i8],
+        cp: &mut [T],
+        m: usize,
+        n: usize,
+        k: usize,
+        requantize: &Requantize,
+    ) {
+        let bt = transpose_b(bp, n, k);
+        gemm_i8_t_requantize(ap, &bt, cp, m, n, k, requantize)
+    }
+}
+
+pub mod complex {
+    //! Complex matrix multiplication on interleaved `(re, im)` storage.
+    //!
+    //! [`Complex32`] products go through the SIMD dot product kernel of the
+    //! `Cpu` trait, [`Complex64`] ones through a scalar loop.
+    use super::ggml::complex::{vec_dot_c32, vec_dot_c64};
+    use super::raw::complex::ggml_compute_forward_mul_mat_t_complex;
+    use super::{batching, get_pool, size};
+    pub use num_complex::{Complex32, Complex64};
+
+    /// Whether an operand is conjugated before the product.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Conj {
+        #[default]
+        No,
+        Yes,
+    }
+
+    impl Conj {
+        fn is_yes(self) -> bool {
+            self == Conj::Yes
+        }
+    }
+
+    /// Complex types with a dot product kernel.
+    pub trait ComplexDot: Copy + Send + Sync + 'static {
+        /// # Safety
+        /// `a` and `b` must be valid for `k` reads.
+        unsafe fn vec_dot(
+            a: *const Self,
+            b: *const Self,
+            k: usize,
+            conj_a: bool,
+            conj_b: bool,
+        ) -> Self;
+    }
+
+    impl ComplexDot for Complex32 {
+        unsafe fn vec_dot(
+            a: *const Self,
+            b: *const Self,
+            k: usize,
+            conj_a: bool,
+            conj_b: bool,
+        ) -> Self {
+            vec_dot_c32(a, b, k, conj_a, conj_b)
+        }
+    }
+
+    impl ComplexDot for Complex64 {
+        unsafe fn vec_dot(
+            a: *const Self,
+            b: *const Self,
+            k: usize,
+            conj_a: bool,
+            conj_b: bool,
+        ) -> Self {
+            vec_dot_c64(a, b, k, conj_a, conj_b)
+        }
+    }
+
+    /// Lays out each batch of `B` as `(n, k)` so that the `A * B` products can
+    /// reuse the dot product kernel.
+    fn transpose_b<T: Copy>(bp: &[T], n: usize, k: usize) -> Vec<T> {
+        let mut bt = bp.to_vec();
+        let b_skip = size(&[k, n]);
+        if b_skip == 0 {
+            return bt;
+        }
+        bp.chunks_exact(b_skip)
+            .zip(bt.chunks_exact_mut(b_skip))
+            .for_each(|(b, bt)| {
+                for kk in 0..k {
+                    for j in 0..n {
+                        bt[j * k + kk] = b[kk * n + j];
+                    }
+                }
+            });
+        bt
+    }
+
+    fn gemm_t<T: ComplexDot>(
+        conj_a: Conj,
+        conj_b: Conj,
+        ap: &[T],
+        bp: &[T],
+        cp: &mut [T],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        let a_skip = size(&[m, k]);
+        let b_skip = size(&[k, n]);
+        let c_skip = size(&[m, n]);
+        let batching = batching([
+            ("A", ap.len(), a_skip),
+            ("B", bp.len(), b_skip),
+            ("C", cp.len(), c_skip),
+        ]);
+        unsafe {
+            ggml_compute_forward_mul_mat_t_complex(
+                ap,
+                a_skip,
+                bp,
+                b_skip,
+                cp,
+                c_skip,
+                m,
+                n,
+                k,
+                batching,
+                conj_a.is_yes(),
+                conj_b.is_yes(),
+                #[cfg(target_arch = "wasm32")]
+                &get_pool().unwrap(),
+                #[cfg(not(target_arch = "wasm32"))]
+                get_pool().unwrap(),
+            );
+        }
+    }
+
+    /// Computes batched complex matrixmultiplication
+    ///
+    /// ```latex
+    /// C = op(A) * op(B).T
+    /// ```
+    ///
+    /// where `op` conjugates its operand for [`Conj::Yes`]. `op(B).T` is a plain
+    /// transpose, [`Conj::Yes`] on `B` gives the conjugate transpose `B.H`.
+    /// Batching is inferred like [`crate::batched_sgemm_t`].
+    ///
+    /// ```
+    /// use ggblas::complex::{batched_cgemm_t, Complex32, Conj};
+    ///
+    /// let a = vec![Complex32::new(1., 1.), Complex32::new(0., 2.)];
+    /// let mut c = vec![Complex32::new(0., 0.)];
+    ///
+    /// // a * a.H is the squared norm.
+    /// batched_cgemm_t(Conj::No, Conj::Yes, &a, &a, &mut c, 1, 1, 2);
+    /// assert_eq!(c, &[Complex32::new(6., 0.)]);
+    /// ```
+    pub fn batched_cgemm_t(
+        conj_a: Conj,
+        conj_b: Conj,
+        ap: &[Complex32],
+        bp: &[Complex32],
+        cp: &mut [Complex32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        gemm_t(conj_a, conj_b, ap, bp, cp, m, n, k)
+    }
+
+    /// Computes batched complex matrixmultiplication
+    ///
+    /// ```latex
+    /// C = op(A) * op(B)
+    /// ```
+    ///
+    /// where `op` conjugates its operand for [`Conj::Yes`].
+    /// Batching is inferred like [`crate::batched_sgemm`].
+    ///
+    /// ```
+    /// use ggblas::complex::{batched_cgemm, Complex32, Conj};
+    ///
+    /// let i = Complex32::new(0., 1.);
+    /// let a = vec![i, i];
+    /// let b = vec![i, i];
+    /// let mut c = vec![Complex32::new(0., 0.)];
+    ///
+    /// batched_cgemm(Conj::No, Conj::No, &a, &b, &mut c, 1, 1, 2);
+    /// assert_eq!(c, &[Complex32::new(-2., 0.)]);
+    /// batched_cgemm(Conj::Yes, Conj::No, &a, &b, &mut c, 1, 1, 2);
+    /// assert_eq!(c, &[Complex32::new(2., 0.)]);
+    /// ```
+    pub fn batched_cgemm(
+        conj_a: Conj,
+        conj_b: Conj,
+        ap: &[Complex32],
+        bp: &[Complex32],
+        cp: &mut [Complex32],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        let bt = transpose_b(bp, n, k);
+        gemm_t(conj_a, conj_b, ap, &bt, cp, m, n, k)
+    }
+
+    /// Same as [`batched_cgemm_t`] in double precision.
+    pub fn batched_zgemm_t(
+        conj_a: Conj,
+        conj_b: Conj,
+        ap: &[Complex64],
+        bp: &[Complex64],
+        cp: &mut [Complex64],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        gemm_t(conj_a, conj_b, ap, bp, cp, m, n, k)
+    }
+
+    /// Same as [`batched_cgemm`] in double precision.
+    pub fn batched_zgemm(
+        conj_a: Conj,
+        conj_b: Conj,
+        ap: &[Complex64],
+        bp: &[Complex64],
+        cp: &mut [Complex64],
+        m: usize,
+        n: usize,
+        k: usize,
+    ) {
+        let bt = transpose_b(bp, n, k);
+        gemm_t(conj_a, conj_b, ap, &bt, cp, m, n, k)
+    }
+}
+
 pub mod tests {
     #[cfg(test)]
     use super::*;
@@ -584,6 +1439,307 @@ pub mod tests {
         assert_eq!(c.data(), [50., 60., 114., 140., 178., 220.]);
     }
 
+    #[test]
+    fn ggml_simple_quantized() {
+        use crate::quantized::{batched_sgemm_t_quantized, BlockQ4_0, BlockQ8_0, GgmlType};
+        let m = 3;
+        let n = 2;
+        let k = 64;
+
+        let a = Tensor {
+            shape: vec![2, m, k],
+            data: (0..2 * m * k).map(|s| (s % 13) as f32 - 6.0).collect(),
+        };
+        let b = Tensor {
+            shape: vec![2, n, k],
+            data: (0..2 * n * k).map(|s| (s % 8) as f32 - 4.0).collect(),
+        };
+        let mut expected = Tensor {
+            shape: vec![2, m, n],
+            data: vec![0.0; 2 * m * n],
+        };
+        batched_sgemm_t(a.data(), b.data(), expected.data_mut(), m, n, k);
+
+        let mut b_q8 = vec![BlockQ8_0::zeros(); 2 * n * k / BlockQ8_0::BLCK_SIZE];
+        BlockQ8_0::from_float(b.data(), &mut b_q8);
+        let mut c = vec![0.0; 2 * m * n];
+        batched_sgemm_t_quantized(a.data(), &b_q8, &mut c, m, n, k);
+        expected
+            .data()
+            .iter()
+            .zip(c.iter())
+            .for_each(|(e, c)| assert!((e - c).abs() < 1.0, "{e} != {c}"));
+
+        // Every block contains -4 so the scale is 0.5 and all of B is exact in Q4_0,
+        // leaving only the error of the Q8_0 activations.
+        let mut b_q4 = vec![BlockQ4_0::zeros(); 2 * n * k / BlockQ4_0::BLCK_SIZE];
+        BlockQ4_0::from_float(b.data(), &mut b_q4);
+        let mut c = vec![0.0; 2 * m * n];
+        batched_sgemm_t_quantized(a.data(), &b_q4, &mut c, m, n, k);
+        expected
+            .data()
+            .iter()
+            .zip(c.iter())
+            .for_each(|(e, c)| assert!((e - c).abs() < 1.0, "{e} != {c}"));
+    }
+
+    #[test]
+    fn ggml_simple_i8() {
+        use crate::int8::{gemm_i8, gemm_i8_requantize, gemm_i8_t, Requantize};
+        let m = 3;
+        let n = 5;
+        let k = 67;
+
+        // Covers -128 * -128 and a k that isn't a multiple of the SIMD width.
+        let a: Vec<i8> = (0..2 * m * k).map(|s| (s * 37 % 256) as u8 as i8).collect();
+        let b: Vec<i8> = (0..2 * k * n).map(|s| (s * 91 % 256) as u8 as i8).collect();
+        let mut expected = vec![0i32; 2 * m * n];
+        for step in 0..2 {
+            for i in 0..m {
+                for j in 0..n {
+                    expected[step * m * n + i * n + j] = (0..k)
+                        .map(|kk| {
+                            a[step * m * k + i * k + kk] as i32
+                                * b[step * k * n + kk * n + j] as i32
+                        })
+                        .sum();
+                }
+            }
+        }
+        let mut c = vec![0i32; 2 * m * n];
+        gemm_i8(&a, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+
+        let mut bt = vec![0i8; b.len()];
+        for step in 0..2 {
+            for kk in 0..k {
+                for j in 0..n {
+                    bt[step * n * k + j * k + kk] = b[step * k * n + kk * n + j];
+                }
+            }
+        }
+        let mut c = vec![0i32; 2 * m * n];
+        gemm_i8_t(&a, &bt, &mut c, m, n, k);
+        assert_eq!(c, expected);
+
+        let scales = [0.5, 0.25, 1e-3];
+        let requantize = Requantize::PerRow {
+            scales: &scales,
+            zero_points: None,
+        };
+        let mut c = vec![0f32; 2 * m * n];
+        gemm_i8_requantize(&a, &b, &mut c, m, n, k, &requantize);
+        c.iter()
+            .zip(expected.iter())
+            .enumerate()
+            .for_each(|(idx, (&c, &e))| {
+                assert_eq!(c, e as f32 * scales[(idx / n) % m]);
+            });
+
+        let zero_points = [1, 2, 3];
+        let requantize = Requantize::PerRow {
+            scales: &scales,
+            zero_points: Some(&zero_points),
+        };
+        let mut c = vec![0i8; 2 * m * n];
+        gemm_i8_requantize(&a, &b, &mut c, m, n, k, &requantize);
+        c.iter()
+            .zip(expected.iter())
+            .enumerate()
+            .for_each(|(idx, (&c, &e))| {
+                let i = (idx / n) % m;
+                let v = (e as f32 * scales[i]).round() as i32 + zero_points[i];
+                assert_eq!(c as i32, v.clamp(-128, 127));
+            });
+    }
+
+    #[test]
+    fn ggml_simple_complex() {
+        use crate::complex::{
+            batched_cgemm, batched_cgemm_t, batched_zgemm, batched_zgemm_t, Complex32, Complex64,
+            Conj,
+        };
+        let m = 3;
+        let n = 5;
+        let k = 67;
+
+        // Small integers keep every product exact.
+        let a: Vec<Complex64> = (0..2 * m * k)
+            .map(|s| Complex64::new((s % 7) as f64 - 3.0, (s % 5) as f64 - 2.0))
+            .collect();
+        let b: Vec<Complex64> = (0..2 * k * n)
+            .map(|s| Complex64::new((s % 3) as f64 - 1.0, (s % 11) as f64 - 5.0))
+            .collect();
+        let mut bt = b.clone();
+        for step in 0..2 {
+            for kk in 0..k {
+                for j in 0..n {
+                    bt[step * n * k + j * k + kk] = b[step * k * n + kk * n + j];
+                }
+            }
+        }
+        let to_c32 = |v: &[Complex64]| -> Vec<Complex32> {
+            v.iter()
+                .map(|v| Complex32::new(v.re as f32, v.im as f32))
+                .collect()
+        };
+        let (a32, b32, bt32) = (to_c32(&a), to_c32(&b), to_c32(&bt));
+
+        for conj_a in [Conj::No, Conj::Yes] {
+            for conj_b in [Conj::No, Conj::Yes] {
+                let op = |v: Complex64, conj: Conj| if conj == Conj::Yes { v.conj() } else { v };
+                let mut expected = vec![Complex64::new(0.0, 0.0); 2 * m * n];
+                for step in 0..2 {
+                    for i in 0..m {
+                        for j in 0..n {
+                            expected[step * m * n + i * n + j] = (0..k)
+                                .map(|kk| {
+                                    op(a[step * m * k + i * k + kk], conj_a)
+                                        * op(b[step * k * n + kk * n + j], conj_b)
+                                })
+                                .sum();
+                        }
+                    }
+                }
+
+                let mut c = vec![Complex64::new(0.0, 0.0); 2 * m * n];
+                batched_zgemm(conj_a, conj_b, &a, &b, &mut c, m, n, k);
+                assert_eq!(c, expected);
+                batched_zgemm_t(conj_a, conj_b, &a, &bt, &mut c, m, n, k);
+                assert_eq!(c, expected);
+
+                let expected = to_c32(&expected);
+                let mut c = vec![Complex32::new(0.0, 0.0); 2 * m * n];
+                batched_cgemm(conj_a, conj_b, &a32, &b32, &mut c, m, n, k);
+                assert_eq!(c, expected);
+                batched_cgemm_t(conj_a, conj_b, &a32, &bt32, &mut c, m, n, k);
+                assert_eq!(c, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn ggml_simple_layout() {
+        let m = 3;
+        let n = 5;
+        let k = 67;
+        let batching = 2;
+
+        // Transposes each batch of `(rows, cols)` row major matrices, which
+        // gives their column major buffer.
+        let transpose = |x: &[f32], rows: usize, cols: usize| {
+            let mut t = vec![0.0; x.len()];
+            for (x, t) in x
+                .chunks_exact(rows * cols)
+                .zip(t.chunks_exact_mut(rows * cols))
+            {
+                for r in 0..rows {
+                    for c in 0..cols {
+                        t[c * rows + r] = x[r * cols + c];
+                    }
+                }
+            }
+            t
+        };
+
+        let a: Vec<f32> = (0..batching * m * k)
+            .map(|s| (s % 7) as f32 - 3.0)
+            .collect();
+        let b: Vec<f32> = (0..batching * k * n)
+            .map(|s| (s % 5) as f32 - 2.0)
+            .collect();
+        let mut expected = vec![0.0; batching * m * n];
+        batched_sgemm(&a, &b, &mut expected, m, n, k);
+        let expected = transpose(&expected, m, n);
+
+        let a_col = transpose(&a, m, k);
+        let b_col = transpose(&b, k, n);
+        let mut c = vec![0.0; batching * m * n];
+        batched_sgemm_layout(Layout::ColMajor, &a_col, &b_col, &mut c, m, n, k);
+        assert_eq!(c, expected);
+
+        // The T variant takes B.T, of shape `(n, k)`, whose column major buffer
+        // is the row major buffer of B.
+        let mut c = vec![f32::NAN; batching * m * n];
+        batched_sgemm_t_layout(Layout::ColMajor, &a_col, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+
+        let mut c = vec![0.0; batching * m * n];
+        batched_sgemm_layout(Layout::RowMajor, &a, &b, &mut c, m, n, k);
+        assert_eq!(transpose(&c, m, n), expected);
+    }
+
+    #[test]
+    fn ggml_simple_epilogue() {
+        let m = 3;
+        let n = 37;
+        let k = 41;
+        let batching = 2;
+
+        let a: Vec<f32> = (0..batching * m * k)
+            .map(|s| ((s % 11) as f32 - 5.0) / 7.0)
+            .collect();
+        let b: Vec<f32> = (0..batching * n * k)
+            .map(|s| ((s % 13) as f32 - 6.0) / 9.0)
+            .collect();
+        let bias_n: Vec<f32> = (0..n).map(|j| j as f32 / 10.0 - 1.0).collect();
+        let bias_m: Vec<f32> = (0..m).map(|i| i as f32 - 1.0).collect();
+        let residual: Vec<f32> = (0..batching * m * n).map(|s| (s % 5) as f32).collect();
+
+        for activation in [
+            Activation::Identity,
+            Activation::Relu,
+            Activation::Gelu,
+            Activation::Silu,
+        ] {
+            for bias in [None, Some(Bias::N(&bias_n)), Some(Bias::M(&bias_m))] {
+                for residual in [None, Some(&residual[..])] {
+                    let epilogue = Epilogue {
+                        alpha: 0.5,
+                        bias,
+                        activation,
+                        residual,
+                    };
+                    for transpose in [true, false] {
+                        // Unfused reference: plain matmul then a separate pass over C.
+                        let mut expected = vec![0.0; batching * m * n];
+                        if transpose {
+                            batched_sgemm_t(&a, &b, &mut expected, m, n, k);
+                        } else {
+                            batched_sgemm(&a, &b, &mut expected, m, n, k);
+                        }
+                        expected.iter_mut().enumerate().for_each(|(idx, c)| {
+                            let i = (idx / n) % m;
+                            let j = idx % n;
+                            let mut v = 0.5 * *c;
+                            match bias {
+                                Some(Bias::N(bias)) => v += bias[j],
+                                Some(Bias::M(bias)) => v += bias[i],
+                                None => {}
+                            }
+                            v = activation.apply(v);
+                            if let Some(residual) = residual {
+                                v += residual[idx];
+                            }
+                            *c = v;
+                        });
+
+                        // Garbage in C must not leak into the fused result.
+                        let mut c = vec![f32::NAN; batching * m * n];
+                        if transpose {
+                            batched_sgemm_t_epilogue(&a, &b, &mut c, m, n, k, &epilogue);
+                        } else {
+                            batched_sgemm_epilogue(&a, &b, &mut c, m, n, k, &epilogue);
+                        }
+                        expected.iter().zip(c.iter()).for_each(|(e, c)| {
+                            assert!((e - c).abs() < 1e-5, "{activation:?} {e} != {c}")
+                        });
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     #[cfg(any(feature = "cblas", feature = "intel-mkl"))]
     fn mkl_simple() {
@@ -664,6 +1820,114 @@ pub mod tests {
         assert_close(&c.data(), &c2.data());
     }
 
+    #[test]
+    fn special_values() {
+        type Gemm = fn(&[f32], &[f32], &[f32], &mut [f32], usize, usize, usize);
+        let gemms: Vec<(&str, Gemm)> = vec![
+            ("n", |a, b, _, c, m, n, k| batched_sgemm(a, b, c, m, n, k)),
+            ("t", |a, _, bt, c, m, n, k| {
+                batched_sgemm_t(a, bt, c, m, n, k)
+            }),
+            ("epilogue_n", |a, b, _, c, m, n, k| {
+                let epilogue = Epilogue::default();
+                batched_sgemm_epilogue(a, b, c, m, n, k, &epilogue)
+            }),
+            ("epilogue_t", |a, _, bt, c, m, n, k| {
+                let epilogue = Epilogue::default();
+                batched_sgemm_t_epilogue(a, bt, c, m, n, k, &epilogue)
+            }),
+            ("accuracy_n", |a, b, _, c, m, n, k| {
+                batched_sgemm_accuracy(Accuracy::Fast, a, b, c, m, n, k)
+            }),
+            ("pairwise", |a, _, bt, c, m, n, k| {
+                batched_sgemm_t_accuracy(Accuracy::Pairwise, a, bt, c, m, n, k)
+            }),
+            ("compensated", |a, _, bt, c, m, n, k| {
+                batched_sgemm_t_accuracy(Accuracy::Compensated, a, bt, c, m, n, k)
+            }),
+            ("f64", |a, _, bt, c, m, n, k| {
+                batched_sgemm_t_accuracy(Accuracy::F64, a, bt, c, m, n, k)
+            }),
+            ("deterministic", |a, _, bt, c, m, n, k| {
+                batched_sgemm_t_accuracy(Accuracy::Deterministic, a, bt, c, m, n, k)
+            }),
+        ];
+        let (m, n) = (3, 5);
+        // Past the SIMD steps, and long enough for split-K.
+        for k in [37, raw::split_k::MIN_K + 3] {
+            let transpose = |b: &[f32]| -> Vec<f32> {
+                let mut bt = vec![0.0; k * n];
+                for kk in 0..k {
+                    for j in 0..n {
+                        bt[j * k + kk] = b[kk * n + j];
+                    }
+                }
+                bt
+            };
+            let a: Vec<f32> = (0..m * k).map(|s| (s % 7) as f32 - 3.0).collect();
+            let b: Vec<f32> = (0..k * n).map(|s| (s % 5) as f32 - 2.0).collect();
+            let mut expected = vec![0.0; m * n];
+            reference::batched_sgemm(&a, &b, &mut expected, m, n, k);
+            let run = |gemm: Gemm, a: &[f32], b: &[f32]| {
+                // Garbage in C must not leak into the results.
+                let mut c = vec![f32::NAN; m * n];
+                gemm(a, b, &transpose(b), &mut c, m, n, k);
+                c
+            };
+
+            for (name, gemm) in &gemms {
+                // The products are small integers, all exact.
+                assert_eq!(run(*gemm, &a, &b), expected, "{name} {k}");
+
+                let mut a_nan = a.clone();
+                a_nan[k + 3] = f32::NAN;
+                let c = run(*gemm, &a_nan, &b);
+                for (idx, (&c, &e)) in c.iter().zip(&expected).enumerate() {
+                    if idx / n == 1 {
+                        assert!(c.is_nan(), "{name} {k}");
+                    } else {
+                        assert_eq!(c, e, "{name} {k}");
+                    }
+                }
+
+                // Column 2 gets `0 * inf`, `1 * inf` and `-1 * inf`.
+                let (mut a_inf, mut b_inf) = (a.clone(), b.clone());
+                a_inf[0] = 0.0;
+                a_inf[k] = 1.0;
+                a_inf[2 * k] = -1.0;
+                b_inf[2] = f32::INFINITY;
+                let c = run(*gemm, &a_inf, &b_inf);
+                assert!(c[2].is_nan(), "{name} {k}");
+                assert_eq!(c[n + 2], f32::INFINITY, "{name} {k}");
+                assert_eq!(c[2 * n + 2], f32::NEG_INFINITY, "{name} {k}");
+
+                let a_zero = vec![-0.0; m * k];
+                let c = run(*gemm, &a_zero, &b);
+                assert!(c.iter().all(|c| c.to_bits() == 0), "{name} {k}: {c:?}");
+            }
+        }
+
+        let a = [f32::NAN, 1.0, -1.0, -0.0, f32::NEG_INFINITY, f32::INFINITY];
+        let relu = Epilogue {
+            activation: Activation::Relu,
+            ..Default::default()
+        };
+        let mut c = vec![0.0; 6];
+        batched_sgemm_t_epilogue(&a, &[1.0], &mut c, 6, 1, 1, &relu);
+        assert!(c[0].is_nan());
+        assert_eq!(c[1..], [1.0, 0.0, 0.0, 0.0, f32::INFINITY]);
+        assert_eq!(c[3].to_bits(), 0);
+        for activation in [Activation::Gelu, Activation::Silu] {
+            let epilogue = Epilogue {
+                activation,
+                ..Default::default()
+            };
+            batched_sgemm_t_epilogue(&a, &[1.0], &mut c, 6, 1, 1, &epilogue);
+            assert!(c[0].is_nan());
+            assert_eq!(c[4..], [0.0, f32::INFINITY], "{activation:?}");
+        }
+    }
+
     #[cfg(any(feature = "cblas", feature = "intel-mkl", feature = "faer-rs"))]
     pub fn assert_close(a: &[f32], b: &[f32]) {
         a.iter().zip(b.iter()).for_each(|(&a, &b)| {
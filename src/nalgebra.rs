@@ -0,0 +1,141 @@
+//! [nalgebra](https://docs.rs/nalgebra/) integration.
+//!
+//! Any matrix or view can be multiplied, whatever its strides. nalgebra
+//! stores matrices in column major order which the kernels read in place,
+//! views with other strides are copied first.
+//!
+//! ```
+//! use nalgebra::DMatrix;
+//!
+//! let a = DMatrix::from_row_slice(2, 2, &[1., 2., 3., 4.]);
+//! let b = DMatrix::from_row_slice(2, 2, &[1., 2., 3., 4.]);
+//! assert_eq!(ggblas::nalgebra::matmul(&a, &b), &a * &b);
+//! ```
+use crate::strided::{self, Strided};
+use ::nalgebra::{DMatrix, Dim, Matrix, RawStorage, RawStorageMut};
+
+fn strided<T, P, R: Dim, C: Dim, S: RawStorage<T, R, C>>(
+    m: &Matrix<T, R, C, S>,
+    ptr: P,
+) -> Strided<P> {
+    let (rows, cols) = m.shape();
+    let (row_stride, col_stride) = m.strides();
+    Strided {
+        ptr,
+        dim: (1, rows, cols),
+        strides: (0, row_stride as isize, col_stride as isize),
+    }
+}
+
+/// Computes matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// with `A` of shape `(m, k)`, `B` of shape `(k, n)` and `C` of shape
+/// `(m, n)`. `C` is overwritten.
+///
+/// ```
+/// use nalgebra::DMatrix;
+///
+/// let a = DMatrix::from_row_slice(2, 3, &[1., 2., 0., 3., 4., 0.]);
+/// let b = DMatrix::from_row_slice(2, 2, &[1., 2., 3., 4.]);
+/// let mut c = DMatrix::zeros(2, 2);
+///
+/// ggblas::nalgebra::sgemm(&a.columns(0, 2), &b.transpose(), &mut c);
+/// assert_eq!(c, DMatrix::from_row_slice(2, 2, &[5., 11., 11., 25.]));
+/// ```
+pub fn sgemm<R1, C1, S1, R2, C2, S2, R3, C3, S3>(
+    a: &Matrix<f32, R1, C1, S1>,
+    b: &Matrix<f32, R2, C2, S2>,
+    c: &mut Matrix<f32, R3, C3, S3>,
+) where
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<f32, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<f32, R2, C2>,
+    R3: Dim,
+    C3: Dim,
+    S3: RawStorageMut<f32, R3, C3>,
+{
+    let c_ptr = c.as_mut_ptr();
+    // The borrows guarantee `c` does not alias the inputs.
+    unsafe {
+        strided::sgemm(
+            strided(a, a.as_ptr()),
+            strided(b, b.as_ptr()),
+            strided(c, c_ptr),
+        )
+    }
+}
+
+/// Computes `A * B` into a new matrix, see [`sgemm`].
+pub fn matmul<R1, C1, S1, R2, C2, S2>(
+    a: &Matrix<f32, R1, C1, S1>,
+    b: &Matrix<f32, R2, C2, S2>,
+) -> DMatrix<f32>
+where
+    R1: Dim,
+    C1: Dim,
+    S1: RawStorage<f32, R1, C1>,
+    R2: Dim,
+    C2: Dim,
+    S2: RawStorage<f32, R2, C2>,
+{
+    let mut c = DMatrix::zeros(a.nrows(), b.ncols());
+    sgemm(a, b, &mut c);
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(rows: usize, cols: usize, seed: usize) -> DMatrix<f32> {
+        DMatrix::from_fn(rows, cols, |i, j| {
+            ((i * 7919 + j * 31 + seed) % 61) as f32 / 16.0 - 1.875
+        })
+    }
+
+    fn close(a: &DMatrix<f32>, b: &DMatrix<f32>) {
+        assert_eq!(a.shape(), b.shape());
+        for (x, y) in a.iter().zip(b) {
+            assert!(
+                (x - y).abs() <= 1e-4 * x.abs().max(y.abs()).max(1.0),
+                "{a} {b}"
+            );
+        }
+    }
+
+    #[test]
+    fn views() {
+        let (m, n, k) = (13, 17, 37);
+        let a = data(m, k, 1);
+        let b = data(k, n, 2);
+        let expected = &a * &b;
+        let a_t = a.transpose();
+        let b_t = b.transpose();
+        // Row major storage through the transposes of owned matrices.
+        let a_row = a_t.transpose();
+        let b_row = b_t.transpose();
+        close(&matmul(&a, &b), &expected);
+        close(&matmul(&a_row, &b), &expected);
+        close(&matmul(&a, &b_row), &expected);
+
+        // Views with strides other than the natural ones.
+        let big = data(2 * m, 2 * k, 3);
+        let a = big.view_with_steps((0, 0), (m, k), (1, 1));
+        let b = data(k + 5, n, 4);
+        let b = b.rows(5, k);
+        close(&matmul(&a, &b), &(a * b));
+
+        let mut big_c = DMatrix::from_element(2 * m, 3 * n, f32::NAN);
+        let mut c = big_c.view_with_steps_mut((1, 0), (m, n), (1, 2));
+        sgemm(&a, &b, &mut c);
+        close(&c.clone_owned(), &(a * b));
+        assert!(big_c.row(0).iter().all(|v| v.is_nan()));
+    }
+}
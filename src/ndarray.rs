@@ -0,0 +1,319 @@
+//! [ndarray](https://docs.rs/ndarray/) integration.
+//!
+//! The functions here take views of any memory layout. Operands that are
+//! contiguous in row major or column major order are read in place, other
+//! views (sliced, stepped or broadcast) are copied first.
+//!
+//! ```
+//! use ggblas::ndarray::GgDot;
+//! use ndarray::array;
+//!
+//! let a = array![[1., 2.], [3., 4.]];
+//! let b = array![[1., 2.], [3., 4.]];
+//! assert_eq!(a.gg_dot(&b), array![[7., 10.], [15., 22.]]);
+//! // Transposed views are read in place.
+//! assert_eq!(a.t().gg_dot(&b), array![[10., 14.], [14., 20.]]);
+//! ```
+use crate::strided::{self, Strided};
+use ::ndarray::{
+    Array2, Array3, ArrayBase, ArrayView2, ArrayView3, ArrayViewMut2, ArrayViewMut3, Axis, Data,
+    Ix2, Ix3,
+};
+
+fn strided<P>(ptr: P, dim: (usize, usize, usize), strides: &[isize]) -> Strided<P> {
+    Strided {
+        ptr,
+        dim,
+        strides: (strides[0], strides[1], strides[2]),
+    }
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// with `A` of shape `(batch, m, k)`, `B` of shape `(batch, k, n)` and `C`
+/// of shape `(batch, m, n)`. `C` is overwritten.
+///
+/// ```
+/// use ggblas::ndarray::batched_sgemm;
+/// use ndarray::{Array3, Axis};
+///
+/// let a = Array3::from_shape_vec((2, 2, 1), vec![1., 2., 3., 4.]).unwrap();
+/// let b = Array3::from_shape_vec((2, 1, 2), vec![1., 2., 3., 4.]).unwrap();
+/// let mut c = Array3::zeros((2, 2, 2));
+///
+/// batched_sgemm(a.view(), b.view(), c.view_mut());
+/// assert_eq!(c.as_slice().unwrap(), &[1., 2., 2., 4., 9., 12., 12., 16.]);
+/// ```
+pub fn batched_sgemm(
+    a: ArrayView3<'_, f32>,
+    b: ArrayView3<'_, f32>,
+    mut c: ArrayViewMut3<'_, f32>,
+) {
+    // The borrows guarantee `c` does not alias the inputs.
+    unsafe {
+        strided::sgemm(
+            strided(a.as_ptr(), a.dim(), a.strides()),
+            strided(b.as_ptr(), b.dim(), b.strides()),
+            strided(c.as_mut_ptr(), c.dim(), c.strides()),
+        )
+    }
+}
+
+/// Computes matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// with `A` of shape `(m, k)`, `B` of shape `(k, n)` and `C` of shape
+/// `(m, n)`. `C` is overwritten.
+///
+/// ```
+/// use ggblas::ndarray::sgemm;
+/// use ndarray::{array, Array2};
+///
+/// let a = array![[1., 2.], [3., 4.]];
+/// let b = array![[1., 2.], [3., 4.]];
+/// let mut c = Array2::zeros((2, 2));
+///
+/// // Output views do not need to be contiguous.
+/// sgemm(a.view(), b.view(), c.view_mut().reversed_axes());
+/// assert_eq!(c, array![[7., 15.], [10., 22.]]);
+/// ```
+pub fn sgemm(a: ArrayView2<'_, f32>, b: ArrayView2<'_, f32>, c: ArrayViewMut2<'_, f32>) {
+    batched_sgemm(
+        a.insert_axis(Axis(0)),
+        b.insert_axis(Axis(0)),
+        c.insert_axis(Axis(0)),
+    )
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Element types [`GgDot`] is implemented for, `f32` and, with the `f16`
+/// feature, `half::f16`.
+pub trait Element: Copy + private::Sealed {
+    #[doc(hidden)]
+    fn batched_dot(a: ArrayView3<'_, Self>, b: ArrayView3<'_, Self>) -> Array3<Self>;
+}
+
+impl private::Sealed for f32 {}
+
+impl Element for f32 {
+    fn batched_dot(a: ArrayView3<'_, f32>, b: ArrayView3<'_, f32>) -> Array3<f32> {
+        let (batch, m, _) = a.dim();
+        let mut c = Array3::zeros((batch, m, b.len_of(Axis(2))));
+        batched_sgemm(a, b, c.view_mut());
+        c
+    }
+}
+
+#[cfg(feature = "f16")]
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+mod f16 {
+    use super::{private, Element};
+    use crate::f16::batched_sgemm_t_f16_pure;
+    use ::ndarray::{Array3, ArrayView3};
+    use half::f16;
+
+    impl private::Sealed for f16 {}
+
+    /// The f16 kernels only exist for `A * B.T`, so `B` is always packed
+    /// transposed and `A` is packed unless it is already row major.
+    impl Element for f16 {
+        fn batched_dot(a: ArrayView3<'_, f16>, b: ArrayView3<'_, f16>) -> Array3<f16> {
+            let (batch, m, k) = a.dim();
+            let (b_batch, b_k, n) = b.dim();
+            assert_eq!(
+                (batch, k),
+                (b_batch, b_k),
+                "inputs have incompatible shapes"
+            );
+            let mut c = Array3::from_elem((batch, m, n), f16::ZERO);
+            if batch * m * n * k == 0 {
+                return c;
+            }
+            let a = a.as_standard_layout();
+            let bt = b.permuted_axes([0, 2, 1]);
+            let bt = bt.as_standard_layout();
+            batched_sgemm_t_f16_pure(
+                a.as_slice().unwrap(),
+                bt.as_slice().unwrap(),
+                c.as_slice_mut().unwrap(),
+                m,
+                n,
+                k,
+            );
+            c
+        }
+    }
+}
+
+/// Matrix products on ndarray arrays, running on ggblas.
+///
+/// Two dimensional arrays are multiplied as matrices, three dimensional ones
+/// as batches of matrices. Panics when the shapes do not line up.
+pub trait GgDot<Rhs> {
+    type Output;
+
+    fn gg_dot(&self, rhs: &Rhs) -> Self::Output;
+}
+
+impl<A, S, S2> GgDot<ArrayBase<S2, Ix2>> for ArrayBase<S, Ix2>
+where
+    A: Element,
+    S: Data<Elem = A>,
+    S2: Data<Elem = A>,
+{
+    type Output = Array2<A>;
+
+    fn gg_dot(&self, rhs: &ArrayBase<S2, Ix2>) -> Array2<A> {
+        A::batched_dot(
+            self.view().insert_axis(Axis(0)),
+            rhs.view().insert_axis(Axis(0)),
+        )
+        .index_axis_move(Axis(0), 0)
+    }
+}
+
+impl<A, S, S2> GgDot<ArrayBase<S2, Ix3>> for ArrayBase<S, Ix3>
+where
+    A: Element,
+    S: Data<Elem = A>,
+    S2: Data<Elem = A>,
+{
+    type Output = Array3<A>;
+
+    fn gg_dot(&self, rhs: &ArrayBase<S2, Ix3>) -> Array3<A> {
+        A::batched_dot(self.view(), rhs.view())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::ndarray::{s, Array, ShapeBuilder};
+
+    fn data(shape: (usize, usize, usize), seed: usize) -> Array3<f32> {
+        let len = shape.0 * shape.1 * shape.2;
+        let v = (0..len)
+            .map(|i| ((i * 7919 + seed) % 61) as f32 / 16.0 - 1.875)
+            .collect();
+        Array3::from_shape_vec(shape, v).unwrap()
+    }
+
+    fn reference(a: ArrayView3<'_, f32>, b: ArrayView3<'_, f32>) -> Array3<f32> {
+        let mut c = Array3::zeros((a.len_of(Axis(0)), a.len_of(Axis(1)), b.len_of(Axis(2))));
+        for (i, mut c) in c.outer_iter_mut().enumerate() {
+            c.assign(&a.index_axis(Axis(0), i).dot(&b.index_axis(Axis(0), i)));
+        }
+        c
+    }
+
+    fn close(a: &Array3<f32>, b: &Array3<f32>) {
+        assert_eq!(a.dim(), b.dim());
+        for (x, y) in a.iter().zip(b) {
+            assert!(
+                (x - y).abs() <= 1e-4 * x.abs().max(y.abs()).max(1.0),
+                "{a:?} {b:?}"
+            );
+        }
+    }
+
+    /// Copies `x` into a column major array of the same shape.
+    fn fortran(x: &Array3<f32>) -> Array3<f32> {
+        let mut f = Array::zeros(x.raw_dim().f());
+        f.assign(x);
+        f
+    }
+
+    #[test]
+    fn layouts() {
+        let (batch, m, n, k) = (3, 13, 17, 37);
+        let a = data((batch, m, k), 1);
+        let b = data((batch, k, n), 2);
+        let expected = reference(a.view(), b.view());
+
+        // Per matrix column major, batches still contiguous.
+        let col = |x: &Array3<f32>| {
+            let mut t = x
+                .clone()
+                .permuted_axes([0, 2, 1])
+                .as_standard_layout()
+                .into_owned();
+            t.swap_axes(1, 2);
+            t
+        };
+        let a_col = col(&a);
+        let b_col = col(&b);
+        assert!(a_col.view().permuted_axes([0, 2, 1]).is_standard_layout());
+
+        for a in [&a, &a_col, &fortran(&a)] {
+            for b in [&b, &b_col, &fortran(&b)] {
+                close(&a.gg_dot(b), &expected);
+
+                let mut c = Array3::from_elem((batch, m, n).f(), f32::NAN);
+                batched_sgemm(a.view(), b.view(), c.view_mut());
+                close(&c, &expected);
+
+                let mut c = Array3::from_elem((batch, n, m), f32::NAN);
+                batched_sgemm(a.view(), b.view(), c.view_mut().permuted_axes([0, 2, 1]));
+                close(&c.permuted_axes([0, 2, 1]).to_owned(), &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn strided() {
+        let a = data((2, 26, 40), 1);
+        let b = data((2, 37, 34), 2);
+        let a = a.slice(s![.., ..;2, 3..]);
+        let b = b.slice(s![.., ..;-1, ..;2]);
+        let expected = reference(a, b);
+        close(&a.gg_dot(&b), &expected);
+
+        let mut c = Array3::zeros((2, 26, 34));
+        let mut out = c.slice_mut(s![.., ..;2, ..;2]);
+        batched_sgemm(a, b, out.view_mut());
+        close(&out.to_owned(), &expected);
+        // The skipped rows and columns are untouched.
+        assert!(c.slice(s![.., 1..;2, ..]).iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn matrices() {
+        let a = data((1, 5, 7), 1).index_axis_move(Axis(0), 0);
+        let b = data((1, 5, 3), 2).index_axis_move(Axis(0), 0);
+        let expected = a.t().dot(&b);
+        let got = a.t().gg_dot(&b);
+        for (x, y) in got.iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-4);
+        }
+
+        // Empty inner dimension.
+        let c = Array2::<f32>::zeros((3, 0)).gg_dot(&Array2::zeros((0, 4)));
+        assert_eq!(c, Array2::zeros((3, 4)));
+    }
+
+    #[cfg(feature = "f16")]
+    #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+    #[test]
+    fn f16() {
+        use half::f16;
+        let a = data((2, 5, 9), 1);
+        let b = data((2, 9, 4), 2);
+        let expected = reference(a.view(), b.view());
+        let a16 = a.mapv(f16::from_f32);
+        let b16 = fortran(&b).mapv(f16::from_f32);
+        let got = a16.gg_dot(&b16).mapv(f16::to_f32);
+        for (x, y) in got.iter().zip(&expected) {
+            assert!((x - y).abs() < 5e-2 * y.abs().max(1.0), "{x} {y}");
+        }
+    }
+}
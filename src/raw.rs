@@ -1,10 +1,15 @@
+use crate::epilogue::Epilogue;
 use crate::ggml::{vec_dot_f32, vec_mad_f32};
 
 use crate::ThreadPool;
 
+/// Computes `C = A * B`, or `C = A.T * B` when `a_transposed` in which case
+/// each batch of `A` is stored as `(k, m)`. `C` is overwritten without being
+/// read.
 pub unsafe fn ggml_compute_forward_mul_mat(
     ap: &[f32],
     a_skip: usize,
+    a_transposed: bool,
     bp: &[f32],
     b_skip: usize,
     cp: &mut [f32],
@@ -13,8 +18,26 @@ pub unsafe fn ggml_compute_forward_mul_mat(
     n: usize,
     k: usize,
     batching: usize,
+    epilogue: Option<&Epilogue>,
     pool: &ThreadPool,
 ) {
+    if split_k::worth_it(batching * m * n, k) {
+        return split_k::ggml_compute_forward_mul_mat(
+            ap,
+            a_skip,
+            a_transposed,
+            bp,
+            b_skip,
+            cp,
+            c_skip,
+            m,
+            n,
+            k,
+            batching,
+            epilogue,
+            pool,
+        );
+    }
     let ap = ap.as_ptr();
     let bp = bp.as_ptr();
     let cp = cp.as_mut_ptr();
@@ -24,6 +47,7 @@ pub unsafe fn ggml_compute_forward_mul_mat(
     let ap = ap as usize;
     let bp = bp as usize;
     let cp = cp as usize;
+    let epilogue = epilogue.map(|e| e as *const Epilogue as usize);
     let total_th = (total / n_cpu) + 1;
 
     (0..n_cpu).for_each(|ith| {
@@ -31,10 +55,19 @@ pub unsafe fn ggml_compute_forward_mul_mat(
             (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
                 let step = iter / m;
                 let i = iter % m;
+                let c_start = step * c_skip + (i * n);
+                // The rows accumulate from +0.0 whatever C held, NaN included.
+                unsafe {
+                    let c_row = (cp as *mut f32).add(c_start);
+                    std::slice::from_raw_parts_mut(c_row, n).fill(0.0);
+                }
                 (0..k).for_each(|kk| {
-                    let a_start = step * a_skip + i * k + kk;
+                    let a_start = if a_transposed {
+                        step * a_skip + kk * m + i
+                    } else {
+                        step * a_skip + i * k + kk
+                    };
                     let b_start = step * b_skip + kk * n;
-                    let c_start = step * c_skip + (i * n);
 
                     unsafe {
                         let ap = ap as *const f32;
@@ -46,6 +79,16 @@ pub unsafe fn ggml_compute_forward_mul_mat(
                         vec_mad_f32(b_row, c_row, av, n);
                     }
                 });
+                if let Some(epilogue) = epilogue {
+                    unsafe {
+                        let epilogue = &*(epilogue as *const Epilogue);
+                        let c_row = (cp as *mut f32).add(c_start);
+                        std::slice::from_raw_parts_mut(c_row, n)
+                            .iter_mut()
+                            .enumerate()
+                            .for_each(|(j, c)| *c = epilogue.apply(*c, i, j, c_start + j));
+                    }
+                }
             });
         });
     });
@@ -63,6 +106,49 @@ pub unsafe fn ggml_compute_forward_mul_mat_t(
     n: usize,
     k: usize,
     batching: usize,
+    epilogue: Option<&Epilogue>,
+    pool: &ThreadPool,
+) {
+    if split_k::worth_it(batching * m * n, k) {
+        return split_k::ggml_compute_forward_mul_mat_t(
+            ap, a_skip, bp, b_skip, cp, c_skip, m, n, k, batching, epilogue, pool,
+        );
+    }
+    ggml_compute_forward_mul_mat_t_dot(
+        vec_dot_f32,
+        ap,
+        a_skip,
+        bp,
+        b_skip,
+        cp,
+        c_skip,
+        m,
+        n,
+        k,
+        batching,
+        epilogue,
+        pool,
+    )
+}
+
+/// Dot product writing `sum(a[i] * b[i])` into `c`.
+pub type DotF32 = unsafe fn(*const f32, *const f32, *mut f32, usize);
+
+/// Same as [`ggml_compute_forward_mul_mat_t`] with every output computed by
+/// `dot`.
+pub unsafe fn ggml_compute_forward_mul_mat_t_dot(
+    dot: DotF32,
+    ap: &[f32],
+    a_skip: usize,
+    bp: &[f32],
+    b_skip: usize,
+    cp: &mut [f32],
+    c_skip: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    batching: usize,
+    epilogue: Option<&Epilogue>,
     pool: &ThreadPool,
 ) {
     let ap = ap.as_ptr();
@@ -75,6 +161,7 @@ pub unsafe fn ggml_compute_forward_mul_mat_t(
     let ap = ap as usize;
     let bp = bp as usize;
     let cp = cp as usize;
+    let epilogue = epilogue.map(|e| e as *const Epilogue as usize);
     let total_th = (total / n_cpu) + 1;
 
     (0..n_cpu).for_each(|ith| {
@@ -94,7 +181,11 @@ pub unsafe fn ggml_compute_forward_mul_mat_t(
                     let a_row = ap.add(a_start);
                     let b_row = bp.add(b_start);
                     let c_ptr = cp.add(c_start);
-                    vec_dot_f32(a_row, b_row, c_ptr, k);
+                    dot(a_row, b_row, c_ptr, k);
+                    if let Some(epilogue) = epilogue {
+                        let epilogue = &*(epilogue as *const Epilogue);
+                        *c_ptr = epilogue.apply(*c_ptr, i, j, c_start);
+                    }
                 }
             });
         });
@@ -102,6 +193,256 @@ pub unsafe fn ggml_compute_forward_mul_mat_t(
     pool.join();
 }
 
+/// Splitting the reduction over `k` for problems with too few outputs to
+/// keep every thread busy.
+///
+/// `k` is cut in at most [`SPLITS`] chunks whatever the thread count, each
+/// chunk is accumulated into its own partial `C` and the partials are then
+/// added in chunk order. Whether a problem gets split only depends on its
+/// shape, so the results never depend on the number of threads.
+pub mod split_k {
+    use super::ThreadPool;
+    use crate::epilogue::Epilogue;
+    use crate::ggml::{vec_dot_f32, vec_mad_f32};
+
+    /// Maximum number of chunks of `k`, bounding the partials to
+    /// `SPLITS * MAX_OUTPUTS` floats.
+    pub const SPLITS: usize = 32;
+    /// Smallest `k` worth splitting.
+    pub const MIN_K: usize = 16384;
+    /// Largest number of outputs, over all batches, worth splitting.
+    pub const MAX_OUTPUTS: usize = 16384;
+
+    pub fn worth_it(outputs: usize, k: usize) -> bool {
+        k >= MIN_K && outputs <= MAX_OUTPUTS
+    }
+
+    /// Chunk length, a multiple of every SIMD step.
+    fn chunk(k: usize) -> usize {
+        k.div_ceil(SPLITS).next_multiple_of(64)
+    }
+
+    /// Runs `partial(k_start, k_len, out)` for every chunk of `k` on the
+    /// pool, `out` being that chunk's `outputs` partial sums, then hands the
+    /// sums of the partials to `store(o, sum)`, `o` being the index of the
+    /// output in `(batch, i, j)` order.
+    unsafe fn run(
+        outputs: usize,
+        k: usize,
+        pool: &ThreadPool,
+        partial: impl Fn(usize, usize, *mut f32) + Copy + Send + 'static,
+        mut store: impl FnMut(usize, f32),
+    ) {
+        let chunk = chunk(k);
+        let chunks = k.div_ceil(chunk);
+        let mut partials = vec![0.0f32; chunks * outputs];
+        let pp = partials.as_mut_ptr() as usize;
+        let n_cpu = pool.max_count();
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith..chunks).step_by(n_cpu).for_each(|s| {
+                    let k_start = s * chunk;
+                    let out = (pp as *mut f32).add(s * outputs);
+                    partial(k_start, chunk.min(k - k_start), out);
+                });
+            });
+        });
+        pool.join();
+        for o in 0..outputs {
+            let sum = (1..chunks).fold(partials[o], |sum, s| sum + partials[s * outputs + o]);
+            store(o, sum);
+        }
+    }
+
+    /// Split version of [`super::ggml_compute_forward_mul_mat`].
+    pub unsafe fn ggml_compute_forward_mul_mat(
+        ap: &[f32],
+        a_skip: usize,
+        a_transposed: bool,
+        bp: &[f32],
+        b_skip: usize,
+        cp: &mut [f32],
+        c_skip: usize,
+        m: usize,
+        n: usize,
+        k: usize,
+        batching: usize,
+        epilogue: Option<&Epilogue>,
+        pool: &ThreadPool,
+    ) {
+        let ap = ap.as_ptr() as usize;
+        let bp = bp.as_ptr() as usize;
+        let partial = move |k_start: usize, k_len: usize, out: *mut f32| {
+            let ap = ap as *const f32;
+            let bp = bp as *const f32;
+            for step in 0..batching {
+                for i in 0..m {
+                    let c_row = out.add((step * m + i) * n);
+                    for kk in k_start..k_start + k_len {
+                        let a_start = if a_transposed {
+                            step * a_skip + kk * m + i
+                        } else {
+                            step * a_skip + i * k + kk
+                        };
+                        let b_row = bp.add(step * b_skip + kk * n);
+                        vec_mad_f32(b_row, c_row, *ap.add(a_start), n);
+                    }
+                }
+            }
+        };
+        run(batching * m * n, k, pool, partial, |o, sum| {
+            let (step, i, j) = (o / (m * n), (o / n) % m, o % n);
+            let c_index = step * c_skip + i * n + j;
+            cp[c_index] = match epilogue {
+                Some(epilogue) => epilogue.apply(sum, i, j, c_index),
+                None => sum,
+            };
+        });
+    }
+
+    /// Split version of [`super::ggml_compute_forward_mul_mat_t`].
+    pub unsafe fn ggml_compute_forward_mul_mat_t(
+        ap: &[f32],
+        a_skip: usize,
+        bp: &[f32],
+        b_skip: usize,
+        cp: &mut [f32],
+        c_skip: usize,
+        m: usize,
+        n: usize,
+        k: usize,
+        batching: usize,
+        epilogue: Option<&Epilogue>,
+        pool: &ThreadPool,
+    ) {
+        let ap = ap.as_ptr() as usize;
+        let bp = bp.as_ptr() as usize;
+        let partial = move |k_start: usize, k_len: usize, out: *mut f32| {
+            let ap = ap as *const f32;
+            let bp = bp as *const f32;
+            for o in 0..batching * m * n {
+                let (step, i, j) = (o / (m * n), (o / n) % m, o % n);
+                let a_row = ap.add(step * a_skip + i * k + k_start);
+                let b_row = bp.add(step * b_skip + j * k + k_start);
+                vec_dot_f32(a_row, b_row, out.add(o), k_len);
+            }
+        };
+        run(batching * m * n, k, pool, partial, |o, sum| {
+            let (step, i, j) = (o / (m * n), (o / n) % m, o % n);
+            let c_index = step * c_skip + i * n + j;
+            cp[c_index] = match epilogue {
+                Some(epilogue) => epilogue.apply(sum, i, j, c_index),
+                None => sum,
+            };
+        });
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{reference, Bias};
+
+        fn data(n: usize, seed: usize) -> Vec<f32> {
+            (0..n)
+                .map(|i| ((i * 7919 + seed) % 61) as f32 / 16.0 - 1.875)
+                .collect()
+        }
+
+        #[test]
+        fn matches_reference_for_any_thread_count() {
+            let (batch, m, n, k) = (2, 3, 5, MIN_K + 1001);
+            assert!(worth_it(batch * m * n, k));
+            let a = data(batch * m * k, 1);
+            let b = data(batch * k * n, 2);
+            let mut b_t = vec![0.0; b.len()];
+            for s in 0..batch {
+                for kk in 0..k {
+                    for j in 0..n {
+                        b_t[(s * n + j) * k + kk] = b[(s * k + kk) * n + j];
+                    }
+                }
+            }
+            let mut expected = vec![0.0; batch * m * n];
+            reference::batched_sgemm(&a, &b, &mut expected, m, n, k);
+            let bias = data(n, 3);
+            let epilogue = Epilogue {
+                alpha: 0.5,
+                bias: Some(Bias::N(&bias)),
+                ..Default::default()
+            };
+
+            let mut first: Option<Vec<Vec<u32>>> = None;
+            for threads in 1..=5 {
+                let pool = ThreadPool::new(threads);
+                let mut c_n = vec![f32::NAN; batch * m * n];
+                let mut c_t = vec![f32::NAN; batch * m * n];
+                let mut c_e = vec![f32::NAN; batch * m * n];
+                unsafe {
+                    super::super::ggml_compute_forward_mul_mat(
+                        &a,
+                        m * k,
+                        false,
+                        &b,
+                        k * n,
+                        &mut c_n,
+                        m * n,
+                        m,
+                        n,
+                        k,
+                        batch,
+                        None,
+                        &pool,
+                    );
+                    super::super::ggml_compute_forward_mul_mat_t(
+                        &a,
+                        m * k,
+                        &b_t,
+                        k * n,
+                        &mut c_t,
+                        m * n,
+                        m,
+                        n,
+                        k,
+                        batch,
+                        None,
+                        &pool,
+                    );
+                    super::super::ggml_compute_forward_mul_mat_t(
+                        &a,
+                        m * k,
+                        &b_t,
+                        k * n,
+                        &mut c_e,
+                        m * n,
+                        m,
+                        n,
+                        k,
+                        batch,
+                        Some(&epilogue),
+                        &pool,
+                    );
+                }
+                for (idx, &e) in expected.iter().enumerate() {
+                    let tol = 1e-4 * e.abs().max(1.0);
+                    assert!((c_n[idx] - e).abs() < tol, "{} {e}", c_n[idx]);
+                    assert!((c_t[idx] - e).abs() < tol, "{} {e}", c_t[idx]);
+                    let e = 0.5 * e + bias[idx % n];
+                    assert!((c_e[idx] - e).abs() < tol, "{} {e}", c_e[idx]);
+                }
+
+                let bits: Vec<Vec<u32>> = [c_n, c_t, c_e]
+                    .iter()
+                    .map(|c| c.iter().map(|v| v.to_bits()).collect())
+                    .collect();
+                match &first {
+                    None => first = Some(bits),
+                    Some(first) => assert_eq!(first, &bits, "{threads} threads"),
+                }
+            }
+        }
+    }
+}
+
 #[cfg(feature = "f16")]
 #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
 pub mod f16 {
@@ -214,3 +555,555 @@ pub mod f16 {
         pool.join();
     }
 }
+
+pub mod quantized {
+    use super::ThreadPool;
+    use crate::ggml::quants::GgmlType;
+
+    pub unsafe fn ggml_compute_forward_mul_mat_t_quantized<T: GgmlType>(
+        ap: &[f32],
+        a_skip: usize,
+        bp: &[T],
+        b_skip: usize,
+        cp: &mut [f32],
+        c_skip: usize,
+        m: usize,
+        n: usize,
+        k: usize,
+        batching: usize,
+        pool: &ThreadPool,
+    ) {
+        // Activations are quantized once up front, one row of `k` values at a time.
+        let nb = k / T::BLCK_SIZE;
+        let mut a_q = vec![T::VecDotType::zeros(); batching * m * nb];
+        a_q.chunks_exact_mut(nb)
+            .zip(ap.chunks_exact(k))
+            .for_each(|(a_q, a)| T::VecDotType::from_float(a, a_q));
+        let ap = a_q.as_ptr();
+        let bp = bp.as_ptr();
+        let cp = cp.as_mut_ptr();
+        let total = batching * m * n;
+        let a_skip = a_skip / T::BLCK_SIZE;
+
+        let n_cpu = pool.max_count();
+
+        let ap = ap as usize;
+        let bp = bp as usize;
+        let cp = cp as usize;
+        let total_th = (total / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
+                    let step = iter / (m * n);
+                    let i = (iter / n) % m;
+                    let j = iter % n;
+                    let a_start = step * a_skip + i * nb;
+                    let b_start = step * b_skip + j * nb;
+                    let c_start = step * c_skip + (i * n + j);
+
+                    unsafe {
+                        let ap = ap as *const T::VecDotType;
+                        let bp = bp as *const T;
+                        let cp = cp as *mut f32;
+                        let a_row = std::slice::from_raw_parts(ap.add(a_start), nb);
+                        let b_row = std::slice::from_raw_parts(bp.add(b_start), nb);
+                        *cp.add(c_start) = T::vec_dot(k, b_row, a_row);
+                    }
+                });
+            });
+        });
+        pool.join();
+    }
+}
+
+pub mod int8 {
+    use super::ThreadPool;
+    use crate::ggml::int8::vec_dot_i8;
+
+    /// Computes `C = A * B.T` on i8 inputs, `epilogue(acc, i, j)` turns the i32
+    /// accumulator of row `i` and column `j` into the stored value.
+    pub unsafe fn ggml_compute_forward_mul_mat_t_i8<T, F>(
+        ap: &[i8],
+        a_skip: usize,
+        bp: &[i8],
+        b_skip: usize,
+        cp: &mut [T],
+        c_skip: usize,
+        m: usize,
+        n: usize,
+        k: usize,
+        batching: usize,
+        epilogue: &F,
+        pool: &ThreadPool,
+    ) where
+        F: Fn(i32, usize, usize) -> T + Sync,
+    {
+        let ap = ap.as_ptr();
+        let bp = bp.as_ptr();
+        let cp = cp.as_mut_ptr();
+        let total = batching * m * n;
+
+        let n_cpu = pool.max_count();
+
+        let ap = ap as usize;
+        let bp = bp as usize;
+        let cp = cp as usize;
+        let epilogue = epilogue as *const F as usize;
+        let total_th = (total / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
+                    let step = iter / (m * n);
+                    let i = (iter / n) % m;
+                    let j = iter % n;
+                    let a_start = step * a_skip + i * k;
+                    let b_start = step * b_skip + j * k;
+                    let c_start = step * c_skip + (i * n + j);
+
+                    unsafe {
+                        let ap = ap as *const i8;
+                        let bp = bp as *const i8;
+                        let cp = cp as *mut T;
+                        let epilogue = &*(epilogue as *const F);
+                        let a_row = ap.add(a_start);
+                        let b_row = bp.add(b_start);
+                        let acc = vec_dot_i8(a_row, b_row, k);
+                        *cp.add(c_start) = epilogue(acc, i, j);
+                    }
+                });
+            });
+        });
+        pool.join();
+    }
+}
+
+pub mod level2 {
+    use super::ThreadPool;
+    use crate::ggml::{vec_dot_f32, vec_mad_f32};
+    use crate::level2::{Diag, Uplo};
+
+    /// Computes `A += alpha * x * y.T`, one row of `A` per task.
+    pub unsafe fn ggml_compute_forward_ger(
+        alpha: f32,
+        xp: &[f32],
+        yp: &[f32],
+        ap: &mut [f32],
+        m: usize,
+        n: usize,
+        pool: &ThreadPool,
+    ) {
+        let xp = xp.as_ptr();
+        let yp = yp.as_ptr();
+        let ap = ap.as_mut_ptr();
+
+        let n_cpu = pool.max_count();
+        let xp = xp as usize;
+        let yp = yp as usize;
+        let ap = ap as usize;
+        let total_th = (m / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(m, (ith + 1) * total_th)).for_each(|i| unsafe {
+                    let xp = xp as *const f32;
+                    let yp = yp as *const f32;
+                    let ap = ap as *mut f32;
+                    vec_mad_f32(yp, ap.add(i * n), alpha * *xp.add(i), n);
+                });
+            });
+        });
+        pool.join();
+    }
+
+    /// Computes `y = alpha * A * x + beta * y` with only the `uplo` triangle of
+    /// `A` being read.
+    ///
+    /// Each stored row contributes both a dot product (its row) and an axpy
+    /// (its mirrored column), the latter are accumulated in per thread buffers
+    /// which get reduced once every thread is done.
+    pub unsafe fn ggml_compute_forward_symv(
+        uplo: Uplo,
+        alpha: f32,
+        ap: &[f32],
+        xp: &[f32],
+        beta: f32,
+        yp: &mut [f32],
+        n: usize,
+        pool: &ThreadPool,
+    ) {
+        let n_cpu = pool.max_count();
+        let mut partials = vec![0.0f32; n_cpu * n];
+
+        let ap = ap.as_ptr() as usize;
+        let xp = xp.as_ptr() as usize;
+        let pp = partials.as_mut_ptr() as usize;
+        let total_th = (n / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(n, (ith + 1) * total_th)).for_each(|i| unsafe {
+                    let ap = ap as *const f32;
+                    let xp = xp as *const f32;
+                    let partial = (pp as *mut f32).add(ith * n);
+                    let a_row = ap.add(i * n);
+                    let xi = *xp.add(i);
+                    let mut dot = 0.0;
+                    match uplo {
+                        Uplo::Upper => {
+                            vec_dot_f32(a_row.add(i), xp.add(i), &mut dot, n - i);
+                            vec_mad_f32(a_row.add(i + 1), partial.add(i + 1), xi, n - i - 1);
+                        }
+                        Uplo::Lower => {
+                            vec_dot_f32(a_row, xp, &mut dot, i + 1);
+                            vec_mad_f32(a_row, partial, xi, i);
+                        }
+                    }
+                    *partial.add(i) += dot;
+                });
+            });
+        });
+        pool.join();
+
+        if beta == 0.0 {
+            yp.fill(0.0);
+        } else if beta != 1.0 {
+            yp.iter_mut().for_each(|y| *y *= beta);
+        }
+        partials
+            .chunks_exact(n)
+            .for_each(|partial| vec_mad_f32(partial.as_ptr(), yp.as_mut_ptr(), alpha, n));
+    }
+
+    /// Computes `x = A * x` for a triangular `A`, rows are independent once
+    /// `x` has been copied.
+    pub unsafe fn ggml_compute_forward_trmv(
+        uplo: Uplo,
+        diag: Diag,
+        ap: &[f32],
+        xp: &mut [f32],
+        n: usize,
+        pool: &ThreadPool,
+    ) {
+        let x0 = xp.to_vec();
+
+        let n_cpu = pool.max_count();
+        let ap = ap.as_ptr() as usize;
+        let x0p = x0.as_ptr() as usize;
+        let xp = xp.as_mut_ptr() as usize;
+        let total_th = (n / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(n, (ith + 1) * total_th)).for_each(|i| unsafe {
+                    let ap = ap as *const f32;
+                    let x0p = x0p as *const f32;
+                    let xp = xp as *mut f32;
+                    let a_row = ap.add(i * n);
+                    // Range of the row strictly off the diagonal.
+                    let (start, len) = match uplo {
+                        Uplo::Upper => (i + 1, n - i - 1),
+                        Uplo::Lower => (0, i),
+                    };
+                    let mut dot = 0.0;
+                    vec_dot_f32(a_row.add(start), x0p.add(start), &mut dot, len);
+                    let d = match diag {
+                        Diag::Unit => *x0p.add(i),
+                        Diag::NonUnit => *a_row.add(i) * *x0p.add(i),
+                    };
+                    *xp.add(i) = dot + d;
+                });
+            });
+        });
+        pool.join();
+    }
+}
+
+pub mod level3 {
+    use super::ThreadPool;
+    use crate::ggml::{vec_dot_f32, vec_mad_f32, vec_scale_f32};
+    use crate::level2::{Diag, Uplo};
+    use crate::level3::Side;
+
+    /// Computes the `uplo` triangle of `C = A * A.T`, one row of `C` per task.
+    pub unsafe fn ggml_compute_forward_syrk(
+        uplo: Uplo,
+        ap: &[f32],
+        a_skip: usize,
+        cp: &mut [f32],
+        c_skip: usize,
+        n: usize,
+        k: usize,
+        batching: usize,
+        pool: &ThreadPool,
+    ) {
+        let ap = ap.as_ptr();
+        let cp = cp.as_mut_ptr();
+        let total = batching * n;
+
+        let n_cpu = pool.max_count();
+        let ap = ap as usize;
+        let cp = cp as usize;
+        let total_th = (total / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
+                    let step = iter / n;
+                    let i = iter % n;
+                    let columns = match uplo {
+                        Uplo::Upper => i..n,
+                        Uplo::Lower => 0..i + 1,
+                    };
+                    columns.for_each(|j| unsafe {
+                        let ap = ap as *const f32;
+                        let cp = cp as *mut f32;
+                        let a_row = ap.add(step * a_skip + i * k);
+                        let b_row = ap.add(step * a_skip + j * k);
+                        let c_ptr = cp.add(step * c_skip + i * n + j);
+                        vec_dot_f32(a_row, b_row, c_ptr, k);
+                    });
+                });
+            });
+        });
+        pool.join();
+    }
+
+    /// Computes `B = A * B` (left) or `B = B * A` (right) for a triangular `A`.
+    ///
+    /// Both sides accumulate rows of the result with `vec_mad_f32`, restricted
+    /// to the stored triangle, from a copy of the original `B`.
+    pub unsafe fn ggml_compute_forward_trmm(
+        side: Side,
+        uplo: Uplo,
+        diag: Diag,
+        ap: &[f32],
+        a_skip: usize,
+        bp: &mut [f32],
+        b_skip: usize,
+        m: usize,
+        n: usize,
+        batching: usize,
+        pool: &ThreadPool,
+    ) {
+        let b0 = bp.to_vec();
+        let ap = ap.as_ptr();
+        let b0p = b0.as_ptr();
+        let bp = bp.as_mut_ptr();
+        let total = batching * m;
+        // Order of `A`.
+        let o = match side {
+            Side::Left => m,
+            Side::Right => n,
+        };
+
+        let n_cpu = pool.max_count();
+        let ap = ap as usize;
+        let b0p = b0p as usize;
+        let bp = bp as usize;
+        let total_th = (total / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
+                    let step = iter / m;
+                    let i = iter % m;
+                    unsafe {
+                        let a = (ap as *const f32).add(step * a_skip);
+                        let b0 = (b0p as *const f32).add(step * b_skip);
+                        let out = (bp as *mut f32).add(step * b_skip + i * n);
+                        std::slice::from_raw_parts_mut(out, n).fill(0.0);
+                        let a_diag = |kk: usize| match diag {
+                            Diag::Unit => 1.0,
+                            Diag::NonUnit => *a.add(kk * o + kk),
+                        };
+                        match side {
+                            // out[i, :] = sum_kk A[i, kk] * B[kk, :]
+                            Side::Left => {
+                                let off_diagonal = match uplo {
+                                    Uplo::Upper => i + 1..m,
+                                    Uplo::Lower => 0..i,
+                                };
+                                off_diagonal.for_each(|kk| {
+                                    vec_mad_f32(b0.add(kk * n), out, *a.add(i * m + kk), n)
+                                });
+                                vec_mad_f32(b0.add(i * n), out, a_diag(i), n);
+                            }
+                            // out[i, :] = sum_kk B[i, kk] * A[kk, :]
+                            Side::Right => (0..n).for_each(|kk| {
+                                let v = *b0.add(i * n + kk);
+                                let a_row = a.add(kk * n);
+                                match uplo {
+                                    Uplo::Upper => vec_mad_f32(
+                                        a_row.add(kk + 1),
+                                        out.add(kk + 1),
+                                        v,
+                                        n - kk - 1,
+                                    ),
+                                    Uplo::Lower => vec_mad_f32(a_row, out, v, kk),
+                                }
+                                *out.add(kk) += v * a_diag(kk);
+                            }),
+                        }
+                    }
+                });
+            });
+        });
+        pool.join();
+    }
+
+    /// Solves `A * X = B` (left) or `X * A = B` (right) for a triangular `A`,
+    /// `X` overwrites `B`.
+    ///
+    /// On the left the substitution runs over the rows of `B` and the columns
+    /// are split between threads, on the right every row of `B` is an
+    /// independent system.
+    pub unsafe fn ggml_compute_forward_trsm(
+        side: Side,
+        uplo: Uplo,
+        diag: Diag,
+        ap: &[f32],
+        a_skip: usize,
+        bp: &mut [f32],
+        b_skip: usize,
+        m: usize,
+        n: usize,
+        batching: usize,
+        pool: &ThreadPool,
+    ) {
+        let ap = ap.as_ptr();
+        let bp = bp.as_mut_ptr();
+
+        let n_cpu = pool.max_count();
+        let (tasks, width) = match side {
+            Side::Left => {
+                let width = (n / n_cpu) + 1;
+                (n.div_ceil(width), width)
+            }
+            Side::Right => (m, 0),
+        };
+        let total = batching * tasks;
+
+        let ap = ap as usize;
+        let bp = bp as usize;
+        let total_th = (total / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
+                    let step = iter / tasks;
+                    let task = iter % tasks;
+                    unsafe {
+                        let a = (ap as *const f32).add(step * a_skip);
+                        let b = (bp as *mut f32).add(step * b_skip);
+                        match side {
+                            Side::Left => {
+                                let start = task * width;
+                                let len = std::cmp::min(n, start + width) - start;
+                                let b = b.add(start);
+                                let mut solve = |i: usize| {
+                                    let off_diagonal = match uplo {
+                                        Uplo::Upper => i + 1..m,
+                                        Uplo::Lower => 0..i,
+                                    };
+                                    off_diagonal.for_each(|kk| {
+                                        let v = -*a.add(i * m + kk);
+                                        vec_mad_f32(b.add(kk * n), b.add(i * n), v, len);
+                                    });
+                                    if diag == Diag::NonUnit {
+                                        vec_scale_f32(b.add(i * n), 1.0 / *a.add(i * m + i), len);
+                                    }
+                                };
+                                match uplo {
+                                    Uplo::Upper => (0..m).rev().for_each(&mut solve),
+                                    Uplo::Lower => (0..m).for_each(&mut solve),
+                                }
+                            }
+                            Side::Right => {
+                                let x = b.add(task * n);
+                                let mut solve = |kk: usize| {
+                                    if diag == Diag::NonUnit {
+                                        *x.add(kk) /= *a.add(kk * n + kk);
+                                    }
+                                    let v = -*x.add(kk);
+                                    let a_row = a.add(kk * n);
+                                    match uplo {
+                                        Uplo::Upper => vec_mad_f32(
+                                            a_row.add(kk + 1),
+                                            x.add(kk + 1),
+                                            v,
+                                            n - kk - 1,
+                                        ),
+                                        Uplo::Lower => vec_mad_f32(a_row, x, v, kk),
+                                    }
+                                };
+                                match uplo {
+                                    Uplo::Upper => (0..n).for_each(&mut solve),
+                                    Uplo::Lower => (0..n).rev().for_each(&mut solve),
+                                }
+                            }
+                        }
+                    }
+                });
+            });
+        });
+        pool.join();
+    }
+}
+
+pub mod complex {
+    use super::ThreadPool;
+    use crate::complex::ComplexDot;
+
+    pub unsafe fn ggml_compute_forward_mul_mat_t_complex<T: ComplexDot>(
+        ap: &[T],
+        a_skip: usize,
+        bp: &[T],
+        b_skip: usize,
+        cp: &mut [T],
+        c_skip: usize,
+        m: usize,
+        n: usize,
+        k: usize,
+        batching: usize,
+        conj_a: bool,
+        conj_b: bool,
+        pool: &ThreadPool,
+    ) {
+        let ap = ap.as_ptr();
+        let bp = bp.as_ptr();
+        let cp = cp.as_mut_ptr();
+        let total = batching * m * n;
+
+        let n_cpu = pool.max_count();
+
+        let ap = ap as usize;
+        let bp = bp as usize;
+        let cp = cp as usize;
+        let total_th = (total / n_cpu) + 1;
+
+        (0..n_cpu).for_each(|ith| {
+            pool.execute(move || {
+                (ith * total_th..std::cmp::min(total, (ith + 1) * total_th)).for_each(|iter| {
+                    let step = iter / (m * n);
+                    let i = (iter / n) % m;
+                    let j = iter % n;
+                    let a_start = step * a_skip + i * k;
+                    let b_start = step * b_skip + j * k;
+                    let c_start = step * c_skip + (i * n + j);
+
+                    unsafe {
+                        let ap = ap as *const T;
+                        let bp = bp as *const T;
+                        let cp = cp as *mut T;
+                        let a_row = ap.add(a_start);
+                        let b_row = bp.add(b_start);
+                        *cp.add(c_start) = T::vec_dot(a_row, b_row, k, conj_a, conj_b);
+                    }
+                });
+            });
+        });
+        pool.join();
+    }
+}
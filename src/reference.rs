@@ -0,0 +1,71 @@
+//! Straightforward GEMMs used as the ground truth by the tests.
+//!
+//! They take the same arguments as their optimized counterparts, accumulate
+//! in `f64` in the natural order and make no attempt at being fast.
+use crate::{batching, size};
+
+fn gemm(
+    ap: &[f32],
+    bp: &[f32],
+    cp: &mut [f32],
+    m: usize,
+    n: usize,
+    k: usize,
+    b_at: impl Fn(&[f32], usize, usize) -> f32,
+) {
+    let a_skip = size(&[m, k]);
+    let b_skip = size(&[k, n]);
+    let c_skip = size(&[m, n]);
+    let batching = batching([
+        ("A", ap.len(), a_skip),
+        ("B", bp.len(), b_skip),
+        ("C", cp.len(), c_skip),
+    ]);
+    for batch in 0..batching {
+        let a = &ap[batch * a_skip..][..a_skip];
+        let b = &bp[batch * b_skip..][..b_skip];
+        let c = &mut cp[batch * c_skip..][..c_skip];
+        for i in 0..m {
+            for j in 0..n {
+                // An empty sum, for `k == 0`, writes zeros.
+                c[i * n + j] = (0..k)
+                    .map(|l| a[i * k + l] as f64 * b_at(b, l, j) as f64)
+                    .sum::<f64>() as f32;
+            }
+        }
+    }
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B
+/// ```
+///
+/// ```
+/// use ggblas::reference::batched_sgemm;
+///
+/// let mut c = vec![0.; 4];
+/// batched_sgemm(&[1., 2., 3., 4.], &[1., 2., 3., 4.], &mut c, 2, 2, 2);
+/// assert_eq!(c, &[7., 10., 15., 22.]);
+/// ```
+pub fn batched_sgemm(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+    gemm(ap, bp, cp, m, n, k, |b, l, j| b[l * n + j])
+}
+
+/// Computes batched matrixmultiplication
+///
+/// ```latex
+/// C = A * B.T
+/// ```
+///
+/// ```
+/// use ggblas::reference::batched_sgemm_t;
+///
+/// let mut c = vec![0.; 4];
+/// batched_sgemm_t(&[1., 2., 3., 4.], &[1., 2., 3., 4.], &mut c, 2, 2, 2);
+/// assert_eq!(c, &[5., 11., 11., 25.]);
+/// ```
+pub fn batched_sgemm_t(ap: &[f32], bp: &[f32], cp: &mut [f32], m: usize, n: usize, k: usize) {
+    gemm(ap, bp, cp, m, n, k, |b, l, j| b[j * k + l])
+}
@@ -0,0 +1,133 @@
+//! GEMM on batches of matrices described by a pointer and element strides,
+//! shared by the container adapters.
+//!
+//! Operands that are contiguous in row major or column major order are handed
+//! to the kernels as they are, with the column major ones walked transposed.
+//! Anything else (sliced, stepped, reversed or broadcast) is copied to a
+//! contiguous buffer first. The output is written in place when it is
+//! contiguous and through a temporary otherwise.
+use crate::{batched_sgemm, batched_sgemm_layout, batched_sgemm_t, batched_sgemm_t_layout, Layout};
+use std::borrow::Cow;
+
+/// A batch of matrices, `dim` is `(batch, rows, cols)` and `strides` the
+/// matching steps in elements.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Strided<P> {
+    pub ptr: P,
+    pub dim: (usize, usize, usize),
+    pub strides: (isize, isize, isize),
+}
+
+impl<P> Strided<P> {
+    fn len(&self) -> usize {
+        self.dim.0 * self.dim.1 * self.dim.2
+    }
+
+    /// Whether the whole batch is one contiguous buffer, each matrix being
+    /// stored in row major order, or column major order.
+    fn contiguous(&self, row_major: bool) -> bool {
+        let (batch, rows, cols) = self.dim;
+        let (batch_stride, row_stride, col_stride) = self.strides;
+        let (inner, inner_stride, outer, outer_stride) = if row_major {
+            (cols, col_stride, rows, row_stride)
+        } else {
+            (rows, row_stride, cols, col_stride)
+        };
+        (inner <= 1 || inner_stride == 1)
+            && (outer <= 1 || outer_stride == inner as isize)
+            && (batch <= 1 || batch_stride == (rows * cols) as isize)
+    }
+
+    /// Element offsets in row major iteration order.
+    fn offsets(&self) -> impl Iterator<Item = isize> {
+        let (batch, rows, cols) = self.dim;
+        let (batch_stride, row_stride, col_stride) = self.strides;
+        (0..batch as isize).flat_map(move |b| {
+            (0..rows as isize).flat_map(move |i| {
+                (0..cols as isize).map(move |j| b * batch_stride + i * row_stride + j * col_stride)
+            })
+        })
+    }
+}
+
+/// A batch of matrices as the kernels want it.
+enum Operand<'a> {
+    /// Each matrix is contiguous in row major order.
+    RowMajor(Cow<'a, [f32]>),
+    /// Each matrix is contiguous in column major order, i.e. the buffer reads
+    /// as the row major transposed matrices.
+    ColMajor(&'a [f32]),
+}
+
+unsafe fn operand<'a>(x: Strided<*const f32>) -> Operand<'a> {
+    if x.contiguous(true) {
+        Operand::RowMajor(Cow::Borrowed(std::slice::from_raw_parts(x.ptr, x.len())))
+    } else if x.contiguous(false) {
+        Operand::ColMajor(std::slice::from_raw_parts(x.ptr, x.len()))
+    } else {
+        Operand::RowMajor(Cow::Owned(x.offsets().map(|o| *x.ptr.offset(o)).collect()))
+    }
+}
+
+/// Runs `f` on a row major, or column major, buffer of `c`.
+unsafe fn output(c: Strided<*mut f32>, row_major: bool, f: impl FnOnce(&mut [f32])) {
+    if c.contiguous(row_major) {
+        return f(std::slice::from_raw_parts_mut(c.ptr, c.len()));
+    }
+    let mut tmp = vec![0.0; c.len()];
+    f(&mut tmp);
+    if row_major {
+        for (o, v) in c.offsets().zip(tmp) {
+            *c.ptr.offset(o) = v;
+        }
+    } else {
+        let (batch, rows, cols) = c.dim;
+        let (batch_stride, row_stride, col_stride) = c.strides;
+        let t = Strided {
+            ptr: c.ptr,
+            dim: (batch, cols, rows),
+            strides: (batch_stride, col_stride, row_stride),
+        };
+        for (o, v) in t.offsets().zip(tmp) {
+            *c.ptr.offset(o) = v;
+        }
+    }
+}
+
+/// Computes `C = A * B` for every matrix of the batch, `C` is overwritten.
+///
+/// # Safety
+/// The pointers must be valid for their dims and strides, and `c` must not
+/// overlap `a` or `b` nor itself.
+pub(crate) unsafe fn sgemm(a: Strided<*const f32>, b: Strided<*const f32>, c: Strided<*mut f32>) {
+    let (batch, m, k) = a.dim;
+    let (b_batch, b_k, n) = b.dim;
+    assert_eq!(
+        (batch, k),
+        (b_batch, b_k),
+        "inputs have incompatible shapes"
+    );
+    assert_eq!(c.dim, (batch, m, n), "output has an incompatible shape");
+    if c.len() == 0 {
+        return;
+    }
+    if k == 0 {
+        return output(c, true, |c| c.fill(0.0));
+    }
+    match (operand(a), operand(b)) {
+        (Operand::RowMajor(a), Operand::RowMajor(b)) => {
+            output(c, true, |c| batched_sgemm(&a, &b, c, m, n, k))
+        }
+        (Operand::RowMajor(a), Operand::ColMajor(bt)) => {
+            output(c, true, |c| batched_sgemm_t(&a, bt, c, m, n, k))
+        }
+        // The column major problem `C.T = B.T * A` reads `B` as `B.T` and
+        // `A.T` as `A`, which are exactly the buffers at hand.
+        (Operand::ColMajor(at), Operand::RowMajor(b)) => output(c, true, |c| {
+            batched_sgemm_t_layout(Layout::ColMajor, &b, at, c, n, m, k)
+        }),
+        (Operand::ColMajor(at), Operand::ColMajor(bt)) => output(c, false, |c| {
+            batched_sgemm_layout(Layout::ColMajor, at, bt, c, m, n, k)
+        }),
+    }
+}
@@ -1,30 +1,262 @@
+//! Thread pool of the wasm32 builds.
+//!
+//! By default the jobs run inline, the kernels seeing a single thread. With
+//! the `wasi-threads` feature the pool spawns workers through `std::thread`,
+//! which only starts threads on WASI: it needs shared memory (the `atomics`
+//! target feature) and a runtime implementing wasi-threads, e.g. the
+//! `wasm32-wasip1-threads` target under `wasmtime -W threads=y -S threads=y`.
+//! Browsers are not supported, `std` cannot spawn Web Workers, and the crate
+//! refuses to build the feature for targets other than WASI. Where spawning
+//! fails the thread calling [`ThreadPool::join`] runs every job itself.
+use std::sync::OnceLock;
+
+#[cfg(not(feature = "wasi-threads"))]
+#[derive(Clone)]
 pub struct ThreadPool {
     n_threads: usize,
 }
 
+#[cfg(not(feature = "wasi-threads"))]
 impl ThreadPool {
     pub fn new(n_threads: usize) -> Self {
         Self { n_threads }
     }
+
     pub fn max_count(&self) -> usize {
         self.n_threads
     }
 
-    pub fn execute<F: Fn() -> ()>(&self, f: F) {
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
         f();
     }
 
     pub fn join(&self) {}
 }
 
-pub struct Once {}
+#[cfg(feature = "wasi-threads")]
+pub use threaded::ThreadPool;
+
+#[cfg(feature = "wasi-threads")]
+mod threaded {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+    type Job = Box<dyn FnOnce() + Send + 'static>;
+
+    #[derive(Default)]
+    struct Queue {
+        jobs: VecDeque<Job>,
+        /// Jobs queued or running.
+        pending: usize,
+        stop: bool,
+    }
+
+    #[derive(Default)]
+    struct Shared {
+        queue: Mutex<Queue>,
+        /// Signaled when a job is queued or the pool stops.
+        work: Condvar,
+        /// Signaled when `pending` drops to 0.
+        done: Condvar,
+    }
+
+    impl Shared {
+        fn lock(&self) -> MutexGuard<'_, Queue> {
+            self.queue.lock().unwrap()
+        }
+
+        fn run(&self, job: Job) {
+            job();
+            let mut queue = self.lock();
+            queue.pending -= 1;
+            if queue.pending == 0 {
+                self.done.notify_all();
+            }
+        }
+
+        fn work(&self) {
+            loop {
+                let job = {
+                    let mut queue = self.lock();
+                    loop {
+                        if queue.stop {
+                            return;
+                        }
+                        if let Some(job) = queue.jobs.pop_front() {
+                            break job;
+                        }
+                        queue = self.work.wait(queue).unwrap();
+                    }
+                };
+                self.run(job);
+            }
+        }
+    }
+
+    /// Stops the workers once the last handle of the pool is gone.
+    struct Workers(Arc<Shared>);
+
+    impl Drop for Workers {
+        fn drop(&mut self) {
+            self.0.lock().stop = true;
+            self.0.work.notify_all();
+        }
+    }
+
+    /// Handle to a pool of `n_threads - 1` workers, the thread calling
+    /// [`ThreadPool::join`] being the last one.
+    #[derive(Clone)]
+    pub struct ThreadPool {
+        workers: Arc<Workers>,
+        n_threads: usize,
+    }
+
+    impl ThreadPool {
+        pub fn new(n_threads: usize) -> Self {
+            let shared = Arc::new(Shared::default());
+            for _ in 1..n_threads {
+                let shared = shared.clone();
+                if std::thread::Builder::new()
+                    .spawn(move || shared.work())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            Self {
+                workers: Arc::new(Workers(shared)),
+                n_threads,
+            }
+        }
+
+        pub fn max_count(&self) -> usize {
+            self.n_threads
+        }
+
+        pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+            let shared = &self.workers.0;
+            let mut queue = shared.lock();
+            queue.jobs.push_back(Box::new(f));
+            queue.pending += 1;
+            shared.work.notify_one();
+        }
+
+        /// Runs the jobs still queued, then waits for the ones the workers
+        /// picked up.
+        pub fn join(&self) {
+            let shared = &self.workers.0;
+            loop {
+                let job = shared.lock().jobs.pop_front();
+                match job {
+                    Some(job) => shared.run(job),
+                    None => break,
+                }
+            }
+            let mut queue = shared.lock();
+            while queue.pending > 0 {
+                queue = shared.done.wait(queue).unwrap();
+            }
+        }
+    }
+}
+
+static POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// Sets the number of threads of the pool used by every product, which
+/// otherwise defaults to [`std::thread::available_parallelism`], usually
+/// unknown to wasm runtimes.
+///
+/// Returns `false` when the pool was already started, by an earlier call or
+/// by a first product.
+///
+/// ```no_run
+/// ggblas::init_thread_pool(4);
+/// ```
+#[cfg(feature = "wasi-threads")]
+pub fn init_thread_pool(threads: usize) -> bool {
+    POOL.set(ThreadPool::new(threads.max(1))).is_ok()
+}
+
+pub(crate) fn global() -> ThreadPool {
+    POOL.get_or_init(|| {
+        let threads = if cfg!(feature = "wasi-threads") {
+            std::thread::available_parallelism().map_or(1, |threads| threads.get())
+        } else {
+            1
+        };
+        ThreadPool::new(threads)
+    })
+    .clone()
+}
+
+#[cfg(test)]
+#[cfg(feature = "wasi-threads")]
+mod tests {
+    use super::ThreadPool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+
+    #[test]
+    fn runs_every_job() {
+        let pool = ThreadPool::new(3);
+        let count = Arc::new(AtomicUsize::new(0));
+        for _ in 0..100 {
+            let count = count.clone();
+            pool.execute(move || {
+                count.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        pool.join();
+        assert_eq!(count.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn runs_jobs_concurrently() {
+        // Deadlocks unless the 4 jobs run at the same time.
+        let pool = ThreadPool::new(4);
+        let barrier = Arc::new(Barrier::new(4));
+        for _ in 0..4 {
+            let barrier = barrier.clone();
+            pool.execute(move || {
+                barrier.wait();
+            });
+        }
+        pool.join();
+    }
 
-impl Once {
-    pub const fn new() -> Self {
-        Self {}
+    #[test]
+    fn matches_single_thread() {
+        let (m, n, k) = (7, 33, 130);
+        let a: Vec<f32> = (0..m * k).map(|i| (i % 7) as f32 - 3.0).collect();
+        let b: Vec<f32> = (0..k * n).map(|i| (i % 5) as f32 - 2.0).collect();
+        let mut expected = vec![0.0; m * n];
+        crate::reference::batched_sgemm_t(&a, &b, &mut expected, m, n, k);
+        // A pool of its own, the global one may already run on one thread.
+        let pool = ThreadPool::new(4);
+        let mut c = vec![0.0; m * n];
+        unsafe {
+            crate::raw::ggml_compute_forward_mul_mat_t(
+                &a,
+                m * k,
+                &b,
+                k * n,
+                &mut c,
+                m * n,
+                m,
+                n,
+                k,
+                1,
+                None,
+                &pool,
+            );
+        }
+        assert_eq!(c, expected);
     }
 
-    pub fn call_once<F: Fn() -> ()>(&self, f: F) -> Self {
-        Self {}
+    #[test]
+    fn init_once() {
+        // Whether or not another test started the pool, it is started now.
+        super::init_thread_pool(4);
+        assert!(!super::init_thread_pool(4));
     }
 }
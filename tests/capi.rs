@@ -0,0 +1,173 @@
+//! Compiles the C programs of `tests/capi` against the cdylib and runs them,
+//! and checks `include/ggblas.h` against the exported functions.
+#![cfg(all(feature = "capi", target_os = "linux"))]
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Builds the cdylib in a target directory of its own, so the programs link
+/// this build and not some `libggblas.so` left in `target/` without `capi`.
+fn library() -> &'static Path {
+    static LIBRARY: OnceLock<PathBuf> = OnceLock::new();
+    LIBRARY.get_or_init(|| {
+        let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cdylib");
+        let status = Command::new(env!("CARGO"))
+            .args([
+                "rustc",
+                "--lib",
+                "--crate-type",
+                "cdylib",
+                "--features",
+                "capi",
+            ])
+            .arg("--target-dir")
+            .arg(&target)
+            .current_dir(root())
+            .status()
+            .unwrap();
+        assert!(status.success(), "building the cdylib failed");
+        target.join("debug/libggblas.so")
+    })
+}
+
+fn compile_and_run(name: &str) {
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+
+    // Linked by path, the library is loaded from that exact path.
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(root().join(format!("tests/capi/{name}.c")))
+        .arg("-I")
+        .arg(root().join("include"))
+        .arg(library())
+        .args(["-lm", "-Wall", "-Werror", "-o"])
+        .arg(&out)
+        .status()
+        .expect("a C compiler is required for this test");
+    assert!(status.success(), "compilation of {name} failed");
+
+    let output = Command::new(&out)
+        .env_remove("LD_LIBRARY_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn cblas() {
+    compile_and_run("cblas_test");
+}
+
+/// A LAPACK-style Cholesky factorization calling the Fortran symbols.
+#[test]
+fn fortran_lapack() {
+    compile_and_run("lapack_test");
+}
+
+/// A C type reduced to what matters to the ABI: the pointer depth, and
+/// whether the pointee is `const`. Enums are `int`s.
+fn c_type(base: &str, is_const: bool, depth: usize) -> String {
+    let base = match base {
+        "c_int" | "CBLAS_LAYOUT" | "CBLAS_TRANSPOSE" | "CBLAS_UPLO" | "CBLAS_DIAG"
+        | "CBLAS_SIDE" => "int",
+        "f32" => "float",
+        "usize" | "CBLAS_INDEX" => "size_t",
+        "c_char" => "char",
+        base => base,
+    };
+    let qualifier = if is_const && depth > 0 { "const " } else { "" };
+    format!("{qualifier}{base}{}", "*".repeat(depth))
+}
+
+/// Return and parameter types of every function.
+type Signatures = BTreeMap<String, (String, Vec<String>)>;
+
+/// The `extern "C" fn`s of a Rust source.
+fn exports(source: &str) -> Signatures {
+    let rust_type = |ty: &str| {
+        let mut ty = ty.trim();
+        let (mut depth, mut is_const) = (0, false);
+        while let Some(pointer) = ty.strip_prefix('*') {
+            let (qualifier, pointee) = pointer.trim_start().split_once(' ').unwrap();
+            is_const = qualifier == "const";
+            depth += 1;
+            ty = pointee.trim_start();
+        }
+        c_type(ty, is_const, depth)
+    };
+    source
+        .split("extern \"C\" fn ")
+        .skip(1)
+        .map(|item| {
+            let (name, rest) = item.split_once('(').unwrap();
+            let (params, rest) = rest.split_once(')').unwrap();
+            let ret = rest[..rest.find('{').unwrap()].trim();
+            let ret = match ret.strip_prefix("->") {
+                Some(ret) => rust_type(ret),
+                None => "void".to_string(),
+            };
+            let params = params
+                .split(',')
+                .filter(|param| !param.trim().is_empty())
+                .map(|param| rust_type(param.split_once(':').unwrap().1))
+                .collect();
+            (name.trim().to_string(), (ret, params))
+        })
+        .collect()
+}
+
+/// The function declarations of a C header.
+fn declarations(header: &str) -> Signatures {
+    let mut code = String::new();
+    let mut rest = header;
+    while let Some(start) = rest.find("/*") {
+        code.push_str(&rest[..start]);
+        rest = &rest[start + rest[start..].find("*/").unwrap() + 2..];
+    }
+    code.push_str(rest);
+    let code: String = code
+        .lines()
+        .filter(|line| !line.starts_with(['#', '}']) && !line.starts_with("extern"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // `const float *A` as `(const float, 1)`, the name being dropped.
+    let c_decl = |decl: &str| {
+        let decl = decl.replace('*', " * ");
+        let mut tokens: Vec<&str> = decl.split_whitespace().collect();
+        if tokens.len() > 1 && tokens.last() != Some(&"*") {
+            tokens.pop();
+        }
+        let depth = tokens.iter().filter(|&&t| t == "*").count();
+        let is_const = tokens.first() == Some(&"const");
+        let base = tokens.iter().find(|&&t| t != "const" && t != "*").unwrap();
+        c_type(base, is_const, depth)
+    };
+    code.split(';')
+        .filter(|item| item.contains('(') && !item.contains("typedef"))
+        .map(|item| {
+            let (ret_name, rest) = item.split_once('(').unwrap();
+            let (ret, name) = ret_name.trim().rsplit_once(' ').unwrap();
+            let params = rest[..rest.rfind(')').unwrap()]
+                .split(',')
+                .map(c_decl)
+                .collect();
+            (name.to_string(), (c_decl(ret.trim()), params))
+        })
+        .collect()
+}
+
+#[test]
+fn header_matches_exports() {
+    let read = |path: &str| std::fs::read_to_string(root().join(path)).unwrap();
+    let mut exported = exports(&read("src/capi.rs"));
+    exported.extend(exports(&read("src/capi/fortran.rs")));
+    assert_eq!(declarations(&read("include/ggblas.h")), exported);
+}
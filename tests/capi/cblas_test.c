@@ -0,0 +1,154 @@
+/* Exercises the CBLAS interface of ggblas from C, compiled and run by tests/capi.rs. */
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "ggblas.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                      \
+    do {                                                                 \
+        if (!(cond)) {                                                   \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            failures++;                                                  \
+        }                                                                \
+    } while (0)
+
+static int close_to(float a, float b) { return fabsf(a - b) <= 1e-4f * fmaxf(1.0f, fabsf(b)); }
+
+/* Naive column major reference for C = alpha * op(A) * op(B) + beta * C. */
+static void reference_sgemm(int ta, int tb, int m, int n, int k, float alpha, const float *a,
+                            int lda, const float *b, int ldb, float beta, float *c, int ldc) {
+    for (int i = 0; i < m; i++) {
+        for (int j = 0; j < n; j++) {
+            float sum = 0.0f;
+            for (int l = 0; l < k; l++) {
+                float av = ta ? a[i * lda + l] : a[l * lda + i];
+                float bv = tb ? b[l * ldb + j] : b[j * ldb + l];
+                sum += av * bv;
+            }
+            c[j * ldc + i] = alpha * sum + beta * c[j * ldc + i];
+        }
+    }
+}
+
+static void test_sgemm(void) {
+    const int m = 17, n = 13, k = 35, ld = 40;
+    float *a = malloc(sizeof(float) * ld * ld);
+    float *b = malloc(sizeof(float) * ld * ld);
+    float *c = malloc(sizeof(float) * ld * ld);
+    float *expected = malloc(sizeof(float) * ld * ld);
+    for (int i = 0; i < ld * ld; i++) {
+        a[i] = (float)(i % 7) - 3.0f;
+        b[i] = (float)(i % 5) - 2.0f;
+    }
+    for (int ta = 0; ta < 2; ta++) {
+        for (int tb = 0; tb < 2; tb++) {
+            for (int i = 0; i < ld * ld; i++) {
+                c[i] = expected[i] = (float)(i % 3);
+            }
+            reference_sgemm(ta, tb, m, n, k, 1.5f, a, ld, b, ld, -1.0f, expected, ld);
+            cblas_sgemm(CblasColMajor, ta ? CblasTrans : CblasNoTrans,
+                        tb ? CblasTrans : CblasNoTrans, m, n, k, 1.5f, a, ld, b, ld, -1.0f, c,
+                        ld);
+            for (int i = 0; i < ld * ld; i++) {
+                CHECK(close_to(c[i], expected[i]));
+            }
+        }
+    }
+
+    /* Row major C = A * B is column major C.T = B.T * A.T. */
+    for (int i = 0; i < ld * ld; i++) {
+        c[i] = expected[i] = 0.0f;
+    }
+    reference_sgemm(0, 0, n, m, k, 1.0f, b, ld, a, ld, 0.0f, expected, ld);
+    cblas_sgemm(CblasRowMajor, CblasNoTrans, CblasNoTrans, m, n, k, 1.0f, a, ld, b, ld, 0.0f, c,
+                ld);
+    for (int i = 0; i < ld * ld; i++) {
+        CHECK(close_to(c[i], expected[i]));
+    }
+
+    /* Batched versions agree with individual calls. */
+    float *c_batch = malloc(sizeof(float) * ld * ld);
+    for (int i = 0; i < ld * ld; i++) {
+        c[i] = c_batch[i] = 1.0f;
+    }
+    const int stride = 4 * 4;
+    for (int p = 0; p < 3; p++) {
+        cblas_sgemm(CblasRowMajor, CblasNoTrans, CblasTrans, 4, 4, 4, 1.0f, a + p * stride, 4,
+                    b + p * stride, 4, 2.0f, c + p * stride, 4);
+    }
+    cblas_sgemm_batch_strided(CblasRowMajor, CblasNoTrans, CblasTrans, 4, 4, 4, 1.0f, a, 4,
+                              stride, b, 4, stride, 2.0f, c_batch, 4, stride, 3);
+    for (int i = 0; i < ld * ld; i++) {
+        CHECK(close_to(c_batch[i], c[i]));
+    }
+
+    for (int i = 0; i < ld * ld; i++) {
+        c_batch[i] = 1.0f;
+    }
+    CBLAS_TRANSPOSE ta = CblasNoTrans, tb = CblasTrans;
+    int four = 4, three = 3;
+    float alpha = 1.0f, beta = 2.0f;
+    const float *as[3] = {a, a + stride, a + 2 * stride};
+    const float *bs[3] = {b, b + stride, b + 2 * stride};
+    float *cs[3] = {c_batch, c_batch + stride, c_batch + 2 * stride};
+    cblas_sgemm_batch(CblasRowMajor, &ta, &tb, &four, &four, &four, &alpha, as, &four, bs, &four,
+                      &beta, cs, &four, 1, &three);
+    for (int i = 0; i < ld * ld; i++) {
+        CHECK(close_to(c_batch[i], c[i]));
+    }
+
+    free(a);
+    free(b);
+    free(c);
+    free(c_batch);
+    free(expected);
+}
+
+static void test_level1(void) {
+    float x[5] = {1.0f, -2.0f, 3.0f, -4.0f, 5.0f};
+    float y[5] = {1.0f, 1.0f, 1.0f, 1.0f, 1.0f};
+    CHECK(cblas_sdot(5, x, 1, y, 1) == 3.0f);
+    CHECK(cblas_sdot(3, x, 2, y, 1) == 9.0f);
+    CHECK(cblas_sasum(5, x, 1) == 15.0f);
+    CHECK(cblas_isamax(5, x, 1) == 4);
+    CHECK(close_to(cblas_snrm2(2, x + 2, 1), 5.0f));
+    cblas_saxpy(5, 2.0f, x, 1, y, 1);
+    CHECK(y[0] == 3.0f && y[1] == -3.0f && y[4] == 11.0f);
+    cblas_sscal(5, 0.5f, y, 1);
+    CHECK(y[0] == 1.5f && y[4] == 5.5f);
+    cblas_scopy(5, x, 1, y, -1);
+    CHECK(y[0] == 5.0f && y[4] == 1.0f);
+}
+
+static void test_sgemv_strsm(void) {
+    /* A = [[2, 1], [0, 4]] stored column major with lda = 3. */
+    float a[6] = {2.0f, 0.0f, -1.0f, 1.0f, 4.0f, -1.0f};
+    float x[2] = {1.0f, 1.0f};
+    float y[2] = {0.0f, 0.0f};
+    cblas_sgemv(CblasColMajor, CblasNoTrans, 2, 2, 1.0f, a, 3, x, 1, 0.0f, y, 1);
+    CHECK(y[0] == 3.0f && y[1] == 4.0f);
+    cblas_sgemv(CblasColMajor, CblasTrans, 2, 2, 1.0f, a, 3, x, 1, 0.0f, y, 1);
+    CHECK(y[0] == 2.0f && y[1] == 5.0f);
+
+    /* Solve A * X = 2 * B with B = A. */
+    float b[4] = {2.0f, 0.0f, 1.0f, 4.0f};
+    cblas_strsm(CblasColMajor, CblasLeft, CblasUpper, CblasNoTrans, CblasNonUnit, 2, 2, 2.0f, a,
+                3, b, 2);
+    CHECK(close_to(b[0], 2.0f) && close_to(b[1], 0.0f) && close_to(b[2], 0.0f) &&
+          close_to(b[3], 2.0f));
+}
+
+int main(void) {
+    test_sgemm();
+    test_level1();
+    test_sgemv_strsm();
+    if (failures) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("ok\n");
+    return 0;
+}
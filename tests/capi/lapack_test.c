@@ -0,0 +1,125 @@
+/*
+ * Blocked Cholesky factorization written like LAPACK's spotrf/spotf2, with
+ * every BLAS call going through the Fortran symbols of ggblas. Compiled and
+ * run by tests/capi.rs.
+ */
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "ggblas.h"
+
+#define A(i, j) a[(i) + (j) * (size_t)lda]
+
+/* Unblocked lower Cholesky of the n x n matrix at a, as in spotf2. */
+static int spotf2(int n, float *a, int lda) {
+    const int one = 1;
+    const float minus_one = -1.0f, plus_one = 1.0f;
+    for (int j = 0; j < n; j++) {
+        float ajj = A(j, j) - cblas_sdot(j, &A(j, 0), lda, &A(j, 0), lda);
+        if (ajj <= 0.0f) {
+            return j + 1;
+        }
+        ajj = sqrtf(ajj);
+        A(j, j) = ajj;
+        if (j < n - 1) {
+            int rows = n - j - 1;
+            sgemv_("N", &rows, &j, &minus_one, &A(j + 1, 0), &lda, &A(j, 0), &lda, &plus_one,
+                   &A(j + 1, j), &one);
+            cblas_sscal(rows, 1.0f / ajj, &A(j + 1, j), 1);
+        }
+    }
+    return 0;
+}
+
+/* Blocked lower Cholesky, as in spotrf. */
+static int spotrf(int n, float *a, int lda, int nb) {
+    const float minus_one = -1.0f, plus_one = 1.0f;
+    for (int j = 0; j < n; j += nb) {
+        int jb = nb < n - j ? nb : n - j;
+        /* Update the diagonal block with the already factored columns. */
+        sgemm_("N", "T", &jb, &jb, &j, &minus_one, &A(j, 0), &lda, &A(j, 0), &lda, &plus_one,
+               &A(j, j), &lda);
+        int info = spotf2(jb, &A(j, j), lda);
+        if (info) {
+            return info + j;
+        }
+        if (j + jb < n) {
+            int rows = n - j - jb;
+            sgemm_("N", "T", &rows, &jb, &j, &minus_one, &A(j + jb, 0), &lda, &A(j, 0), &lda,
+                   &plus_one, &A(j + jb, j), &lda);
+            strsm_("R", "L", "T", "N", &rows, &jb, &plus_one, &A(j, j), &lda, &A(j + jb, j),
+                   &lda);
+        }
+    }
+    return 0;
+}
+
+int main(void) {
+    const int n = 45, lda = 50, nb = 8;
+    float *a = calloc((size_t)lda * n, sizeof(float));
+    float *m = calloc((size_t)n * n, sizeof(float));
+    /* A = M * M.T + n * I is symmetric positive definite. */
+    for (int i = 0; i < n * n; i++) {
+        m[i] = (float)((i * 7919) % 61) / 61.0f - 0.5f;
+    }
+    for (int i = 0; i < n; i++) {
+        for (int j = 0; j < n; j++) {
+            float sum = i == j ? (float)n : 0.0f;
+            for (int l = 0; l < n; l++) {
+                sum += m[i + l * n] * m[j + l * n];
+            }
+            A(i, j) = sum;
+        }
+    }
+    float *a0 = malloc(sizeof(float) * lda * n);
+    for (int i = 0; i < lda * n; i++) {
+        a0[i] = a[i];
+    }
+
+    int info = spotrf(n, a, lda, nb);
+    if (info) {
+        fprintf(stderr, "spotrf failed at %d\n", info);
+        return 1;
+    }
+
+    /* L * L.T must give back A. */
+    float max_err = 0.0f;
+    for (int i = 0; i < n; i++) {
+        for (int j = 0; j <= i; j++) {
+            float sum = 0.0f;
+            for (int l = 0; l <= j; l++) {
+                sum += A(i, l) * A(j, l);
+            }
+            float err = fabsf(sum - a0[i + j * lda]);
+            max_err = err > max_err ? err : max_err;
+        }
+    }
+    if (max_err > 1e-3f) {
+        fprintf(stderr, "L * L.T differs from A by %g\n", max_err);
+        return 1;
+    }
+
+    /* Solve A * x = b with the two triangular solves of spotrs. */
+    float x[45], b[45];
+    for (int i = 0; i < n; i++) {
+        x[i] = (float)(i % 5) - 2.0f;
+    }
+    const int one = 1;
+    const float plus_one = 1.0f, zero = 0.0f;
+    sgemv_("N", &n, &n, &plus_one, a0, &lda, x, &one, &zero, b, &one);
+    strsm_("L", "L", "N", "N", &n, &one, &plus_one, a, &lda, b, &n);
+    strsm_("L", "L", "T", "N", &n, &one, &plus_one, a, &lda, b, &n);
+    for (int i = 0; i < n; i++) {
+        if (fabsf(b[i] - x[i]) > 1e-3f) {
+            fprintf(stderr, "x[%d] = %g instead of %g\n", i, b[i], x[i]);
+            return 1;
+        }
+    }
+
+    free(a);
+    free(a0);
+    free(m);
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,170 @@
+//! Small shapes through every safe entry point, meant to run under Miri.
+//!
+//! ```bash
+//! RUSTFLAGS="" MIRIFLAGS="-Zmiri-permissive-provenance -Zmiri-ignore-leaks" \
+//!     cargo +nightly miri test --test miri
+//! ```
+//!
+//! Miri does not run the SIMD intrinsics, the crate builds its scalar
+//! fallbacks there instead. The shapes are kept tiny for the interpreter
+//! while still crossing the 32 elements block of those fallbacks, and the
+//! malformed calls must panic before any kernel runs.
+use ggblas::complex::{batched_cgemm, batched_cgemm_t, Complex32, Conj};
+use ggblas::int8::{gemm_i8, gemm_i8_t};
+use ggblas::quantized::{batched_sgemm_t_quantized, BlockQ8_0, GgmlType};
+use ggblas::{
+    batched_sgemm, batched_sgemm_accuracy, batched_sgemm_epilogue, batched_sgemm_t,
+    batched_sgemm_t_accuracy, batched_sgemm_t_epilogue, reference, sasum, saxpy, sdot, sger, ssymm,
+    ssymv, ssyrk, strsv, Accuracy, Diag, Epilogue, Side, Uplo,
+};
+
+const SHAPES: &[(usize, usize, usize, usize)] = &[(1, 1, 1, 1), (2, 3, 2, 5), (1, 2, 3, 37)];
+
+fn data(len: usize, seed: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| ((i * 7 + seed) % 11) as f32 - 5.0)
+        .collect()
+}
+
+#[test]
+fn gemm_matches_reference() {
+    for &(batch, m, n, k) in SHAPES {
+        let a = data(batch * m * k, 1);
+        let b = data(batch * k * n, 2);
+        let mut expected = vec![0.0; batch * m * n];
+        let mut c = vec![f32::NAN; batch * m * n];
+
+        reference::batched_sgemm(&a, &b, &mut expected, m, n, k);
+        batched_sgemm(&a, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+        batched_sgemm_epilogue(&a, &b, &mut c, m, n, k, &Epilogue::default());
+        assert_eq!(c, expected);
+        batched_sgemm_accuracy(Accuracy::Compensated, &a, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+
+        reference::batched_sgemm_t(&a, &b, &mut expected, m, n, k);
+        batched_sgemm_t(&a, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+        batched_sgemm_t_epilogue(&a, &b, &mut c, m, n, k, &Epilogue::default());
+        assert_eq!(c, expected);
+        batched_sgemm_t_accuracy(Accuracy::Deterministic, &a, &b, &mut c, m, n, k);
+        assert_eq!(c, expected);
+    }
+}
+
+#[test]
+fn other_types() {
+    let (m, n, k) = (2, 3, 37);
+    let a: Vec<i8> = (0..m * k).map(|i| (i % 7) as i8 - 3).collect();
+    let b: Vec<i8> = (0..k * n).map(|i| (i % 5) as i8 - 2).collect();
+    let mut c = vec![0; m * n];
+    gemm_i8_t(&a, &b, &mut c, m, n, k);
+    let expected: Vec<i32> = (0..m * n)
+        .map(|i| {
+            let (i, j) = (i / n, i % n);
+            (0..k)
+                .map(|l| a[i * k + l] as i32 * b[j * k + l] as i32)
+                .sum()
+        })
+        .collect();
+    assert_eq!(c, expected);
+    gemm_i8(&a, &b, &mut c, m, n, k);
+
+    let a: Vec<_> = data(m * k, 3)
+        .into_iter()
+        .map(|x| Complex32::new(x, -x))
+        .collect();
+    let b: Vec<_> = data(k * n, 4)
+        .into_iter()
+        .map(|x| Complex32::new(1.0, x))
+        .collect();
+    let mut c = vec![Complex32::default(); m * n];
+    batched_cgemm_t(Conj::No, Conj::Yes, &a, &b, &mut c, m, n, k);
+    batched_cgemm(Conj::Yes, Conj::No, &a, &b, &mut c, m, n, k);
+
+    let k = 2 * BlockQ8_0::BLCK_SIZE;
+    let a = data(m * k, 5);
+    let b = data(n * k, 6);
+    let mut b_q = vec![BlockQ8_0::zeros(); n * k / BlockQ8_0::BLCK_SIZE];
+    BlockQ8_0::from_float(&b, &mut b_q);
+    let mut c = vec![0.0; m * n];
+    batched_sgemm_t_quantized(&a, &b_q, &mut c, m, n, k);
+}
+
+#[test]
+fn blas_routines() {
+    let x = data(9, 1);
+    let mut y = data(5, 2);
+    assert_eq!(
+        sdot(3, &x, 4, &y, 2),
+        x[0] * y[0] + x[4] * y[2] + x[8] * y[4]
+    );
+    saxpy(5, 2.0, &x, 2, &mut y, 1);
+    assert_eq!(sasum(0, &[], 1), 0.0);
+
+    let n = 3;
+    let mut a = vec![0.0; n * n];
+    sger(1.0, &x[..n], &x[..n], &mut a, n, n);
+    let mut y = vec![0.0; n];
+    ssymv(Uplo::Upper, 1.0, &a, &x[..n], 0.0, &mut y, n);
+    let mut y = x[..n].to_vec();
+    a.iter_mut().step_by(n + 1).for_each(|d| *d = 1.0);
+    strsv(Uplo::Lower, Diag::NonUnit, &a, &mut y, n);
+
+    let mut c = vec![0.0; n * n];
+    ssyrk(Uplo::Upper, &x, &mut c, n, 3);
+}
+
+#[test]
+fn degenerate_shapes() {
+    // No reduction: C is all zeros.
+    let mut c = vec![f32::NAN; 6];
+    batched_sgemm(&[], &[], &mut c, 2, 3, 0);
+    assert_eq!(c, [0.0; 6]);
+    let mut c = vec![f32::NAN; 6];
+    batched_sgemm_t(&[], &[], &mut c, 2, 3, 0);
+    assert_eq!(c, [0.0; 6]);
+    let mut c = vec![f32::NAN; 6];
+    reference::batched_sgemm(&[], &[], &mut c, 2, 3, 0);
+    assert_eq!(c, [0.0; 6]);
+
+    // Empty outputs.
+    batched_sgemm(&[], &data(12, 0), &mut [], 0, 3, 4);
+    batched_sgemm_t(&data(8, 0), &[], &mut [], 2, 0, 4);
+    reference::batched_sgemm_t(&data(8, 0), &[], &mut [], 2, 0, 4);
+    gemm_i8_t(&[], &[], &mut [], 0, 0, 4);
+    batched_cgemm(Conj::No, Conj::No, &[], &[], &mut [], 0, 0, 0);
+    ssymm(Side::Left, Uplo::Upper, &[], &[], &mut [], 0, 3);
+}
+
+#[test]
+#[should_panic(expected = "B holds 5 elements")]
+fn short_operand() {
+    batched_sgemm(&[0.0; 6], &[0.0; 5], &mut [0.0; 4], 2, 2, 3);
+}
+
+#[test]
+#[should_panic(expected = "C holds 4 elements")]
+fn mismatched_batches() {
+    batched_sgemm_t(&[0.0; 12], &[0.0; 12], &mut [0.0; 4], 2, 2, 3);
+}
+
+#[test]
+#[should_panic(expected = "overflows")]
+fn overflowing_dimensions() {
+    // m * k wraps around to 0 when computed without overflow checks.
+    let half = 1 << (usize::BITS / 2);
+    batched_sgemm(&[], &[], &mut [], half, half, half);
+}
+
+#[test]
+#[should_panic(expected = "don't fit")]
+fn overflowing_stride() {
+    sdot(3, &[0.0; 2], usize::MAX / 2 + 1, &[0.0; 3], 1);
+}
+
+#[test]
+#[should_panic]
+fn short_int8() {
+    gemm_i8(&[0; 6], &[0; 6], &mut [0; 3], 2, 2, 3);
+}
@@ -0,0 +1,763 @@
+//! Differential tests of the public entry points against `ggblas::reference`.
+//!
+//! Shapes are drawn well past the SIMD step so that both the vectorized
+//! loops and their leftovers get exercised, with a few batches and thread
+//! counts, and now and then an empty dimension. Results must stay within
+//! the usual error bound of a dot product of length `k`,
+//! `|err| <= k * u * sum(|a| * |b|)`, plus a few ULPs for the final rounding.
+//!
+//! The adapters get their own properties with the `ndarray`, `nalgebra` and
+//! `faer` features.
+use ggblas::backend;
+#[cfg(not(target_arch = "wasm32"))]
+use ggblas::backend::{Backend, GgmlPool};
+use ggblas::complex::{Complex32, Complex64, Conj};
+use ggblas::quantized::{BlockQ4_0, BlockQ8_0, GgmlType};
+use ggblas::{reference, Bias, Diag, Epilogue, Layout, Side, Uplo};
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy)]
+struct Problem {
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    seed: u64,
+}
+
+/// A dimension, now and then empty.
+fn dim(max: usize) -> impl Strategy<Value = usize> {
+    prop_oneof![1 => Just(0), 9 => 1..=max]
+}
+
+fn problem() -> impl Strategy<Value = Problem> {
+    (1..=3usize, dim(33), dim(33), dim(150), any::<u64>()).prop_map(|(batch, m, n, k, seed)| {
+        Problem {
+            batch,
+            m,
+            n,
+            k,
+            seed,
+        }
+    })
+}
+
+/// Values in `[-4, 4)` spread over a few binades.
+fn data(len: usize, seed: u64) -> Vec<f32> {
+    let mut state = seed | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let mantissa = (state >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0;
+            let exponent = (state % 6) as i32 - 3;
+            mantissa * 2f32.powi(exponent)
+        })
+        .collect()
+}
+
+fn abs(x: &[f32]) -> Vec<f32> {
+    x.iter().map(|v| v.abs()).collect()
+}
+
+/// Transposes every `(rows, cols)` matrix of a batch.
+fn transpose(x: &[f32], rows: usize, cols: usize) -> Vec<f32> {
+    let mut t = vec![0.0; x.len()];
+    if t.is_empty() {
+        return t;
+    }
+    for (x, t) in x.chunks(rows * cols).zip(t.chunks_mut(rows * cols)) {
+        for i in 0..rows {
+            for j in 0..cols {
+                t[j * rows + i] = x[i * cols + j];
+            }
+        }
+    }
+    t
+}
+
+struct Case {
+    p: Problem,
+    a: Vec<f32>,
+    b: Vec<f32>,
+    b_t: Vec<f32>,
+    expected: Vec<f32>,
+    /// `sum(|a| * |b|)` for every output element.
+    magnitude: Vec<f32>,
+}
+
+impl Case {
+    fn new(p: Problem) -> Self {
+        let Problem {
+            batch,
+            m,
+            n,
+            k,
+            seed,
+            ..
+        } = p;
+        let a = data(batch * m * k, seed);
+        let b = data(batch * k * n, seed.wrapping_mul(31).wrapping_add(7));
+        let b_t = transpose(&b, k, n);
+        let mut expected = vec![0.0; batch * m * n];
+        reference::batched_sgemm(&a, &b, &mut expected, m, n, k);
+        let mut expected_t = vec![0.0; batch * m * n];
+        reference::batched_sgemm_t(&a, &b_t, &mut expected_t, m, n, k);
+        assert_eq!(expected, expected_t);
+        let mut magnitude = vec![0.0; batch * m * n];
+        reference::batched_sgemm(&abs(&a), &abs(&b), &mut magnitude, m, n, k);
+        Self {
+            p,
+            a,
+            b,
+            b_t,
+            expected,
+            magnitude,
+        }
+    }
+
+    fn check(&self, name: &str, got: &[f32]) -> Result<(), TestCaseError> {
+        check(
+            name,
+            got,
+            &self.expected,
+            &self.magnitude,
+            self.p.k,
+            f32::EPSILON,
+        )
+    }
+
+    /// An output buffer, which is never read.
+    fn c(&self) -> Vec<f32> {
+        vec![f32::NAN; self.expected.len()]
+    }
+}
+
+/// `u` is the unit roundoff of the accumulation and output type.
+fn check(
+    name: &str,
+    got: &[f32],
+    expected: &[f32],
+    magnitude: &[f32],
+    k: usize,
+    u: f32,
+) -> Result<(), TestCaseError> {
+    prop_assert_eq!(got.len(), expected.len());
+    for (i, ((&got, &expected), &magnitude)) in got.iter().zip(expected).zip(magnitude).enumerate()
+    {
+        let bound = (k + 2) as f32 * u * magnitude + 2.0 * u * expected.abs() + f32::MIN_POSITIVE;
+        prop_assert!(
+            (got - expected).abs() <= bound,
+            "{name}[{i}]: {got} != {expected} (bound {bound})"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn pool(threads: usize) -> &'static GgmlPool {
+    static POOLS: OnceLock<Vec<GgmlPool>> = OnceLock::new();
+    &POOLS.get_or_init(|| (1..=4).map(GgmlPool::new).collect())[threads - 1]
+}
+
+fn conj() -> impl Strategy<Value = Conj> {
+    prop_oneof![Just(Conj::No), Just(Conj::Yes)]
+}
+
+fn side() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Left), Just(Side::Right)]
+}
+
+fn uplo() -> impl Strategy<Value = Uplo> {
+    prop_oneof![Just(Uplo::Upper), Just(Uplo::Lower)]
+}
+
+fn diag() -> impl Strategy<Value = Diag> {
+    prop_oneof![Just(Diag::Unit), Just(Diag::NonUnit)]
+}
+
+fn complex(len: usize, seed: u64) -> Vec<Complex32> {
+    data(2 * len, seed)
+        .chunks(2)
+        .map(|x| Complex32::new(x[0], x[1]))
+        .collect()
+}
+
+/// `op(A) * op(B)` in double precision, with `B` laid out as `(k, n)` or,
+/// for `b_t`, as `(n, k)`.
+#[allow(clippy::too_many_arguments)]
+fn complex_reference(
+    conj_a: Conj,
+    conj_b: Conj,
+    a: &[Complex32],
+    b: &[Complex32],
+    b_t: bool,
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+) -> Vec<Complex64> {
+    let op = |conj: Conj, x: Complex32| {
+        let x = Complex64::new(x.re.into(), x.im.into());
+        match conj {
+            Conj::No => x,
+            Conj::Yes => x.conj(),
+        }
+    };
+    let mut c = vec![Complex64::default(); batch * m * n];
+    for s in 0..batch {
+        for i in 0..m {
+            for j in 0..n {
+                c[(s * m + i) * n + j] = (0..k)
+                    .map(|l| {
+                        let b = if b_t {
+                            b[(s * n + j) * k + l]
+                        } else {
+                            b[(s * k + l) * n + j]
+                        };
+                        op(conj_a, a[(s * m + i) * k + l]) * op(conj_b, b)
+                    })
+                    .sum();
+            }
+        }
+    }
+    c
+}
+
+/// Checks the real and imaginary parts, each bounded by the moduli of the
+/// products.
+fn check_complex(
+    name: &str,
+    got: &[Complex32],
+    expected: &[Complex64],
+    magnitude: &[f32],
+    k: usize,
+) -> Result<(), TestCaseError> {
+    let parts = |c: &mut dyn Iterator<Item = (f32, f32)>| -> Vec<f32> {
+        c.flat_map(|(re, im)| [re, im]).collect()
+    };
+    let got = parts(&mut got.iter().map(|c| (c.re, c.im)));
+    let expected = parts(&mut expected.iter().map(|c| (c.re as f32, c.im as f32)));
+    let magnitude = parts(&mut magnitude.iter().map(|&m| (m, m)));
+    // Each product rounds twice before the sum.
+    check(name, &got, &expected, &magnitude, k + 2, 2.0 * f32::EPSILON)
+}
+
+/// Rounds `x` through the quantization format `T`.
+fn quantize<T: GgmlType>(x: &[f32]) -> Vec<f32> {
+    let mut q = vec![T::zeros(); x.len() / T::BLCK_SIZE];
+    T::from_float(x, &mut q);
+    let mut y = vec![0.0; x.len()];
+    T::to_float(&q, &mut y);
+    y
+}
+
+/// `A * B.T` against weights in `T`, compared with the product of the
+/// rounded operands: the integer dot products are exact, only the scaling
+/// and the sums of the blocks round.
+fn quantized<T: GgmlType>(p: Problem) -> Result<(), TestCaseError> {
+    let Problem {
+        batch,
+        m,
+        n,
+        k,
+        seed,
+    } = p;
+    let k = (k / T::BLCK_SIZE + 1) * T::BLCK_SIZE;
+    let a = data(batch * m * k, seed);
+    let b = data(batch * n * k, seed ^ 5);
+    let mut b_q = vec![T::zeros(); b.len() / T::BLCK_SIZE];
+    T::from_float(&b, &mut b_q);
+
+    let a_r = quantize::<T::VecDotType>(&a);
+    let b_r = quantize::<T>(&b);
+    let mut expected = vec![0.0; batch * m * n];
+    reference::batched_sgemm_t(&a_r, &b_r, &mut expected, m, n, k);
+    let mut magnitude = vec![0.0; batch * m * n];
+    reference::batched_sgemm_t(&abs(&a_r), &abs(&b_r), &mut magnitude, m, n, k);
+
+    let mut c = vec![f32::NAN; batch * m * n];
+    ggblas::quantized::batched_sgemm_t_quantized(&a, &b_q, &mut c, m, n, k);
+    check(
+        std::any::type_name::<T>(),
+        &c,
+        &expected,
+        &magnitude,
+        k / T::BLCK_SIZE,
+        f32::EPSILON,
+    )
+}
+
+fn stored(uplo: Uplo, i: usize, j: usize) -> bool {
+    match uplo {
+        Uplo::Upper => j >= i,
+        Uplo::Lower => j <= i,
+    }
+}
+
+/// The `uplo` triangle of every `(dim, dim)` matrix of `x`, the other
+/// entries (and the diagonal for [`Diag::Unit`]) set to NaN as they must
+/// never be read.
+fn triangle(x: &[f32], dim: usize, uplo: Uplo, diag: Diag) -> Vec<f32> {
+    let mut t = x.to_vec();
+    if t.is_empty() {
+        return t;
+    }
+    for t in t.chunks_mut(dim * dim) {
+        for i in 0..dim {
+            for j in 0..dim {
+                if !stored(uplo, i, j) || (i == j && diag == Diag::Unit) {
+                    t[i * dim + j] = f32::NAN;
+                }
+            }
+        }
+    }
+    t
+}
+
+/// The full matrices a `triangle` stands for, mirrored when `symmetric`.
+fn full(t: &[f32], dim: usize, uplo: Uplo, diag: Diag, symmetric: bool) -> Vec<f32> {
+    let mut f = t.to_vec();
+    if f.is_empty() {
+        return f;
+    }
+    for f in f.chunks_mut(dim * dim) {
+        for i in 0..dim {
+            for j in 0..dim {
+                f[i * dim + j] = if i == j && diag == Diag::Unit {
+                    1.0
+                } else if stored(uplo, i, j) {
+                    f[i * dim + j]
+                } else if symmetric {
+                    f[j * dim + i]
+                } else {
+                    0.0
+                };
+            }
+        }
+    }
+    f
+}
+
+/// `A * B` for [`Side::Left`], `B * A` for [`Side::Right`], `A` being a
+/// `(dim, dim)` matrix with `dim` the reduction length, and the bound of
+/// each output.
+fn side_product(side: Side, a: &[f32], b: &[f32], m: usize, n: usize) -> (Vec<f32>, Vec<f32>) {
+    let mut c = vec![0.0; b.len()];
+    let mut magnitude = vec![0.0; b.len()];
+    match side {
+        Side::Left => {
+            reference::batched_sgemm(a, b, &mut c, m, n, m);
+            reference::batched_sgemm(&abs(a), &abs(b), &mut magnitude, m, n, m);
+        }
+        Side::Right => {
+            reference::batched_sgemm(b, a, &mut c, m, n, n);
+            reference::batched_sgemm(&abs(b), &abs(a), &mut magnitude, m, n, n);
+        }
+    }
+    (c, magnitude)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn sgemm(p in problem()) {
+        let case = Case::new(p);
+        let Problem { m, n, k, .. } = p;
+
+        let mut c = case.c();
+        ggblas::batched_sgemm(&case.a, &case.b, &mut c, m, n, k);
+        case.check("batched_sgemm", &c)?;
+
+        let mut c = case.c();
+        ggblas::batched_sgemm_t(&case.a, &case.b_t, &mut c, m, n, k);
+        case.check("batched_sgemm_t", &c)?;
+    }
+
+    #[test]
+    fn layouts(p in problem()) {
+        let case = Case::new(p);
+        let Problem { m, n, k, .. } = p;
+        let a_col = transpose(&case.a, m, k);
+        let b_col = transpose(&case.b, k, n);
+        let b_t_col = transpose(&case.b_t, n, k);
+        for layout in [Layout::RowMajor, Layout::ColMajor] {
+            let (a, b, b_t) = match layout {
+                Layout::RowMajor => (&case.a, &case.b, &case.b_t),
+                Layout::ColMajor => (&a_col, &b_col, &b_t_col),
+            };
+            let to_row = |c: Vec<f32>| match layout {
+                Layout::RowMajor => c,
+                Layout::ColMajor => transpose(&c, n, m),
+            };
+
+            let mut c = case.c();
+            ggblas::batched_sgemm_layout(layout, a, b, &mut c, m, n, k);
+            case.check(&format!("batched_sgemm_layout({layout:?})"), &to_row(c))?;
+
+            let mut c = case.c();
+            ggblas::batched_sgemm_t_layout(layout, a, b_t, &mut c, m, n, k);
+            case.check(&format!("batched_sgemm_t_layout({layout:?})"), &to_row(c))?;
+        }
+    }
+
+    #[test]
+    fn epilogues(p in problem(), alpha in -2.0f32..2.0) {
+        let case = Case::new(p);
+        let Problem { m, n, k, seed, .. } = p;
+        let bias = data(n, seed ^ 1);
+        let residual = data(case.expected.len(), seed ^ 2);
+        let epilogue = Epilogue {
+            alpha,
+            bias: Some(Bias::N(&bias)),
+            residual: Some(&residual),
+            ..Default::default()
+        };
+        let mut expected = case.expected.clone();
+        let mut magnitude = case.magnitude.clone();
+        for (idx, (e, mag)) in expected.iter_mut().zip(&mut magnitude).enumerate() {
+            let j = idx % n;
+            *e = alpha * *e + bias[j] + residual[idx];
+            *mag = alpha.abs() * *mag + bias[j].abs() + residual[idx].abs();
+        }
+
+        let mut c = case.c();
+        ggblas::batched_sgemm_epilogue(&case.a, &case.b, &mut c, m, n, k, &epilogue);
+        check("batched_sgemm_epilogue", &c, &expected, &magnitude, k, f32::EPSILON)?;
+
+        let mut c = case.c();
+        ggblas::batched_sgemm_t_epilogue(&case.a, &case.b_t, &mut c, m, n, k, &epilogue);
+        check("batched_sgemm_t_epilogue", &c, &expected, &magnitude, k, f32::EPSILON)?;
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn threads(p in problem(), threads in 1..=4usize) {
+        let case = Case::new(p);
+        let Problem { m, n, k, .. } = p;
+        let pool = pool(threads);
+
+        let mut c = vec![f32::NAN; case.expected.len()];
+        pool.batched_sgemm(&case.a, &case.b, &mut c, m, n, k);
+        case.check(pool.name(), &c)?;
+
+        let mut c = vec![f32::NAN; case.expected.len()];
+        pool.batched_sgemm_t(&case.a, &case.b_t, &mut c, m, n, k);
+        case.check(pool.name(), &c)?;
+    }
+
+    #[test]
+    fn backends(p in problem()) {
+        let case = Case::new(p);
+        let Problem { m, n, k, .. } = p;
+        for backend in backend::available() {
+            let mut c = vec![f32::NAN; case.expected.len()];
+            backend.batched_sgemm(&case.a, &case.b, &mut c, m, n, k);
+            case.check(backend.name(), &c)?;
+
+            let mut c = vec![f32::NAN; case.expected.len()];
+            backend.batched_sgemm_t(&case.a, &case.b_t, &mut c, m, n, k);
+            case.check(backend.name(), &c)?;
+        }
+    }
+
+    #[test]
+    fn int8(p in problem()) {
+        let Problem { batch, m, n, k, seed } = p;
+        let to_i8 = |x: Vec<f32>| -> Vec<i8> { x.iter().map(|v| (v * 32.0) as i8).collect() };
+        let a = to_i8(data(batch * m * k, seed));
+        let b = to_i8(data(batch * k * n, seed ^ 3));
+        let b_t: Vec<i8> = {
+            let b: Vec<f32> = b.iter().map(|&v| v as f32).collect();
+            transpose(&b, k, n).iter().map(|&v| v as i8).collect()
+        };
+        let mut expected = vec![0; batch * m * n];
+        for s in 0..batch {
+            for i in 0..m {
+                for j in 0..n {
+                    expected[(s * m + i) * n + j] = (0..k)
+                        .map(|l| a[(s * m + i) * k + l] as i32 * b[(s * k + l) * n + j] as i32)
+                        .sum::<i32>();
+                }
+            }
+        }
+
+        let mut c = vec![0; expected.len()];
+        ggblas::int8::gemm_i8(&a, &b, &mut c, m, n, k);
+        prop_assert_eq!(&c, &expected);
+
+        let mut c = vec![0; expected.len()];
+        ggblas::int8::gemm_i8_t(&a, &b_t, &mut c, m, n, k);
+        prop_assert_eq!(&c, &expected);
+    }
+    #[test]
+    fn cgemm(p in problem(), conj_a in conj(), conj_b in conj()) {
+        let Problem { batch, m, n, k, seed } = p;
+        let a = complex(batch * m * k, seed);
+        let b = complex(batch * k * n, seed ^ 4);
+        let magnitude = {
+            let a: Vec<f32> = a.iter().map(|x| x.norm()).collect();
+            let b: Vec<f32> = b.iter().map(|x| x.norm()).collect();
+            let mut magnitude = vec![0.0; batch * m * n];
+            reference::batched_sgemm(&a, &b, &mut magnitude, m, n, k);
+            magnitude
+        };
+        let expected = complex_reference(conj_a, conj_b, &a, &b, false, batch, m, n, k);
+        let mut c = vec![Complex32::new(f32::NAN, f32::NAN); batch * m * n];
+        ggblas::complex::batched_cgemm(conj_a, conj_b, &a, &b, &mut c, m, n, k);
+        check_complex("batched_cgemm", &c, &expected, &magnitude, k)?;
+
+        // The same products with `B` laid out as `(n, k)`.
+        let b_t: Vec<Complex32> = {
+            let re: Vec<f32> = b.iter().map(|x| x.re).collect();
+            let im: Vec<f32> = b.iter().map(|x| x.im).collect();
+            transpose(&re, k, n)
+                .into_iter()
+                .zip(transpose(&im, k, n))
+                .map(|(re, im)| Complex32::new(re, im))
+                .collect()
+        };
+        let expected_t = complex_reference(conj_a, conj_b, &a, &b_t, true, batch, m, n, k);
+        prop_assert_eq!(&expected_t, &expected);
+        let mut c = vec![Complex32::new(f32::NAN, f32::NAN); batch * m * n];
+        ggblas::complex::batched_cgemm_t(conj_a, conj_b, &a, &b_t, &mut c, m, n, k);
+        check_complex("batched_cgemm_t", &c, &expected, &magnitude, k)?;
+    }
+
+    #[test]
+    fn zgemm(p in problem(), conj_a in conj(), conj_b in conj()) {
+        let Problem { batch, m, n, k, seed } = p;
+        let a = complex(batch * m * k, seed);
+        let b = complex(batch * k * n, seed ^ 4);
+        let expected = complex_reference(conj_a, conj_b, &a, &b, false, batch, m, n, k);
+        let widen = |x: &[Complex32]| -> Vec<Complex64> {
+            x.iter().map(|x| Complex64::new(x.re.into(), x.im.into())).collect()
+        };
+        let mut c = vec![Complex64::new(f64::NAN, f64::NAN); batch * m * n];
+        ggblas::complex::batched_zgemm(conj_a, conj_b, &widen(&a), &widen(&b), &mut c, m, n, k);
+        for (i, (got, expected)) in c.iter().zip(&expected).enumerate() {
+            // The inputs are exact in double precision: the products are
+            // off by a few ULPs of the (small) magnitude, far below f32's.
+            prop_assert!(
+                (got - expected).norm() <= 1e-9,
+                "batched_zgemm[{}]: {} != {}", i, got, expected
+            );
+        }
+    }
+
+    #[test]
+    fn quantized_q8_0(p in problem()) {
+        quantized::<BlockQ8_0>(p)?;
+    }
+
+    #[test]
+    fn quantized_q4_0(p in problem()) {
+        quantized::<BlockQ4_0>(p)?;
+    }
+
+    #[test]
+    fn syrk(p in problem(), uplo in uplo()) {
+        let Problem { batch, n, k, seed, .. } = p;
+        let a = data(batch * n * k, seed);
+        let mut expected = vec![0.0; batch * n * n];
+        reference::batched_sgemm_t(&a, &a, &mut expected, n, n, k);
+        let mut magnitude = vec![0.0; batch * n * n];
+        reference::batched_sgemm_t(&abs(&a), &abs(&a), &mut magnitude, n, n, k);
+        // The other triangle is left untouched.
+        let untouched = data(batch * n * n, seed ^ 6);
+        for (idx, (e, u)) in expected.iter_mut().zip(&untouched).enumerate() {
+            let (i, j) = (idx / n % n, idx % n);
+            if !stored(uplo, i, j) {
+                *e = *u;
+            }
+        }
+
+        let mut c = untouched.clone();
+        ggblas::batched_ssyrk(uplo, &a, &mut c, n, k);
+        check("batched_ssyrk", &c, &expected, &magnitude, k, f32::EPSILON)?;
+        if batch == 1 {
+            let mut c = untouched;
+            ggblas::ssyrk(uplo, &a, &mut c, n, k);
+            check("ssyrk", &c, &expected, &magnitude, k, f32::EPSILON)?;
+        }
+    }
+
+    #[test]
+    fn symm(p in problem(), side in side(), uplo in uplo()) {
+        let Problem { batch, m, n, seed, .. } = p;
+        let dim = if side == Side::Left { m } else { n };
+        let a = triangle(&data(batch * dim * dim, seed), dim, uplo, Diag::NonUnit);
+        let b = data(batch * m * n, seed ^ 7);
+        let (expected, magnitude) =
+            side_product(side, &full(&a, dim, uplo, Diag::NonUnit, true), &b, m, n);
+
+        let mut c = vec![f32::NAN; batch * m * n];
+        ggblas::batched_ssymm(side, uplo, &a, &b, &mut c, m, n);
+        check("batched_ssymm", &c, &expected, &magnitude, dim, f32::EPSILON)?;
+        if batch == 1 {
+            let mut c = vec![f32::NAN; m * n];
+            ggblas::ssymm(side, uplo, &a, &b, &mut c, m, n);
+            check("ssymm", &c, &expected, &magnitude, dim, f32::EPSILON)?;
+        }
+    }
+
+    #[test]
+    fn trmm(p in problem(), side in side(), uplo in uplo(), diag in diag()) {
+        let Problem { batch, m, n, seed, .. } = p;
+        let dim = if side == Side::Left { m } else { n };
+        let a = triangle(&data(batch * dim * dim, seed), dim, uplo, diag);
+        let b = data(batch * m * n, seed ^ 7);
+        let (expected, magnitude) = side_product(side, &full(&a, dim, uplo, diag, false), &b, m, n);
+
+        let mut c = b.clone();
+        ggblas::batched_strmm(side, uplo, diag, &a, &mut c, m, n);
+        check("batched_strmm", &c, &expected, &magnitude, dim, f32::EPSILON)?;
+        if batch == 1 {
+            let mut c = b;
+            ggblas::strmm(side, uplo, diag, &a, &mut c, m, n);
+            check("strmm", &c, &expected, &magnitude, dim, f32::EPSILON)?;
+        }
+    }
+
+    #[test]
+    fn trsm(p in problem(), side in side(), uplo in uplo(), diag in diag()) {
+        let Problem { batch, m, n, seed, .. } = p;
+        let dim = if side == Side::Left { m } else { n };
+        // A dominant diagonal keeps the solutions, and so the residuals, in
+        // range.
+        let mut a = data(batch * dim * dim, seed);
+        for (idx, a) in a.iter_mut().enumerate() {
+            let (i, j) = (idx / dim % dim, idx % dim);
+            *a = if i == j { 4.0 + a.abs() } else { *a / dim as f32 };
+        }
+        let a = triangle(&a, dim, uplo, diag);
+        let a_full = full(&a, dim, uplo, diag, false);
+        let b = data(batch * m * n, seed ^ 7);
+
+        let mut x = b.clone();
+        ggblas::batched_strsm(side, uplo, diag, &a, &mut x, m, n);
+        // Backward stable: `op(A) * X` is `B` up to a rounding of each term
+        // of the solve and of the product checking it.
+        let (residual, magnitude) = side_product(side, &a_full, &x, m, n);
+        check("batched_strsm", &residual, &b, &magnitude, 2 * dim, f32::EPSILON)?;
+        if batch == 1 {
+            let mut y = b.clone();
+            ggblas::strsm(side, uplo, diag, &a, &mut y, m, n);
+            prop_assert_eq!(y, x);
+        }
+    }
+}
+
+#[cfg(feature = "f16")]
+#[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn f16(p in problem()) {
+        use half::f16;
+        let case = Case::new(p);
+        let Problem { batch, m, n, k, .. } = p;
+        // The reference runs on the rounded inputs.
+        let round = |x: &[f32]| -> Vec<f16> { x.iter().map(|&v| f16::from_f32(v)).collect() };
+        let a16 = round(&case.a);
+        let b16 = round(&case.b_t);
+        let widen = |x: &[f16]| -> Vec<f32> { x.iter().map(|v| v.to_f32()).collect() };
+        let mut expected = vec![0.0; batch * m * n];
+        reference::batched_sgemm_t(&widen(&a16), &widen(&b16), &mut expected, m, n, k);
+        let mut magnitude = vec![0.0; batch * m * n];
+        reference::batched_sgemm_t(&abs(&widen(&a16)), &abs(&widen(&b16)), &mut magnitude, m, n, k);
+
+        let mut c = vec![0.0; batch * m * n];
+        ggblas::f16::batched_sgemm_t_f16_mixed(&case.a, &b16, &mut c, m, n, k);
+        // `A` gets rounded to f16 on the way.
+        check("batched_sgemm_t_f16_mixed", &c, &expected, &magnitude, k, f16::EPSILON.to_f32())?;
+
+        let mut c = vec![f16::ZERO; batch * m * n];
+        ggblas::f16::batched_sgemm_t_f16_pure(&a16, &b16, &mut c, m, n, k);
+        check("batched_sgemm_t_f16_pure", &widen(&c), &expected, &magnitude, k, f16::EPSILON.to_f32())?;
+    }
+}
+
+#[cfg(feature = "ndarray")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn ndarray(p in problem()) {
+        use ndarray::{Array3, ArrayView3};
+        let case = Case::new(p);
+        let Problem { batch, m, n, k, .. } = p;
+        let a = ArrayView3::from_shape((batch, m, k), &case.a).unwrap();
+        let b = ArrayView3::from_shape((batch, k, n), &case.b).unwrap();
+        // `B` read in place from its `(n, k)` transposes.
+        let b_t = ArrayView3::from_shape((batch, n, k), &case.b_t).unwrap();
+        let b_t = b_t.permuted_axes([0, 2, 1]);
+        // Stepped views get copied.
+        let a_wide: Vec<f32> = case.a.iter().flat_map(|&x| [x, f32::NAN]).collect();
+        let a_wide = ArrayView3::from_shape((batch, m, 2 * k), &a_wide).unwrap();
+        let a_stepped = a_wide.slice(ndarray::s![.., .., ..;2]);
+
+        for (name, a, b) in [("contiguous", a, b), ("transposed", a, b_t), ("stepped", a_stepped, b)] {
+            let mut c = Array3::from_elem((batch, m, n), f32::NAN);
+            ggblas::ndarray::batched_sgemm(a, b, c.view_mut());
+            case.check(name, c.as_slice().unwrap())?;
+
+            // A column major output.
+            let mut c = Array3::from_elem((batch, n, m), f32::NAN);
+            ggblas::ndarray::batched_sgemm(a, b, c.view_mut().permuted_axes([0, 2, 1]));
+            let c: Vec<f32> = c.permuted_axes([0, 2, 1]).iter().copied().collect();
+            case.check(name, &c)?;
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn nalgebra(p in problem()) {
+        use nalgebra::{DMatrix, DMatrixView};
+        let case = Case::new(Problem { batch: 1, ..p });
+        let Problem { m, n, k, .. } = p;
+        // Row major views, read in place, and their column major copies.
+        let a = DMatrixView::from_slice_with_strides(&case.a, m, k, k, 1);
+        let b = DMatrixView::from_slice_with_strides(&case.b_t, k, n, 1, k);
+        let to_row = |c: &DMatrix<f32>| -> Vec<f32> {
+            (0..m).flat_map(|i| (0..n).map(move |j| c[(i, j)])).collect()
+        };
+
+        case.check("matmul", &to_row(&ggblas::nalgebra::matmul(&a, &b)))?;
+        let mut c = DMatrix::from_element(m, n, f32::NAN);
+        ggblas::nalgebra::sgemm(&a.clone_owned(), &b.clone_owned(), &mut c);
+        case.check("sgemm", &to_row(&c))?;
+    }
+}
+
+#[cfg(feature = "faer")]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn faer(p in problem()) {
+        use faer::{Mat, MatRef};
+        let case = Case::new(Problem { batch: 1, ..p });
+        let Problem { m, n, k, .. } = p;
+        let a = MatRef::from_row_major_slice(&case.a, m, k);
+        let b = MatRef::from_column_major_slice(&case.b_t, k, n);
+        let to_row = |c: &Mat<f32>| -> Vec<f32> {
+            (0..m).flat_map(|i| (0..n).map(move |j| c[(i, j)])).collect()
+        };
+
+        case.check("matmul", &to_row(&ggblas::faer::matmul(a, b)))?;
+        let mut c = Mat::from_fn(m, n, |_, _| f32::NAN);
+        ggblas::faer::sgemm(a.to_owned().as_ref(), b.to_owned().as_ref(), c.as_mut());
+        case.check("sgemm", &to_row(&c))?;
+    }
+}